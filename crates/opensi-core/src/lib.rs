@@ -10,7 +10,16 @@ pub mod prelude {
     pub use crate::node::*;
     pub use crate::package_trait::*;
     pub use crate::v4::{
-        AtomKindv4 as AtomKind, Atomv4 as Atom, Infov4 as Info, Packagev4 as Package,
-        Questionv4 as Question, ResourceIdv4 as ResourceId, Roundv4 as Round, Themev4 as Theme,
+        AtomKindv4 as AtomKind, Atomv4 as Atom, BudgetReportv4 as BudgetReport, Codecv4 as Codec,
+        ColumnMappingv4 as ColumnMapping, FormatMismatchv4 as FormatMismatch,
+        Fragmentv4 as Fragment, ImageOptimizationv4 as ImageOptimization, Infov4 as Info,
+        JsonResourcesv4 as JsonResources, MediaBudgetv4 as MediaBudget,
+        MediaFormatv4 as MediaFormat, MediaProbev4 as MediaProbe,
+        OptimizationReportv4 as OptimizationReport, PackageDiffv4 as PackageDiff,
+        PackageTemplatev4 as PackageTemplate, Packagev4 as Package, PriceLadderv4 as PriceLadder,
+        Questionv4 as Question, ResourceIdv4 as ResourceId, ResourceMetav4 as ResourceMeta,
+        ResourceUsagev4 as ResourceUsage, Roundv4 as Round,
+        SpreadsheetColumnv4 as SpreadsheetColumn, SpreadsheetReportv4 as SpreadsheetReport,
+        Spreadsheetv4 as Spreadsheet, Themev4 as Theme, Waveformv4 as Waveform,
     };
 }
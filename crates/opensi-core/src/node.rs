@@ -1,7 +1,31 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use derive_more::{AsRef, Deref, From};
 
+/// Stable identity of a [`Package`](crate::package::Package) tree node.
+///
+/// Unlike [`PackageNode`], it follows the node when the tree is reordered.
+/// Ids are not serialized and are generated anew when package is loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u64);
+
+impl NodeId {
+    /// Generate a new unique id.
+    pub fn new() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for NodeId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// [`Package`](crate::package::Package) tree node which
 /// operates on indices and is easy to copy.
 #[derive(
@@ -230,4 +254,10 @@ mod tests {
         assert_eq!(question.parent(), ThemeIdx { round_index: 1, index: 2 });
         assert_eq!(question.parent().parent(), RoundIdx { index: 1 });
     }
+
+    #[test]
+    fn unique_ids() {
+        let (first, second) = (NodeId::new(), NodeId::default());
+        assert_ne!(first, second);
+    }
 }
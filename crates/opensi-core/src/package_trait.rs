@@ -1,9 +1,35 @@
-use crate::node::{PackageNode, QuestionIdx, RoundIdx, ThemeIdx};
+use std::collections::HashMap;
+
+use crate::node::{NodeId, PackageNode, QuestionIdx, RoundIdx, ThemeIdx};
 
 pub trait PackageBase: RoundContainer + Default + Clone {}
-pub trait RoundBase: ThemesContainer + Default + Clone {}
-pub trait ThemeBase: QuestionsContainer + Default + Clone {}
+pub trait RoundBase: ThemesContainer + Default + Clone {
+    fn id(&self) -> NodeId;
+    fn set_id(&mut self, id: NodeId);
+
+    /// Generate new ids for the round and all of its themes and questions.
+    fn renew_ids(&mut self) {
+        self.set_id(NodeId::new());
+        for theme in self.get_themes_mut(0).into_iter().flatten() {
+            theme.renew_ids();
+        }
+    }
+}
+pub trait ThemeBase: QuestionsContainer + Default + Clone {
+    fn id(&self) -> NodeId;
+    fn set_id(&mut self, id: NodeId);
+
+    /// Generate new ids for the theme and all of its questions.
+    fn renew_ids(&mut self) {
+        self.set_id(NodeId::new());
+        for question in self.get_questions_mut((0, 0)).into_iter().flatten() {
+            question.set_id(NodeId::new());
+        }
+    }
+}
 pub trait QuestionBase: Default + Clone {
+    fn id(&self) -> NodeId;
+    fn set_id(&mut self, id: NodeId);
     fn get_price(&self) -> usize;
     fn set_price(&mut self, price: usize);
 }
@@ -74,7 +100,9 @@ pub trait RoundContainer {
     /// a reference to the new round.
     fn duplicate_round(&mut self, idx: impl Into<RoundIdx>) -> Option<&mut Self::Round> {
         let idx = idx.into();
-        self.get_round(idx).cloned().and_then(|round| self.insert_round(idx.next(), round))
+        let mut round = self.get_round(idx).cloned()?;
+        round.renew_ids();
+        self.insert_round(idx.next(), round)
     }
 
     /// Create a new default [`Round`], push it and return
@@ -158,7 +186,9 @@ pub trait ThemesContainer {
     /// a reference to the new theme.
     fn duplicate_theme(&mut self, idx: impl Into<ThemeIdx>) -> Option<&mut Self::Theme> {
         let idx = idx.into();
-        self.get_theme(idx).cloned().and_then(|theme| self.insert_theme(idx.next(), theme))
+        let mut theme = self.get_theme(idx).cloned()?;
+        theme.renew_ids();
+        self.insert_theme(idx.next(), theme)
     }
 
     /// Create a new default [`Theme`], push it to the [`Round`]
@@ -167,6 +197,55 @@ pub trait ThemesContainer {
         let idx = idx.into();
         self.push_theme(idx, Self::Theme::default())
     }
+
+    /// Set prices of all [`Question`]s in every [`Theme`] of the [`Round`]
+    /// to a ladder of `start`, `start + step`, `start + 2 * step`, etc.
+    fn reprice_round(&mut self, idx: impl Into<RoundIdx>, start: usize, step: usize) -> bool {
+        let Some(themes) = self.get_themes_mut(idx) else {
+            return false;
+        };
+        for theme in themes {
+            theme.reprice_theme((0, 0), start, step);
+        }
+        true
+    }
+
+    /// Multiply prices of all [`Question`]s in the [`Round`] by `factor`,
+    /// rounding them to the nearest integer.
+    fn multiply_round_prices(&mut self, idx: impl Into<RoundIdx>, factor: f64) -> bool {
+        let Some(themes) = self.get_themes_mut(idx) else {
+            return false;
+        };
+        for theme in themes {
+            for question in theme.get_questions_mut((0, 0)).into_iter().flatten() {
+                let price = (question.get_price() as f64 * factor).round().max(0.0);
+                question.set_price(price as usize);
+            }
+        }
+        true
+    }
+
+    /// Make every [`Theme`] of the [`Round`] use the same price ladder, taken
+    /// from the theme with the most questions.
+    fn normalize_round_prices(&mut self, idx: impl Into<RoundIdx>) -> bool {
+        let Some(themes) = self.get_themes_mut(idx) else {
+            return false;
+        };
+        let ladder = themes
+            .iter()
+            .filter_map(|theme| theme.get_questions((0, 0)))
+            .max_by_key(|questions| questions.len())
+            .map(|questions| questions.iter().map(|question| question.get_price()).collect())
+            .unwrap_or_else(Vec::new);
+        for theme in themes {
+            for (question, &price) in
+                theme.get_questions_mut((0, 0)).into_iter().flatten().zip(&ladder)
+            {
+                question.set_price(price);
+            }
+        }
+        true
+    }
 }
 
 impl<R, C> ThemesContainer for C
@@ -214,6 +293,18 @@ pub trait QuestionsContainer {
         }
     }
 
+    /// Set prices of all [`Question`]s in the [`Theme`] to a ladder
+    /// of `start`, `start + step`, `start + 2 * step`, etc.
+    fn reprice_theme(&mut self, idx: impl Into<ThemeIdx>, start: usize, step: usize) -> bool {
+        let Some(questions) = self.get_questions_mut(idx) else {
+            return false;
+        };
+        for (index, question) in questions.iter_mut().enumerate() {
+            question.set_price(start.saturating_add(index.saturating_mul(step)));
+        }
+        true
+    }
+
     /// Return amount of [`Question`]s in a [`Theme`].
     fn count_questions(&self, idx: impl Into<ThemeIdx>) -> usize {
         self.get_questions(idx).map(|questions| questions.len()).unwrap_or_default()
@@ -280,9 +371,9 @@ pub trait QuestionsContainer {
     /// a reference to the new question.
     fn duplicate_question(&mut self, idx: impl Into<QuestionIdx>) -> Option<&mut Self::Question> {
         let idx = idx.into();
-        self.get_question(idx)
-            .cloned()
-            .and_then(|question| self.insert_question(idx.next(), question))
+        let mut question = self.get_question(idx).cloned()?;
+        question.set_id(NodeId::new());
+        self.insert_question(idx.next(), question)
     }
 
     /// Create a new default [`Question`], push it to the [`Theme`] in [`Round`]
@@ -319,6 +410,33 @@ pub trait NodeContainer {
     fn duplicate_node(&mut self, node: PackageNode);
     fn allocate_node(&mut self, node: PackageNode);
     fn remove_node(&mut self, node: PackageNode);
+
+    /// Move node to a new position, possibly into another parent. `to` is
+    /// the position the node will have after the move and must be of the same
+    /// kind. Returns the new position of the node, or `None` if either of the
+    /// positions is invalid.
+    fn move_node(&mut self, from: PackageNode, to: PackageNode) -> Option<PackageNode>;
+    /// Swap two nodes of the same kind, possibly from different parents.
+    /// Returns `false` if nodes can't be swapped.
+    fn swap_nodes(&mut self, a: PackageNode, b: PackageNode) -> bool;
+
+    /// Remove several nodes at once, possibly of different kinds.
+    fn remove_nodes(&mut self, nodes: &[PackageNode]);
+    /// Duplicate several nodes at once, placing each copy right after
+    /// its original. Returns positions of the copies.
+    fn duplicate_nodes(&mut self, nodes: &[PackageNode]) -> Vec<PackageNode>;
+    /// Move nodes of the same kind as `before` next to each other, in
+    /// front of the node which is at `before` position prior to the move.
+    /// `before` may point right past the last child of its parent. Nodes of
+    /// other kinds are ignored. Returns new positions of the moved nodes.
+    fn move_nodes(&mut self, nodes: &[PackageNode], before: PackageNode) -> Vec<PackageNode>;
+
+    /// Get a stable [`NodeId`] of the node by its position.
+    fn node_id(&self, node: PackageNode) -> Option<NodeId>;
+    /// Find current position of the node with a stable [`NodeId`].
+    fn find_node(&self, id: NodeId) -> Option<PackageNode>;
+    /// Map stable ids of every node to their current positions.
+    fn node_positions(&self) -> HashMap<NodeId, PackageNode>;
 }
 
 impl<T> NodeContainer for T
@@ -366,4 +484,474 @@ where
             },
         };
     }
+
+    fn move_node(&mut self, from: PackageNode, to: PackageNode) -> Option<PackageNode> {
+        match (from, to) {
+            (PackageNode::Round(from), PackageNode::Round(to)) => {
+                if !self.contains_round(from) || *to >= self.count_rounds() {
+                    return None;
+                }
+                let round = self.remove_round(from)?;
+                self.insert_round(to, round)?;
+            },
+            (PackageNode::Theme(from), PackageNode::Theme(to)) => {
+                if !self.contains_theme(from) || !self.contains_round(to.parent()) {
+                    return None;
+                }
+                let same_parent = from.parent() == to.parent();
+                if *to > self.count_themes(to.parent()) - usize::from(same_parent) {
+                    return None;
+                }
+                let theme = self.remove_theme(from)?;
+                self.insert_theme(to, theme)?;
+            },
+            (PackageNode::Question(from), PackageNode::Question(to)) => {
+                if !self.contains_question(from) || !self.contains_theme(to.parent()) {
+                    return None;
+                }
+                let same_parent = from.parent() == to.parent();
+                if *to > self.count_questions(to.parent()) - usize::from(same_parent) {
+                    return None;
+                }
+                let question = self.remove_question(from)?;
+                self.insert_question(to, question)?;
+            },
+            _ => return None,
+        }
+        Some(to)
+    }
+
+    fn swap_nodes(&mut self, a: PackageNode, b: PackageNode) -> bool {
+        match (a, b) {
+            (PackageNode::Round(a), PackageNode::Round(b)) => {
+                if !self.contains_round(a) || !self.contains_round(b) {
+                    return false;
+                }
+                self.get_rounds_mut().swap(*a, *b);
+            },
+            (PackageNode::Theme(a), PackageNode::Theme(b)) => {
+                if !self.contains_theme(a) || !self.contains_theme(b) {
+                    return false;
+                }
+                // taking the node out would lose it when swapped with itself
+                if a == b {
+                    return true;
+                }
+                let first = std::mem::take(self.get_theme_mut(a).unwrap());
+                let second = std::mem::replace(self.get_theme_mut(b).unwrap(), first);
+                *self.get_theme_mut(a).unwrap() = second;
+            },
+            (PackageNode::Question(a), PackageNode::Question(b)) => {
+                if !self.contains_question(a) || !self.contains_question(b) {
+                    return false;
+                }
+                // taking the node out would lose it when swapped with itself
+                if a == b {
+                    return true;
+                }
+                let first = std::mem::take(self.get_question_mut(a).unwrap());
+                let second = std::mem::replace(self.get_question_mut(b).unwrap(), first);
+                *self.get_question_mut(a).unwrap() = second;
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn remove_nodes(&mut self, nodes: &[PackageNode]) {
+        let mut nodes = nodes.to_vec();
+        nodes.sort();
+        nodes.dedup();
+
+        // children and later siblings go first, so that
+        // positions of the rest of nodes stay valid
+        for &node in nodes.iter().rev() {
+            self.remove_node(node);
+        }
+    }
+
+    fn duplicate_nodes(&mut self, nodes: &[PackageNode]) -> Vec<PackageNode> {
+        let ids = nodes.iter().filter_map(|&node| self.node_id(node)).collect::<Vec<_>>();
+        let mut copies = vec![];
+        for id in ids {
+            let Some(node) = self.find_node(id) else {
+                continue;
+            };
+            self.duplicate_node(node);
+            let copy = match node {
+                PackageNode::Round(idx) => idx.next().into(),
+                PackageNode::Theme(idx) => idx.next().into(),
+                PackageNode::Question(idx) => idx.next().into(),
+            };
+            copies.extend(self.node_id(copy));
+        }
+        copies.into_iter().filter_map(|id| self.find_node(id)).collect()
+    }
+
+    fn move_nodes(&mut self, nodes: &[PackageNode], before: PackageNode) -> Vec<PackageNode> {
+        let mut nodes = nodes
+            .iter()
+            .copied()
+            .filter(|node| std::mem::discriminant(node) == std::mem::discriminant(&before))
+            .filter(|&node| self.node_id(node).is_some())
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+
+        // index of `before` once the moved nodes are taken out
+        let shift = nodes
+            .iter()
+            .filter(|node| node.parent() == before.parent() && node.index() < before.index())
+            .count();
+        let index = before.index() - shift;
+        let siblings = match before {
+            PackageNode::Round(_) => Some(self.count_rounds()),
+            PackageNode::Theme(idx) => {
+                self.contains_round(idx.parent()).then(|| self.count_themes(idx.parent()))
+            },
+            PackageNode::Question(idx) => {
+                self.contains_theme(idx.parent()).then(|| self.count_questions(idx.parent()))
+            },
+        };
+        let Some(siblings) = siblings else {
+            return vec![];
+        };
+        let moved_siblings = nodes.iter().filter(|node| node.parent() == before.parent()).count();
+        if nodes.is_empty() || before.index() > siblings || index > siblings - moved_siblings {
+            return vec![];
+        }
+
+        match before {
+            PackageNode::Round(_) => {
+                let mut rounds = nodes
+                    .iter()
+                    .rev()
+                    .filter_map(|&node| match node {
+                        PackageNode::Round(idx) => self.remove_round(idx),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                rounds.reverse();
+                for (offset, round) in rounds.into_iter().enumerate() {
+                    self.insert_round(index + offset, round);
+                }
+            },
+            PackageNode::Theme(to) => {
+                let mut themes = nodes
+                    .iter()
+                    .rev()
+                    .filter_map(|&node| match node {
+                        PackageNode::Theme(idx) => self.remove_theme(idx),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                themes.reverse();
+                for (offset, theme) in themes.into_iter().enumerate() {
+                    self.insert_theme(to.parent().theme(index + offset), theme);
+                }
+            },
+            PackageNode::Question(to) => {
+                let mut questions = nodes
+                    .iter()
+                    .rev()
+                    .filter_map(|&node| match node {
+                        PackageNode::Question(idx) => self.remove_question(idx),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                questions.reverse();
+                for (offset, question) in questions.into_iter().enumerate() {
+                    self.insert_question(to.parent().question(index + offset), question);
+                }
+            },
+        }
+
+        (index..index + nodes.len())
+            .map(|index| match before {
+                PackageNode::Round(_) => RoundIdx::from(index).into(),
+                PackageNode::Theme(to) => to.parent().theme(index).into(),
+                PackageNode::Question(to) => to.parent().question(index).into(),
+            })
+            .collect()
+    }
+
+    fn node_id(&self, node: PackageNode) -> Option<NodeId> {
+        match node {
+            PackageNode::Round(idx) => self.get_round(idx).map(RoundBase::id),
+            PackageNode::Theme(idx) => self.get_theme(idx).map(ThemeBase::id),
+            PackageNode::Question(idx) => self.get_question(idx).map(QuestionBase::id),
+        }
+    }
+
+    fn find_node(&self, id: NodeId) -> Option<PackageNode> {
+        for (round_index, round) in self.get_rounds().iter().enumerate() {
+            let round_idx = RoundIdx::from(round_index);
+            if round.id() == id {
+                return Some(round_idx.into());
+            }
+            for (theme_index, theme) in
+                round.get_themes(round_idx).into_iter().flatten().enumerate()
+            {
+                let theme_idx = round_idx.theme(theme_index);
+                if theme.id() == id {
+                    return Some(theme_idx.into());
+                }
+                let mut questions = theme.get_questions(theme_idx).into_iter().flatten();
+                if let Some(index) = questions.position(|question| question.id() == id) {
+                    return Some(theme_idx.question(index).into());
+                }
+            }
+        }
+        None
+    }
+
+    fn node_positions(&self) -> HashMap<NodeId, PackageNode> {
+        let mut positions = HashMap::new();
+        for (round_index, round) in self.get_rounds().iter().enumerate() {
+            let round_idx = RoundIdx::from(round_index);
+            positions.insert(round.id(), round_idx.into());
+            for (theme_index, theme) in
+                round.get_themes(round_idx).into_iter().flatten().enumerate()
+            {
+                let theme_idx = round_idx.theme(theme_index);
+                positions.insert(theme.id(), theme_idx.into());
+                for (index, question) in
+                    theme.get_questions(theme_idx).into_iter().flatten().enumerate()
+                {
+                    positions.insert(question.id(), theme_idx.question(index).into());
+                }
+            }
+        }
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v4::{Packagev4, Themev4};
+
+    #[test]
+    fn ids_follow_nodes() {
+        let mut package = Packagev4::new();
+        package.allocate_round().themes.push(Themev4::default());
+        package.allocate_round();
+
+        let question = PackageNode::from((0, 0, 3));
+        let id = package.node_id(question).unwrap();
+        assert_eq!(package.find_node(id), Some(question));
+
+        package.remove_question((0, 0, 1));
+        assert_eq!(package.find_node(id), Some((0, 0, 2).into()));
+
+        let round_id = package.node_id(1.into()).unwrap();
+        package.remove_round(0);
+        assert_eq!(package.find_node(id), None);
+        assert_eq!(package.find_node(round_id), Some(0.into()));
+        assert_eq!(package.node_positions().len(), 1);
+    }
+
+    /// Package with two rounds, with two themes of five questions each.
+    fn test_package() -> Packagev4 {
+        let mut package = Packagev4::new();
+        for _ in 0..2 {
+            let round = package.allocate_round();
+            round.themes.push(Themev4 { name: "first".into(), ..Themev4::default() });
+            round.themes.push(Themev4 { name: "second".into(), ..Themev4::default() });
+        }
+        package
+    }
+
+    #[test]
+    fn move_within_parent() {
+        let mut package = test_package();
+        let first = package.node_id((0, 0, 0).into()).unwrap();
+        let last = package.node_id((0, 0, 4).into()).unwrap();
+
+        assert_eq!(package.move_node((0, 0, 0).into(), (0, 0, 4).into()), Some((0, 0, 4).into()));
+        assert_eq!(package.find_node(first), Some((0, 0, 4).into()));
+        assert_eq!(package.find_node(last), Some((0, 0, 3).into()));
+        assert_eq!(package.get_question((0, 0, 4)).unwrap().price, 100);
+
+        assert_eq!(package.move_node((0, 0, 4).into(), (0, 0, 0).into()), Some((0, 0, 0).into()));
+        assert_eq!(package.find_node(first), Some((0, 0, 0).into()));
+
+        // past the end of the same parent
+        assert_eq!(package.move_node((0, 0, 0).into(), (0, 0, 5).into()), None);
+        assert_eq!(package.find_node(first), Some((0, 0, 0).into()));
+    }
+
+    #[test]
+    fn move_across_parents() {
+        let mut package = test_package();
+        let question = package.node_id((0, 0, 2).into()).unwrap();
+
+        // to the very end of another theme
+        assert_eq!(package.move_node((0, 0, 2).into(), (1, 1, 5).into()), Some((1, 1, 5).into()));
+        assert_eq!(package.find_node(question), Some((1, 1, 5).into()));
+        assert_eq!(package.count_questions((0, 0)), 4);
+        assert_eq!(package.count_questions((1, 1)), 6);
+
+        // to the beginning of another round
+        let theme = package.node_id((0, 1).into()).unwrap();
+        assert_eq!(package.move_node((0, 1).into(), (1, 0).into()), Some((1, 0).into()));
+        assert_eq!(package.find_node(theme), Some((1, 0).into()));
+        assert_eq!(package.get_theme((1, 0)).unwrap().name, "second");
+        assert_eq!(package.count_themes(0), 1);
+        assert_eq!(package.count_themes(1), 3);
+
+        let round = package.node_id(0.into()).unwrap();
+        assert_eq!(package.move_node(0.into(), 1.into()), Some(1.into()));
+        assert_eq!(package.find_node(round), Some(1.into()));
+    }
+
+    #[test]
+    fn invalid_moves() {
+        let mut package = test_package();
+        let original = package.clone();
+
+        assert_eq!(package.move_node((0, 0).into(), (0, 0, 0).into()), None);
+        assert_eq!(package.move_node((0, 0, 5).into(), (0, 1, 0).into()), None);
+        assert_eq!(package.move_node((0, 0, 0).into(), (0, 1, 6).into()), None);
+        assert_eq!(package.move_node((0, 0, 0).into(), (0, 2, 0).into()), None);
+        assert_eq!(package.move_node((0, 0).into(), (2, 0).into()), None);
+        assert_eq!(package.move_node(0.into(), 2.into()), None);
+        assert_eq!(package, original);
+    }
+
+    #[test]
+    fn swap() {
+        let mut package = test_package();
+        package.get_question_mut((1, 1, 4)).unwrap().price = 1000;
+        let first = package.node_id((0, 0, 0).into()).unwrap();
+        let second = package.node_id((1, 1, 4).into()).unwrap();
+
+        assert!(package.swap_nodes((0, 0, 0).into(), (1, 1, 4).into()));
+        assert_eq!(package.find_node(first), Some((1, 1, 4).into()));
+        assert_eq!(package.find_node(second), Some((0, 0, 0).into()));
+        assert_eq!(package.get_question((0, 0, 0)).unwrap().price, 1000);
+
+        assert!(package.swap_nodes((0, 0).into(), (0, 1).into()));
+        assert_eq!(package.get_theme((0, 0)).unwrap().name, "second");
+
+        assert!(!package.swap_nodes((0, 0).into(), (0, 0, 1).into()));
+        assert!(!package.swap_nodes(0.into(), 2.into()));
+    }
+
+    #[test]
+    fn swap_with_itself() {
+        let mut package = test_package();
+        package.get_question_mut((0, 0, 1)).unwrap().price = 1000;
+        let before = package.clone();
+
+        assert!(package.swap_nodes((0, 0).into(), (0, 0).into()));
+        assert!(package.swap_nodes((0, 0, 1).into(), (0, 0, 1).into()));
+        assert!(package.swap_nodes(1.into(), 1.into()));
+        assert_eq!(package, before);
+    }
+
+    #[test]
+    fn bulk_remove() {
+        let mut package = test_package();
+        let kept = package.node_id((1, 0, 4).into()).unwrap();
+
+        package.remove_nodes(&[
+            (1, 0, 0).into(),
+            (1, 1).into(),
+            (1, 1, 2).into(),
+            (1, 0, 3).into(),
+            0.into(),
+        ]);
+        assert_eq!(package.count_rounds(), 1);
+        assert_eq!(package.count_themes(0), 1);
+        assert_eq!(package.count_questions((0, 0)), 3);
+        assert_eq!(package.find_node(kept), Some((0, 0, 2).into()));
+    }
+
+    #[test]
+    fn bulk_duplicate() {
+        let mut package = test_package();
+        let copies = package.duplicate_nodes(&[(0, 0, 0).into(), (0, 0, 2).into(), (1, 1).into()]);
+        assert_eq!(copies, vec![(0, 0, 1).into(), (0, 0, 4).into(), (1, 2).into()]);
+        assert_eq!(package.count_questions((0, 0)), 7);
+        assert_eq!(package.get_question((0, 0, 4)).unwrap().price, 300);
+        assert_eq!(package.get_theme((1, 2)).unwrap().name, "second");
+    }
+
+    #[test]
+    fn bulk_move() {
+        let mut package = test_package();
+        let first = package.node_id((0, 0, 0).into()).unwrap();
+        let other = package.node_id((1, 1, 3).into()).unwrap();
+
+        // in front of a later sibling and from another round
+        let moved = package
+            .move_nodes(&[(0, 0, 0).into(), (1, 1, 3).into(), (0, 0).into()], (0, 0, 3).into());
+        assert_eq!(moved, vec![(0, 0, 2).into(), (0, 0, 3).into()]);
+        assert_eq!(package.find_node(first), Some((0, 0, 2).into()));
+        assert_eq!(package.find_node(other), Some((0, 0, 3).into()));
+        assert_eq!(package.count_questions((0, 0)), 6);
+        assert_eq!(package.count_questions((1, 1)), 4);
+
+        // to the end of a parent
+        let moved = package.move_nodes(&[(0, 0).into(), (0, 1).into()], (1, 2).into());
+        assert_eq!(moved, vec![(1, 2).into(), (1, 3).into()]);
+        assert_eq!(package.count_themes(0), 0);
+        assert_eq!(package.find_node(first), Some((1, 2, 2).into()));
+
+        let original = package.clone();
+        assert!(package.move_nodes(&[(1, 0).into()], (1, 5).into()).is_empty());
+        assert!(package.move_nodes(&[(1, 0).into()], (3, 0).into()).is_empty());
+        assert!(package.move_nodes(&[(1, 0, 0).into()], (1, 0).into()).is_empty());
+        assert_eq!(package, original);
+    }
+
+    #[test]
+    fn repricing() {
+        let mut package = test_package();
+        let prices = |package: &Packagev4, idx: (usize, usize)| {
+            package
+                .get_questions(idx)
+                .unwrap()
+                .iter()
+                .map(|question| question.price)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(package.reprice_theme((0, 1), 10, 20));
+        assert_eq!(prices(&package, (0, 1)), [10, 30, 50, 70, 90]);
+        assert_eq!(prices(&package, (0, 0)), [100, 200, 300, 400, 500]);
+
+        assert!(package.reprice_round(1, 200, 200));
+        assert_eq!(prices(&package, (1, 0)), [200, 400, 600, 800, 1000]);
+        assert_eq!(prices(&package, (1, 1)), [200, 400, 600, 800, 1000]);
+
+        assert!(package.multiply_round_prices(0, 1.5));
+        assert_eq!(prices(&package, (0, 0)), [150, 300, 450, 600, 750]);
+        assert_eq!(prices(&package, (0, 1)), [15, 45, 75, 105, 135]);
+
+        package.remove_question((0, 0, 4));
+        package.get_question_mut((0, 1, 0)).unwrap().price = 1;
+        assert!(package.normalize_round_prices(0));
+        assert_eq!(prices(&package, (0, 0)), [1, 45, 75, 105]);
+        assert_eq!(prices(&package, (0, 1)), [1, 45, 75, 105, 135]);
+
+        assert!(package.reprice_theme((0, 1), usize::MAX / 2, usize::MAX / 2));
+        assert_eq!(prices(&package, (0, 1))[4], usize::MAX);
+
+        assert!(!package.reprice_theme((0, 2), 100, 100));
+        assert!(!package.reprice_round(2, 100, 100));
+    }
+
+    #[test]
+    fn duplicates_get_new_ids() {
+        let mut package = Packagev4::new();
+        package.allocate_round().themes.push(Themev4::default());
+        package.duplicate_node((0, 0).into());
+
+        let original = package.node_id((0, 0, 0).into()).unwrap();
+        let duplicate = package.node_id((0, 1, 0).into()).unwrap();
+        assert_ne!(original, duplicate);
+        assert_eq!(package.get_theme((0, 0)), package.get_theme((0, 1)));
+    }
 }
@@ -41,3 +41,4 @@ generate_serde_mod!(answers: String as answer);
 generate_serde_mod!(authors: String as author);
 generate_serde_mod!(sources: String as source);
 generate_serde_mod!(tags: String as tag);
+generate_serde_mod!(fragment_resources: crate::v4::fragment::ResourceXml as resource);
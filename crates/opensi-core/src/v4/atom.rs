@@ -115,6 +115,23 @@ impl ResourceIdv4 {
         }
     }
 
+    /// Get the same resource with a numeric suffix added to its name,
+    /// e.g. "Images/@joker_1.png".
+    pub fn with_suffix(&self, suffix: usize) -> Option<Self> {
+        let (category, name) = self.path().split_once('/')?;
+        let name = match name.rsplit_once('.') {
+            Some((stem, extension)) => format!("{stem}_{suffix}.{extension}"),
+            None => format!("{name}_{suffix}"),
+        };
+        Self::try_new(format!("{category}/{name}"))
+    }
+
+    /// Body of an atom which links to the resource, e.g. "@joker.png"
+    /// for "Images/@joker.png".
+    pub fn atom_body(&self) -> String {
+        percent_encoding::percent_decode_str(self.name()).decode_utf8_lossy().to_string()
+    }
+
     /// Get only the name part of the resource, e.g. "@joker.png".
     pub fn name(&self) -> &str {
         match self {
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    node::{RoundIdx, ThemeIdx},
+    node::{NodeId, RoundIdx, ThemeIdx},
     package_trait::{QuestionBase, QuestionsContainer, RoundBase, ThemeBase, ThemesContainer},
     serde_impl,
 };
@@ -21,9 +21,11 @@ pub struct Infov4 {
     pub sources: Vec<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Roundv4 {
+    #[serde(skip)]
+    pub id: NodeId,
     #[serde(rename = "@name")]
     pub name: String,
     // TODO: Actual enum of kinds
@@ -37,11 +39,34 @@ pub struct Roundv4 {
 
 impl Default for Roundv4 {
     fn default() -> Self {
-        Self { name: "Новый раунд".to_string(), kind: None, info: None, themes: vec![] }
+        Self {
+            id: NodeId::new(),
+            name: "Новый раунд".to_string(),
+            kind: None,
+            info: None,
+            themes: vec![],
+        }
     }
 }
 
-impl RoundBase for Roundv4 {}
+impl PartialEq for Roundv4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.kind == other.kind
+            && self.info == other.info
+            && self.themes == other.themes
+    }
+}
+
+impl RoundBase for Roundv4 {
+    fn id(&self) -> NodeId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: NodeId) {
+        self.id = id;
+    }
+}
 impl ThemesContainer for Roundv4 {
     type Theme = Themev4;
 
@@ -54,8 +79,10 @@ impl ThemesContainer for Roundv4 {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Themev4 {
+    #[serde(skip)]
+    pub id: NodeId,
     #[serde(rename = "@name")]
     pub name: String,
     #[serde(with = "serde_impl::questions")]
@@ -64,7 +91,21 @@ pub struct Themev4 {
     pub info: Option<Infov4>,
 }
 
-impl ThemeBase for Themev4 {}
+impl PartialEq for Themev4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.questions == other.questions && self.info == other.info
+    }
+}
+
+impl ThemeBase for Themev4 {
+    fn id(&self) -> NodeId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: NodeId) {
+        self.id = id;
+    }
+}
 impl QuestionsContainer for Themev4 {
     type Question = Questionv4;
 
@@ -80,6 +121,7 @@ impl QuestionsContainer for Themev4 {
 impl Default for Themev4 {
     fn default() -> Self {
         Self {
+            id: NodeId::new(),
             name: "Новая тема".to_string(),
             questions: vec![
                 Questionv4 { price: 100, ..Questionv4::default() },
@@ -93,9 +135,11 @@ impl Default for Themev4 {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Questionv4 {
+    #[serde(skip)]
+    pub id: NodeId,
     #[serde(rename = "@price")]
     pub price: usize,
     #[serde(rename = "type")]
@@ -113,6 +157,7 @@ pub struct Questionv4 {
 impl Default for Questionv4 {
     fn default() -> Self {
         Self {
+            id: NodeId::new(),
             price: 100,
             question_type: QuestionTypev4::default(),
             scenario: vec![],
@@ -123,7 +168,26 @@ impl Default for Questionv4 {
     }
 }
 
+impl PartialEq for Questionv4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.price == other.price
+            && self.question_type == other.question_type
+            && self.scenario == other.scenario
+            && self.right == other.right
+            && self.wrong == other.wrong
+            && self.info == other.info
+    }
+}
+
 impl QuestionBase for Questionv4 {
+    fn id(&self) -> NodeId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: NodeId) {
+        self.id = id;
+    }
+
     fn get_price(&self) -> usize {
         self.price
     }
@@ -141,6 +205,15 @@ pub struct QuestionTypev4 {
     pub params: Option<Vec<Paramv4>>,
 }
 
+impl QuestionTypev4 {
+    /// Names of question types known to SIGame.
+    pub const NAMES: &'static [&'static str] = &["simple", "auction", "cat", "bagcat", "sponsored"];
+
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), params: None }
+    }
+}
+
 impl std::fmt::Display for QuestionTypev4 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let display = match self.name.as_str() {
@@ -0,0 +1,107 @@
+use super::Packagev4;
+
+/// Amount of items in two versions of a package.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CountDiffv4 {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl CountDiffv4 {
+    pub fn is_changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// Summary of changes between two versions of a [`Packagev4`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackageDiffv4 {
+    pub rounds: CountDiffv4,
+    pub themes: CountDiffv4,
+    pub questions: CountDiffv4,
+    pub resources: CountDiffv4,
+    /// Questions which differ at the same position in both versions.
+    pub changed_questions: usize,
+    /// Name, info or any other meta information of the package.
+    pub metadata_changed: bool,
+}
+
+impl PackageDiffv4 {
+    pub fn is_empty(&self) -> bool {
+        !self.rounds.is_changed()
+            && !self.themes.is_changed()
+            && !self.questions.is_changed()
+            && !self.resources.is_changed()
+            && self.changed_questions == 0
+            && !self.metadata_changed
+    }
+}
+
+impl Packagev4 {
+    /// Summarize changes made in the `newer` version of this package.
+    pub fn diff(&self, newer: &Packagev4) -> PackageDiffv4 {
+        let diff = |count: fn(&Packagev4) -> usize| CountDiffv4 {
+            before: count(self),
+            after: count(newer),
+        };
+
+        let changed_questions = self
+            .rounds
+            .iter()
+            .zip(&newer.rounds)
+            .flat_map(|(before, after)| before.themes.iter().zip(&after.themes))
+            .flat_map(|(before, after)| before.questions.iter().zip(&after.questions))
+            .filter(|(before, after)| before != after)
+            .count();
+
+        let metadata = |package: &Packagev4| Packagev4 {
+            rounds: vec![],
+            resources: Default::default(),
+            ..package.clone()
+        };
+
+        PackageDiffv4 {
+            rounds: diff(|package| package.rounds.len()),
+            themes: diff(|package| package.rounds.iter().map(|round| round.themes.len()).sum()),
+            questions: diff(|package| {
+                let themes = package.rounds.iter().flat_map(|round| &round.themes);
+                themes.map(|theme| theme.questions.len()).sum()
+            }),
+            resources: diff(|package| package.resources.len()),
+            changed_questions,
+            metadata_changed: metadata(self) != metadata(newer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::package_trait::{QuestionsContainer, RoundContainer, ThemesContainer};
+    use crate::v4::{PackageTemplatev4, ResourceIdv4, Themev4};
+
+    #[test]
+    fn package_diff() {
+        let before = PackageTemplatev4::default().build("Пак", "", "ru");
+        assert!(before.diff(&before).is_empty());
+
+        let mut after = before.clone();
+        after.name = "Новое имя".to_string();
+        after.remove_round(3);
+        after.get_question_mut((0, 1, 2)).unwrap().price = 1;
+        after.allocate_theme(1);
+        after.allocate_question((1, 0));
+        after.resources.insert(ResourceIdv4::image("a.png"), Arc::from([0u8].as_slice()));
+
+        let diff = before.diff(&after);
+        assert!(diff.metadata_changed);
+        assert_eq!(diff.rounds, CountDiffv4 { before: 4, after: 3 });
+        assert_eq!(diff.themes, CountDiffv4 { before: 24, after: 19 });
+        let new_theme = Themev4::default().questions.len();
+        assert_eq!(diff.questions, CountDiffv4 { before: 96, after: 91 + new_theme });
+        assert_eq!(diff.resources, CountDiffv4 { before: 0, after: 1 });
+        assert_eq!(diff.changed_questions, 1);
+    }
+}
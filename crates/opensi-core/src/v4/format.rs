@@ -0,0 +1,273 @@
+use super::{AtomKindv4, Packagev4, ResourceIdv4};
+
+/// Real format of a resource, sniffed from its magic bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MediaFormatv4 {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Bmp,
+    Mp3,
+    Ogg,
+    Wav,
+    Flac,
+    /// Raw AAC audio in ADTS frames.
+    Aac,
+    /// Audio-only MP4 container.
+    M4a,
+    Mp4,
+    Webm,
+}
+
+impl MediaFormatv4 {
+    /// Recognize a format by the first bytes of a file.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        let riff = |kind: &[u8]| bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(kind);
+        let format = match bytes {
+            [0x89, b'P', b'N', b'G', ..] => Self::Png,
+            [0xFF, 0xD8, 0xFF, ..] => Self::Jpeg,
+            [b'G', b'I', b'F', b'8', ..] => Self::Gif,
+            [b'B', b'M', ..] => Self::Bmp,
+            _ if riff(b"WEBP") => Self::Webp,
+            _ if riff(b"WAVE") => Self::Wav,
+            [b'I', b'D', b'3', ..] => Self::Mp3,
+            // ADTS frame sync, which is MPEG frame sync with layer bits 00
+            [0xFF, second, ..] if second & 0xF6 == 0xF0 => Self::Aac,
+            // MPEG audio frame sync
+            [0xFF, second, ..] if second & 0xE0 == 0xE0 && second & 0x06 != 0 => Self::Mp3,
+            [b'O', b'g', b'g', b'S', ..] => Self::Ogg,
+            [b'f', b'L', b'a', b'C', ..] => Self::Flac,
+            [0x1A, 0x45, 0xDF, 0xA3, ..] => Self::Webm,
+            _ if bytes.get(4..8) == Some(b"ftyp") => match bytes.get(8..12) {
+                Some(b"M4A " | b"M4B ") => Self::M4a,
+                _ => Self::Mp4,
+            },
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// Guess a format by a file extension, e.g. "jpg".
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.to_lowercase();
+        [
+            Self::Png,
+            Self::Jpeg,
+            Self::Gif,
+            Self::Webp,
+            Self::Bmp,
+            Self::Mp3,
+            Self::Ogg,
+            Self::Wav,
+            Self::Flac,
+            Self::Aac,
+            Self::M4a,
+            Self::Mp4,
+            Self::Webm,
+        ]
+        .into_iter()
+        .find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+            Self::Bmp => "image/bmp",
+            Self::Mp3 => "audio/mpeg",
+            Self::Ogg => "audio/ogg",
+            Self::Wav => "audio/wav",
+            Self::Flac => "audio/flac",
+            Self::Aac => "audio/aac",
+            Self::M4a => "audio/mp4",
+            Self::Mp4 => "video/mp4",
+            Self::Webm => "video/webm",
+        }
+    }
+
+    /// File extensions of the format, the first one is preferred.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Png => &["png"],
+            Self::Jpeg => &["jpg", "jpeg", "jpe", "jfif"],
+            Self::Gif => &["gif"],
+            Self::Webp => &["webp"],
+            Self::Bmp => &["bmp"],
+            Self::Mp3 => &["mp3"],
+            Self::Ogg => &["ogg", "oga", "opus"],
+            Self::Wav => &["wav"],
+            Self::Flac => &["flac"],
+            Self::Aac => &["aac"],
+            Self::M4a => &["m4a", "aac"],
+            Self::Mp4 => &["mp4", "m4v"],
+            Self::Webm => &["webm", "mkv"],
+        }
+    }
+
+    /// Kind of atoms which can show the format.
+    pub fn atom_kind(&self) -> AtomKindv4 {
+        match self {
+            Self::Png | Self::Jpeg | Self::Gif | Self::Webp | Self::Bmp => AtomKindv4::Image,
+            Self::Mp3 | Self::Ogg | Self::Wav | Self::Flac | Self::Aac | Self::M4a => {
+                AtomKindv4::Voice
+            },
+            Self::Mp4 | Self::Webm => AtomKindv4::Video,
+        }
+    }
+
+    /// Short name of the format, e.g. "PNG".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::Gif => "GIF",
+            Self::Webp => "WebP",
+            Self::Bmp => "BMP",
+            Self::Mp3 => "MP3",
+            Self::Ogg => "Ogg",
+            Self::Wav => "WAV",
+            Self::Flac => "FLAC",
+            Self::Aac => "AAC",
+            Self::M4a => "M4A",
+            Self::Mp4 => "MP4",
+            Self::Webm => "WebM",
+        }
+    }
+}
+
+/// Reason why the content of a resource doesn't fit its name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatMismatchv4 {
+    /// Content isn't any known media format.
+    Unknown,
+    /// Content is media of another kind, e.g. an image in `Audio/`.
+    WrongKind(MediaFormatv4),
+    /// Content doesn't match the file extension, e.g. a PNG named `.jpg`.
+    WrongExtension(MediaFormatv4),
+}
+
+/// Metadata of a resource sniffed from its content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceMetav4 {
+    pub format: Option<MediaFormatv4>,
+    pub mismatch: Option<FormatMismatchv4>,
+}
+
+impl ResourceMetav4 {
+    pub fn mime(&self) -> Option<&'static str> {
+        self.format.as_ref().map(MediaFormatv4::mime)
+    }
+}
+
+impl ResourceIdv4 {
+    /// Kind of atoms which can use the resource.
+    pub fn atom_kind(&self) -> AtomKindv4 {
+        match self {
+            Self::Image(_) => AtomKindv4::Image,
+            Self::Audio(_) => AtomKindv4::Voice,
+            Self::Video(_) => AtomKindv4::Video,
+            Self::Texts(_) => AtomKindv4::Text,
+        }
+    }
+
+    /// Lowercase file extension, e.g. "png".
+    pub fn extension(&self) -> Option<String> {
+        self.name().rsplit_once('.').map(|(_, extension)| extension.to_lowercase())
+    }
+
+    /// Sniff the real format of the resource content and check
+    /// whether it fits the resource name.
+    pub fn inspect(&self, bytes: &[u8]) -> ResourceMetav4 {
+        let format = MediaFormatv4::sniff(bytes);
+        if let Self::Texts(_) = self {
+            return ResourceMetav4 { format, mismatch: None };
+        }
+
+        let mismatch = match format {
+            None => Some(FormatMismatchv4::Unknown),
+            Some(format) if format.atom_kind() != self.atom_kind() => {
+                Some(FormatMismatchv4::WrongKind(format))
+            },
+            Some(format) => match self.extension() {
+                Some(extension) if !format.extensions().contains(&extension.as_str()) => {
+                    Some(FormatMismatchv4::WrongExtension(format))
+                },
+                _ => None,
+            },
+        };
+        ResourceMetav4 { format, mismatch }
+    }
+}
+
+/// # Formats of resources.
+impl Packagev4 {
+    /// Metadata of a resource, if the package has it.
+    pub fn resource_meta(&self, id: &ResourceIdv4) -> Option<ResourceMetav4> {
+        self.resources.get(id).map(|bytes| id.inspect(bytes))
+    }
+
+    /// Resources which content doesn't fit their names, sorted by path.
+    pub fn format_mismatches(&self) -> Vec<(ResourceIdv4, FormatMismatchv4)> {
+        let mut mismatches = self
+            .resources
+            .iter()
+            .filter_map(|(id, bytes)| Some((id.clone(), id.inspect(bytes).mismatch?)))
+            .collect::<Vec<_>>();
+        mismatches.sort_by(|(a, _), (b, _)| a.path().cmp(b.path()));
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_formats() {
+        let cases: [(&[u8], Option<MediaFormatv4>); 12] = [
+            (b"\x89PNG\r\n\x1a\n", Some(MediaFormatv4::Png)),
+            (b"\xFF\xD8\xFF\xE0", Some(MediaFormatv4::Jpeg)),
+            (b"RIFF\0\0\0\0WEBPVP8 ", Some(MediaFormatv4::Webp)),
+            (b"RIFF\0\0\0\0WAVEfmt ", Some(MediaFormatv4::Wav)),
+            (b"ID3\x04\0", Some(MediaFormatv4::Mp3)),
+            (b"\xFF\xFB\x90\x00", Some(MediaFormatv4::Mp3)),
+            (b"\xFF\xF1\x50\x80", Some(MediaFormatv4::Aac)),
+            (b"\xFF\xF9\x50\x80", Some(MediaFormatv4::Aac)),
+            (b"OggS\0", Some(MediaFormatv4::Ogg)),
+            (b"\0\0\0\x20ftypisom", Some(MediaFormatv4::Mp4)),
+            (b"\0\0\0\x20ftypM4A ", Some(MediaFormatv4::M4a)),
+            (b"plain text", None),
+        ];
+        for (bytes, format) in cases {
+            assert_eq!(MediaFormatv4::sniff(bytes), format, "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn inspect_resources() {
+        let png = b"\x89PNG\r\n\x1a\n";
+
+        let meta = ResourceIdv4::image("a.png").inspect(png);
+        assert_eq!(meta.mime(), Some("image/png"));
+        assert_eq!(meta.mismatch, None);
+        assert_eq!(ResourceIdv4::image("a.PNG").inspect(png).mismatch, None);
+
+        assert_eq!(
+            ResourceIdv4::image("a.jpg").inspect(png).mismatch,
+            Some(FormatMismatchv4::WrongExtension(MediaFormatv4::Png))
+        );
+        assert_eq!(
+            ResourceIdv4::audio("a.mp3").inspect(png).mismatch,
+            Some(FormatMismatchv4::WrongKind(MediaFormatv4::Png))
+        );
+        assert_eq!(
+            ResourceIdv4::audio("a.mp3").inspect(b"plain text").mismatch,
+            Some(FormatMismatchv4::Unknown)
+        );
+        assert_eq!(ResourceIdv4::texts("a.txt").inspect(b"plain text").mismatch, None);
+        assert_eq!(ResourceIdv4::audio("a.aac").inspect(b"\xFF\xF1\x50\x80").mismatch, None);
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    sync::Arc,
+};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+
+use super::{Packagev4, Questionv4, ResourceIdv4, Roundv4, Themev4};
+use crate::{
+    node::{NodeId, PackageNode, RoundIdx, ThemeIdx},
+    package_trait::{
+        QuestionBase, QuestionsContainer, RoundBase, RoundContainer, ThemeBase, ThemesContainer,
+    },
+    serde_impl,
+};
+
+/// Detached part of a [`Packagev4`] tree with all resources it references.
+/// Used to move nodes between packages, e.g. through the clipboard.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fragmentv4 {
+    pub rounds: Vec<Roundv4>,
+    pub themes: Vec<Themev4>,
+    pub questions: Vec<Questionv4>,
+    pub resources: HashMap<ResourceIdv4, Arc<[u8]>>,
+}
+
+/// Xml representation of [`Fragmentv4`].
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename = "opensi-fragment", default)]
+struct FragmentXml {
+    #[serde(with = "serde_impl::rounds", skip_serializing_if = "Vec::is_empty")]
+    rounds: Vec<Roundv4>,
+    #[serde(with = "serde_impl::themes", skip_serializing_if = "Vec::is_empty")]
+    themes: Vec<Themev4>,
+    #[serde(with = "serde_impl::questions", skip_serializing_if = "Vec::is_empty")]
+    questions: Vec<Questionv4>,
+    #[serde(with = "serde_impl::fragment_resources", skip_serializing_if = "Vec::is_empty")]
+    resources: Vec<ResourceXml>,
+}
+
+/// Resource of [`FragmentXml`] encoded in base64.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ResourceXml {
+    #[serde(rename = "@path")]
+    path: String,
+    #[serde(rename = "$text", default)]
+    data: String,
+}
+
+impl Fragmentv4 {
+    pub fn is_empty(&self) -> bool {
+        self.rounds.is_empty() && self.themes.is_empty() && self.questions.is_empty()
+    }
+
+    /// Serialize fragment into a standalone xml string.
+    pub fn to_xml_string(&self) -> Result<String, Error> {
+        let mut resources = self
+            .resources
+            .iter()
+            .map(|(id, bytes)| ResourceXml {
+                path: id.path().to_string(),
+                data: BASE64_STANDARD.encode(bytes),
+            })
+            .collect::<Vec<_>>();
+        resources.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let xml = FragmentXml {
+            rounds: self.rounds.clone(),
+            themes: self.themes.clone(),
+            questions: self.questions.clone(),
+            resources,
+        };
+        quick_xml::se::to_string(&xml).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Deserialize fragment from an xml string made by [`Fragmentv4::to_xml_string`].
+    pub fn from_xml_str(xml: &str) -> Result<Self, Error> {
+        let xml: FragmentXml =
+            quick_xml::de::from_str(xml).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let mut resources = HashMap::new();
+        for resource in xml.resources {
+            let id = ResourceIdv4::try_new(&resource.path).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidInput, format!("Unknown resource {}", resource.path))
+            })?;
+            let bytes = BASE64_STANDARD
+                .decode(resource.data.trim())
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            resources.insert(id, Arc::from(bytes.into_boxed_slice()));
+        }
+
+        Ok(Self { rounds: xml.rounds, themes: xml.themes, questions: xml.questions, resources })
+    }
+
+    fn questions_mut(&mut self) -> impl Iterator<Item = &mut Questionv4> {
+        let round_questions = self
+            .rounds
+            .iter_mut()
+            .flat_map(|round| round.themes.iter_mut())
+            .flat_map(|theme| theme.questions.iter_mut());
+        let theme_questions = self.themes.iter_mut().flat_map(|theme| theme.questions.iter_mut());
+        round_questions.chain(theme_questions).chain(self.questions.iter_mut())
+    }
+}
+
+/// # Fragments of package.
+impl Packagev4 {
+    /// Copy nodes with all resources they reference into a [`Fragmentv4`].
+    pub fn copy_fragment(&self, nodes: &[PackageNode]) -> Fragmentv4 {
+        let mut fragment = Fragmentv4::default();
+        for &node in nodes {
+            match node {
+                PackageNode::Round(idx) => fragment.rounds.extend(self.get_round(idx).cloned()),
+                PackageNode::Theme(idx) => fragment.themes.extend(self.get_theme(idx).cloned()),
+                PackageNode::Question(idx) => {
+                    fragment.questions.extend(self.get_question(idx).cloned())
+                },
+            }
+        }
+
+        let ids = fragment
+            .questions_mut()
+            .flat_map(|question| question.scenario.iter())
+            .filter_map(|atom| atom.resource())
+            .collect::<Vec<_>>();
+        for id in ids {
+            if let Some(bytes) = self.resources.get(&id) {
+                fragment.resources.insert(id, bytes.clone());
+            }
+        }
+
+        fragment
+    }
+
+    /// Paste nodes of a [`Fragmentv4`] relative to the `target` node:
+    /// - Nodes of the same kind as `target` are inserted right after it;
+    /// - Nodes of a child kind are appended to the `target`;
+    /// - Other nodes are inserted after the closest fitting node.
+    ///
+    /// Resources with colliding names get renamed. Returns positions of
+    /// all pasted nodes.
+    pub fn paste_fragment(
+        &mut self,
+        mut fragment: Fragmentv4,
+        target: Option<PackageNode>,
+    ) -> Vec<PackageNode> {
+        self.merge_fragment_resources(&mut fragment);
+
+        let mut pasted = vec![];
+        let round_idx = target.map(|node| match node {
+            PackageNode::Round(idx) => idx,
+            PackageNode::Theme(idx) => idx.parent(),
+            PackageNode::Question(idx) => idx.parent().parent(),
+        });
+
+        let mut next_round = round_idx.map(|idx| idx.next()).unwrap_or(self.count_rounds().into());
+        for mut round in fragment.rounds {
+            round.renew_ids();
+            if self.insert_round(next_round, round).is_some() {
+                pasted.push(next_round.into());
+                next_round = next_round.next();
+            }
+        }
+
+        if !fragment.themes.is_empty() {
+            let mut next_theme = match target {
+                Some(PackageNode::Theme(idx)) => idx.next(),
+                Some(PackageNode::Question(idx)) => idx.parent().next(),
+                _ => {
+                    let round_idx = round_idx.unwrap_or_else(|| self.last_round_or_new());
+                    round_idx.theme(self.count_themes(round_idx))
+                },
+            };
+            for mut theme in fragment.themes {
+                theme.renew_ids();
+                if self.insert_theme(next_theme, theme).is_some() {
+                    pasted.push(next_theme.into());
+                    next_theme = next_theme.next();
+                }
+            }
+        }
+
+        if !fragment.questions.is_empty() {
+            let mut next_question = match target {
+                Some(PackageNode::Question(idx)) => idx.next(),
+                Some(PackageNode::Theme(idx)) => idx.question(self.count_questions(idx)),
+                _ => {
+                    let round_idx = round_idx.unwrap_or_else(|| self.last_round_or_new());
+                    let theme_idx = self.last_theme_or_new(round_idx);
+                    theme_idx.question(self.count_questions(theme_idx))
+                },
+            };
+            for mut question in fragment.questions {
+                question.set_id(NodeId::new());
+                if self.insert_question(next_question, question).is_some() {
+                    pasted.push(next_question.into());
+                    next_question = next_question.next();
+                }
+            }
+        }
+
+        pasted
+    }
+
+    /// Move resources of the fragment into package, renaming those that
+    /// collide with different resources of the package.
+    fn merge_fragment_resources(&mut self, fragment: &mut Fragmentv4) {
+        let mut renames = HashMap::new();
+        for (id, bytes) in std::mem::take(&mut fragment.resources) {
+            let new_id = self.add_resource(id.clone(), bytes);
+            if new_id != id {
+                renames.insert(id, new_id);
+            }
+        }
+
+        for atom in fragment.questions_mut().flat_map(|question| question.scenario.iter_mut()) {
+            if let Some(new_id) = atom.resource().and_then(|id| renames.get(&id)) {
+                atom.body = new_id.atom_body();
+            }
+        }
+    }
+
+    fn last_round_or_new(&mut self) -> RoundIdx {
+        if self.count_rounds() == 0 {
+            self.allocate_round();
+        }
+        (self.count_rounds() - 1).into()
+    }
+
+    fn last_theme_or_new(&mut self, round_idx: RoundIdx) -> ThemeIdx {
+        let count = self.count_themes(round_idx);
+        if count > 0 {
+            return round_idx.theme(count - 1);
+        }
+        self.push_theme(round_idx, Themev4 { questions: vec![], ..Themev4::default() });
+        round_idx.theme(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        package_trait::NodeContainer,
+        v4::{AtomKindv4, Atomv4},
+    };
+
+    fn image_question(body: &str) -> Questionv4 {
+        let atom = Atomv4 { kind: AtomKindv4::Image, body: body.to_string(), time: None };
+        Questionv4 { scenario: vec![atom], ..Questionv4::default() }
+    }
+
+    #[test]
+    fn xml_roundtrip() {
+        let mut package = Packagev4::new();
+        package.allocate_round().themes.push(Themev4::default());
+        package.push_question((0, 0), image_question("@cat.png"));
+        package.resources.insert(ResourceIdv4::image("@cat.png"), Arc::from(&b"meow"[..]));
+
+        let fragment = package.copy_fragment(&[(0, 0).into()]);
+        assert_eq!(fragment.themes.len(), 1);
+        assert_eq!(fragment.resources.len(), 1);
+
+        let xml = fragment.to_xml_string().unwrap();
+        assert_eq!(Fragmentv4::from_xml_str(&xml).unwrap(), fragment);
+        assert!(Fragmentv4::from_xml_str("<package/>").unwrap().is_empty());
+    }
+
+    #[test]
+    fn paste_relative_to_target() {
+        let mut package = Packagev4::new();
+        package.allocate_round().themes.push(Themev4::default());
+        package.allocate_round();
+
+        let fragment = package.copy_fragment(&[(0, 0, 1).into(), (0, 0, 2).into()]);
+        let pasted = package.paste_fragment(fragment.clone(), Some((0, 0, 0).into()));
+        assert_eq!(pasted, vec![(0, 0, 1).into(), (0, 0, 2).into()]);
+        assert_eq!(package.count_questions((0, 0)), 7);
+
+        let pasted = package.paste_fragment(fragment, Some(1.into()));
+        assert_eq!(pasted, vec![(1, 0, 0).into(), (1, 0, 1).into()]);
+        assert_eq!(package.get_question((1, 0, 1)).unwrap().price, 300);
+
+        let fragment = package.copy_fragment(&[0.into()]);
+        let pasted = package.paste_fragment(fragment, None);
+        assert_eq!(pasted, vec![2.into()]);
+        assert_ne!(package.node_id(0.into()), package.node_id(2.into()));
+    }
+
+    #[test]
+    fn paste_renames_colliding_resources() {
+        let mut source = Packagev4::new();
+        source.allocate_round().themes.push(Themev4 { questions: vec![], ..Themev4::default() });
+        source.push_question((0, 0), image_question("@cat.png"));
+        source.resources.insert(ResourceIdv4::image("@cat.png"), Arc::from(&b"meow"[..]));
+        let fragment = source.copy_fragment(&[(0, 0, 0).into()]);
+
+        let mut target = source.clone();
+        target.paste_fragment(fragment.clone(), Some((0, 0, 0).into()));
+        assert_eq!(target.resources.len(), 1);
+        assert_eq!(target.get_question((0, 0, 1)).unwrap().scenario[0].body, "@cat.png");
+
+        target.resources.insert(ResourceIdv4::image("@cat.png"), Arc::from(&b"woof"[..]));
+        target.paste_fragment(fragment, Some((0, 0, 0).into()));
+        let atom = &target.get_question((0, 0, 1)).unwrap().scenario[0];
+        assert_eq!(atom.body, "@cat_1.png");
+        assert_eq!(target.get_resource(atom).map(|bytes| &bytes[..]), Some(&b"meow"[..]));
+    }
+
+    #[test]
+    fn add_resource_keeps_existing() {
+        let mut package = Packagev4::new();
+        let id = ResourceIdv4::image("@my cat.png");
+        assert_eq!(package.add_resource(id.clone(), Arc::from(&b"meow"[..])), id);
+        assert_eq!(package.add_resource(id.clone(), Arc::from(&b"meow"[..])), id);
+
+        let renamed = package.add_resource(id.clone(), Arc::from(&b"purr"[..]));
+        assert_eq!(renamed.atom_body(), "@my cat_1.png");
+        assert_eq!(package.resources.get(&id).map(|bytes| &bytes[..]), Some(&b"meow"[..]));
+        assert_eq!(package.resources.len(), 2);
+    }
+}
@@ -0,0 +1,345 @@
+use std::fmt::Write;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+
+use super::{
+    AtomKindv4, Atomv4, ImageOptimizationv4, Infov4, MediaFormatv4, Packagev4, Questionv4,
+};
+
+/// Thumbnails of image questions fit into this width and height.
+const THUMBNAIL_SIZE: u32 = 320;
+/// Max size of an image which can't be downscaled, e.g. an animated one,
+/// to be embedded as is. Bigger images are left out of the script.
+const MAX_THUMBNAIL_BYTES: usize = 64 * 1024;
+
+const HOST_SCRIPT_STYLE: &str = "
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; line-height: 1.4; }
+h2 { border-bottom: 2px solid; }
+table { width: 100%; border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #999; padding: 0.4em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+tr { page-break-inside: avoid; }
+.price { width: 4em; font-weight: bold; }
+.answer { font-weight: bold; }
+.wrong, .comment, .media { color: #555; }
+img { max-width: 100%; display: block; margin: 0.3em 0; }
+@media print { .round { page-break-before: always; } .round:first-of-type { page-break-before: auto; } }
+";
+
+/// # Host script export.
+///
+/// A host script is a printable sheet for the host of a live game:
+/// every question with its right and wrong answers and comments.
+impl Packagev4 {
+    /// Render the host script into a self-contained HTML page,
+    /// image questions get embedded thumbnails.
+    pub fn to_host_script_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{} — сценарий ведущего</title>\n<style>{HOST_SCRIPT_STYLE}</style>\n\
+             </head>\n<body>\n<h1>{}</h1>\n",
+            escape(&self.language),
+            escape(&self.name),
+            escape(&self.name),
+        );
+        if !self.info.authors.is_empty() {
+            let _ = writeln!(html, "<p>Авторы: {}</p>", escape(&self.info.authors.join(", ")));
+        }
+        info_html(&self.info, &mut html);
+
+        for round in &self.rounds {
+            let _ = writeln!(html, "<section class=\"round\">\n<h2>{}</h2>", escape(&round.name));
+            if let Some(info) = &round.info {
+                info_html(info, &mut html);
+            }
+            for theme in &round.themes {
+                let _ = writeln!(html, "<h3>{}</h3>", escape(&theme.name));
+                if let Some(info) = &theme.info {
+                    info_html(info, &mut html);
+                }
+                html.push_str(
+                    "<table>\n<tr><th>Цена</th><th>Вопрос</th><th>Ответ</th><th>Комментарий</th></tr>\n",
+                );
+                for question in &theme.questions {
+                    self.question_html(question, &mut html);
+                }
+                html.push_str("</table>\n");
+            }
+            html.push_str("</section>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Render the host script into Markdown. Media is listed by file names.
+    pub fn to_host_script_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n", markdown_escape(&self.name));
+        if !self.info.authors.is_empty() {
+            let _ =
+                writeln!(markdown, "Авторы: {}\n", markdown_escape(&self.info.authors.join(", ")));
+        }
+        info_markdown(&self.info, &mut markdown);
+
+        for round in &self.rounds {
+            let _ = writeln!(markdown, "## {}\n", markdown_escape(&round.name));
+            if let Some(info) = &round.info {
+                info_markdown(info, &mut markdown);
+            }
+            for theme in &round.themes {
+                let _ = writeln!(markdown, "### {}\n", markdown_escape(&theme.name));
+                if let Some(info) = &theme.info {
+                    info_markdown(info, &mut markdown);
+                }
+                for question in &theme.questions {
+                    question_markdown(question, &mut markdown);
+                }
+            }
+        }
+        markdown
+    }
+
+    fn question_html(&self, question: &Questionv4, html: &mut String) {
+        let _ = write!(html, "<tr><td class=\"price\">{}</td><td>", question.price);
+        if let Some(question_type) = question_type(question) {
+            let _ = write!(html, "<p class=\"comment\">{}</p>", escape(&question_type));
+        }
+        for atom in &question.scenario {
+            match atom.kind {
+                AtomKindv4::Text => {
+                    let _ = write!(html, "<p>{}</p>", escape(&atom.body).replace('\n', "<br>"));
+                },
+                AtomKindv4::Image => match self.thumbnail(atom) {
+                    Some(src) => {
+                        let _ = write!(
+                            html,
+                            "<img src=\"{src}\" alt=\"{}\">",
+                            escape(&media_name(atom))
+                        );
+                    },
+                    None => {
+                        let _ =
+                            write!(html, "<p class=\"media\">{}</p>", escape(&media_label(atom)));
+                    },
+                },
+                AtomKindv4::Voice | AtomKindv4::Video => {
+                    let _ = write!(html, "<p class=\"media\">{}</p>", escape(&media_label(atom)));
+                },
+            }
+        }
+
+        let _ =
+            write!(html, "</td><td><p class=\"answer\">{}</p>", escape(&question.right.join("; ")));
+        if !question.wrong.is_empty() {
+            let _ = write!(
+                html,
+                "<p class=\"wrong\">Неправильные: {}</p>",
+                escape(&question.wrong.join("; "))
+            );
+        }
+        html.push_str("</td><td class=\"comment\">");
+        if let Some(info) = &question.info {
+            let _ = write!(html, "{}", escape(&info_text(info)).replace('\n', "<br>"));
+        }
+        html.push_str("</td></tr>\n");
+    }
+
+    /// Downscaled image of an atom as a data URI.
+    fn thumbnail(&self, atom: &Atomv4) -> Option<String> {
+        let bytes = self.resources.get(&atom.resource()?)?;
+        let options = ImageOptimizationv4 { max_dimension: THUMBNAIL_SIZE, jpeg_quality: 70 };
+        let (bytes, mime) = match options.optimize(bytes).ok()? {
+            Some(thumbnail) => {
+                let mime = if thumbnail.extension == "png" { "image/png" } else { "image/jpeg" };
+                (thumbnail.bytes, mime)
+            },
+            // already small enough or animated
+            None if bytes.len() <= MAX_THUMBNAIL_BYTES => {
+                (bytes.to_vec(), MediaFormatv4::sniff(bytes)?.mime())
+            },
+            None => return None,
+        };
+        Some(format!("data:{mime};base64,{}", BASE64_STANDARD.encode(bytes)))
+    }
+}
+
+fn question_markdown(question: &Questionv4, markdown: &mut String) {
+    let _ = writeln!(markdown, "#### {}\n", question.price);
+    if let Some(question_type) = question_type(question) {
+        let _ = writeln!(markdown, "*{}*\n", markdown_escape(&question_type));
+    }
+    for atom in &question.scenario {
+        let text = match atom.kind {
+            AtomKindv4::Text => markdown_escape(&atom.body).replace('\n', "  \n"),
+            _ => format!("*{}*", markdown_escape(&media_label(atom))),
+        };
+        let _ = writeln!(markdown, "{text}\n");
+    }
+    let _ = writeln!(markdown, "**Ответ:** {}\n", markdown_escape(&question.right.join("; ")));
+    if !question.wrong.is_empty() {
+        let _ = writeln!(
+            markdown,
+            "**Неправильные:** {}\n",
+            markdown_escape(&question.wrong.join("; "))
+        );
+    }
+    if let Some(info) = &question.info {
+        let text = info_text(info);
+        if !text.is_empty() {
+            let _ = writeln!(markdown, "> {}\n", markdown_escape(&text).replace('\n', "  \n> "));
+        }
+    }
+}
+
+/// Name of a non-simple question type.
+fn question_type(question: &Questionv4) -> Option<String> {
+    match question.question_type.name.as_str() {
+        "" | "simple" => None,
+        _ => Some(question.question_type.to_string()),
+    }
+}
+
+/// Comments, authors and sources of a question.
+fn info_text(info: &Infov4) -> String {
+    let mut lines = vec![];
+    if !info.comments.is_empty() {
+        lines.push(info.comments.clone());
+    }
+    if !info.authors.is_empty() {
+        lines.push(format!("Авторы: {}", info.authors.join(", ")));
+    }
+    if !info.sources.is_empty() {
+        lines.push(format!("Источники: {}", info.sources.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn info_html(info: &Infov4, html: &mut String) {
+    if !info.comments.is_empty() {
+        let _ = writeln!(
+            html,
+            "<p class=\"comment\">{}</p>",
+            escape(&info.comments).replace('\n', "<br>")
+        );
+    }
+}
+
+fn info_markdown(info: &Infov4, markdown: &mut String) {
+    if !info.comments.is_empty() {
+        let _ = writeln!(markdown, "*{}*\n", markdown_escape(&info.comments));
+    }
+}
+
+/// Decoded file name of a media atom.
+fn media_name(atom: &Atomv4) -> String {
+    let name = atom.body.strip_prefix('@').unwrap_or(&atom.body);
+    percent_encoding::percent_decode_str(name).decode_utf8_lossy().into_owned()
+}
+
+/// Description of a media atom, e.g. "Аудио: song.mp3 (10 с)".
+fn media_label(atom: &Atomv4) -> String {
+    let kind = match atom.kind {
+        AtomKindv4::Image => "Изображение",
+        AtomKindv4::Voice => "Аудио",
+        AtomKindv4::Video => "Видео",
+        AtomKindv4::Text => "Текст",
+    };
+    match atom.time {
+        Some(time) => format!("{kind}: {} ({time} с)", media_name(atom)),
+        None => format!("{kind}: {}", media_name(atom)),
+    }
+}
+
+fn escape(text: &str) -> String {
+    quick_xml::escape::escape(text).into_owned()
+}
+
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        if r"\`*_[]<>#|".contains(char) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::package_trait::QuestionsContainer;
+    use crate::v4::{PackageTemplatev4, ResourceIdv4};
+
+    #[test]
+    fn host_script() {
+        let mut package = PackageTemplatev4::default().build("Пак <1>", "Автор", "ru");
+        let question = package.get_question_mut((0, 0, 0)).unwrap();
+        question.scenario = vec![
+            Atomv4 { body: "Кто *снял* фильм?".to_string(), ..Atomv4::default() },
+            Atomv4 { kind: AtomKindv4::Image, body: "@a.png".to_string(), time: None },
+            Atomv4 { kind: AtomKindv4::Voice, body: "@b.mp3".to_string(), time: Some(10.0) },
+        ];
+        question.right = vec!["Тарковский".to_string()];
+        question.wrong = vec!["Михалков".to_string()];
+        question.info = Some(Infov4 {
+            comments: "Засчитывать фамилию".to_string(),
+            ..Infov4::default()
+        });
+
+        let mut png = vec![];
+        image::DynamicImage::new_rgb8(1000, 500)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        package.resources.insert(ResourceIdv4::image("a.png"), Arc::from(png));
+
+        let html = package.to_host_script_html();
+        assert!(html.contains("<h1>Пак &lt;1&gt;</h1>"));
+        assert!(html.contains("<img src=\"data:image/png;base64,"));
+        assert!(html.contains("Аудио: b.mp3 (10 с)"));
+        assert!(html.contains("Тарковский") && html.contains("Неправильные: Михалков"));
+        assert!(html.contains("Засчитывать фамилию"));
+        assert!(html.contains(&package.rounds[1].name));
+
+        let markdown = package.to_host_script_markdown();
+        assert!(markdown.starts_with("# Пак \\<1\\>\n"));
+        assert!(markdown.contains("Кто \\*снял\\* фильм?"));
+        assert!(markdown.contains("*Изображение: a.png*"));
+        assert!(markdown.contains("**Ответ:** Тарковский"));
+        assert!(markdown.contains("> Засчитывать фамилию"));
+    }
+
+    #[test]
+    fn big_animated_image_is_skipped() {
+        let mut package = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        let question = package.get_question_mut((0, 0, 0)).unwrap();
+        question.scenario =
+            vec![Atomv4 { kind: AtomKindv4::Image, body: "@a.gif".to_string(), time: None }];
+
+        let mut gif = vec![];
+        {
+            let mut encoder = image::codecs::gif::GifEncoder::new(&mut gif);
+            let mut state = 0x2545_f491_u32;
+            for _ in 0..4 {
+                let pixels = image::RgbaImage::from_fn(200, 200, |_, _| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    let [r, g, b, _] = state.to_le_bytes();
+                    image::Rgba([r, g, b, 255])
+                });
+                encoder.encode_frame(image::Frame::new(pixels)).unwrap();
+            }
+        }
+        assert!(gif.len() > MAX_THUMBNAIL_BYTES);
+        package.resources.insert(ResourceIdv4::image("a.gif"), Arc::from(gif));
+
+        let html = package.to_host_script_html();
+        assert!(!html.contains("data:image/"));
+        assert!(html.contains("a.gif"));
+    }
+}
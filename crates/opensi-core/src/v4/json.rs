@@ -0,0 +1,389 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AtomKindv4, Atomv4, Infov4, Packagev4, Paramv4, QuestionTypev4, Questionv4, ResourceIdv4,
+    Roundv4, Themev4,
+};
+
+/// Version of the JSON representation, bumped on breaking changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// How resources of a package are stored in its JSON representation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonResourcesv4 {
+    /// Resource content is embedded as base64.
+    #[default]
+    Base64,
+    /// Resources are separate files referenced by a path
+    /// relative to the JSON file, e.g. `Images/@photo.jpg`.
+    Files,
+}
+
+/// Stable JSON representation of [`Packagev4`], described by
+/// [`Packagev4::JSON_SCHEMA`]. All fields are always present.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PackageJson {
+    format_version: u32,
+    name: String,
+    version: f32,
+    id: String,
+    date: String,
+    publisher: String,
+    difficulty: u8,
+    language: String,
+    logo: Option<String>,
+    restriction: String,
+    namespace: String,
+    tags: Vec<String>,
+    info: InfoJson,
+    rounds: Vec<RoundJson>,
+    resources: BTreeMap<String, ResourceJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct InfoJson {
+    authors: Vec<String>,
+    sources: Vec<String>,
+    comments: String,
+    extension: String,
+}
+
+impl From<&Infov4> for InfoJson {
+    fn from(info: &Infov4) -> Self {
+        Self {
+            authors: info.authors.clone(),
+            sources: info.sources.clone(),
+            comments: info.comments.clone(),
+            extension: info.extension.clone(),
+        }
+    }
+}
+
+impl From<InfoJson> for Infov4 {
+    fn from(info: InfoJson) -> Self {
+        Self {
+            authors: info.authors,
+            sources: info.sources,
+            comments: info.comments,
+            extension: info.extension,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RoundJson {
+    name: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    info: Option<InfoJson>,
+    themes: Vec<ThemeJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ThemeJson {
+    name: String,
+    info: Option<InfoJson>,
+    questions: Vec<QuestionJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct QuestionJson {
+    price: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    params: Option<Vec<ParamJson>>,
+    scenario: Vec<AtomJson>,
+    right: Vec<String>,
+    wrong: Vec<String>,
+    info: Option<InfoJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ParamJson {
+    name: String,
+    value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct AtomJson {
+    kind: AtomKindv4,
+    /// Text, or a reference to a resource like `@photo.jpg`.
+    body: String,
+    time: Option<f64>,
+    /// Key of the referenced resource in [`PackageJson::resources`].
+    /// Only informative, it's derived from `body` on import.
+    #[serde(default)]
+    resource: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+enum ResourceJson {
+    Base64(String),
+    File(String),
+}
+
+fn invalid_data(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
+    Error::new(ErrorKind::InvalidData, err)
+}
+
+/// # JSON representation.
+impl Packagev4 {
+    /// JSON Schema of the representation made by [`Packagev4::to_json`].
+    pub const JSON_SCHEMA: &'static str = include_str!("../../package.schema.json");
+
+    /// Write the package as JSON. With [`JsonResourcesv4::Files`]
+    /// resources are only referenced, see [`Packagev4::export_json`].
+    pub fn to_json(&self, resources: JsonResourcesv4) -> Result<String, Error> {
+        let info = |info: &Option<Infov4>| info.as_ref().map(InfoJson::from);
+        let atom = |atom: &Atomv4| AtomJson {
+            kind: atom.kind,
+            body: atom.body.clone(),
+            time: atom.time,
+            resource: atom.resource().map(|id| id.path().to_string()),
+        };
+        let question = |question: &Questionv4| QuestionJson {
+            price: question.price,
+            kind: question.question_type.name.clone(),
+            params: question.question_type.params.as_ref().map(|params| {
+                params
+                    .iter()
+                    .map(|param| ParamJson { name: param.name.clone(), value: param.body.clone() })
+                    .collect()
+            }),
+            scenario: question.scenario.iter().map(atom).collect(),
+            right: question.right.clone(),
+            wrong: question.wrong.clone(),
+            info: info(&question.info),
+        };
+        let theme = |theme: &Themev4| ThemeJson {
+            name: theme.name.clone(),
+            info: info(&theme.info),
+            questions: theme.questions.iter().map(question).collect(),
+        };
+        let round = |round: &Roundv4| RoundJson {
+            name: round.name.clone(),
+            kind: round.kind.clone(),
+            info: info(&round.info),
+            themes: round.themes.iter().map(theme).collect(),
+        };
+
+        let resources = self
+            .resources
+            .iter()
+            .map(|(id, bytes)| {
+                let resource = match resources {
+                    JsonResourcesv4::Base64 => ResourceJson::Base64(BASE64_STANDARD.encode(bytes)),
+                    JsonResourcesv4::Files => ResourceJson::File(id.path().to_string()),
+                };
+                (id.path().to_string(), resource)
+            })
+            .collect();
+
+        let package = PackageJson {
+            format_version: FORMAT_VERSION,
+            name: self.name.clone(),
+            version: self.version,
+            id: self.id.clone(),
+            date: self.date.clone(),
+            publisher: self.publisher.clone(),
+            difficulty: self.difficulty,
+            language: self.language.clone(),
+            logo: self.logo.clone(),
+            restriction: self.restriction.clone(),
+            namespace: self.namespace.clone(),
+            tags: self.tags.clone(),
+            info: InfoJson::from(&self.info),
+            rounds: self.rounds.iter().map(round).collect(),
+            resources,
+        };
+        serde_json::to_string_pretty(&package).map_err(invalid_data)
+    }
+
+    /// Read a package from JSON made by [`Packagev4::to_json`]. Resource
+    /// files are read relative to `dir`, they are an error without it.
+    pub fn from_json(json: &str, dir: Option<&Path>) -> Result<Packagev4, Error> {
+        let package = serde_json::from_str::<PackageJson>(json).map_err(invalid_data)?;
+        if package.format_version > FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported JSON format version {}",
+                package.format_version
+            )));
+        }
+
+        let mut resources = HashMap::new();
+        for (path, resource) in package.resources {
+            let id = ResourceIdv4::try_new(&path).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidInput, format!("Unknown resource {path}"))
+            })?;
+            let bytes = match (resource, dir) {
+                (ResourceJson::Base64(data), _) => {
+                    BASE64_STANDARD.decode(data.trim()).map_err(invalid_data)?
+                },
+                (ResourceJson::File(file), Some(dir)) => fs::read(dir.join(&file))
+                    .map_err(|err| Error::new(err.kind(), format!("{file}: {err}")))?,
+                (ResourceJson::File(file), None) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Resource file {file} can't be read without a directory"),
+                    ));
+                },
+            };
+            resources.insert(id, Arc::from(bytes));
+        }
+
+        let info = |info: Option<InfoJson>| info.map(Infov4::from);
+        let atom = |atom: AtomJson| Atomv4 { kind: atom.kind, body: atom.body, time: atom.time };
+        let question = |question: QuestionJson| Questionv4 {
+            price: question.price,
+            question_type: QuestionTypev4 {
+                name: question.kind,
+                params: question.params.map(|params| {
+                    params
+                        .into_iter()
+                        .map(|param| Paramv4 { name: param.name, body: param.value })
+                        .collect()
+                }),
+            },
+            scenario: question.scenario.into_iter().map(atom).collect(),
+            right: question.right,
+            wrong: question.wrong,
+            info: info(question.info),
+            ..Questionv4::default()
+        };
+        let theme = |theme: ThemeJson| Themev4 {
+            name: theme.name,
+            info: info(theme.info),
+            questions: theme.questions.into_iter().map(question).collect(),
+            ..Themev4::default()
+        };
+        let round = |round: RoundJson| Roundv4 {
+            name: round.name,
+            kind: round.kind,
+            info: info(round.info),
+            themes: round.themes.into_iter().map(theme).collect(),
+            ..Roundv4::default()
+        };
+
+        Ok(Packagev4 {
+            name: package.name,
+            version: package.version,
+            id: package.id,
+            date: package.date,
+            publisher: package.publisher,
+            difficulty: package.difficulty,
+            language: package.language,
+            logo: package.logo,
+            restriction: package.restriction,
+            namespace: package.namespace,
+            info: package.info.into(),
+            rounds: package.rounds.into_iter().map(round).collect(),
+            tags: package.tags,
+            resources,
+        })
+    }
+
+    /// Write the package as a JSON file at `path`. With
+    /// [`JsonResourcesv4::Files`] resources are written next to it.
+    pub fn export_json(
+        &self,
+        path: impl AsRef<Path>,
+        resources: JsonResourcesv4,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+        if resources == JsonResourcesv4::Files {
+            let dir = path.parent().unwrap_or(Path::new(""));
+            for (id, bytes) in &self.resources {
+                let file = dir.join(id.path());
+                if let Some(parent) = file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(file, bytes)?;
+            }
+        }
+        fs::write(path, self.to_json(resources)?)
+    }
+
+    /// Read a JSON file at `path` with resource files next to it.
+    pub fn import_json(path: impl AsRef<Path>) -> Result<Packagev4, Error> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)?;
+        Self::from_json(&json, Some(path.parent().unwrap_or(Path::new(""))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::package_trait::QuestionsContainer;
+    use crate::v4::PackageTemplatev4;
+
+    /// Sorted property names of a JSON object.
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys = value.as_object().unwrap().keys().map(String::as_str).collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut package = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        let question = package.get_question_mut((0, 0, 0)).unwrap();
+        question.question_type.params =
+            Some(vec![Paramv4 { name: "cost".to_string(), body: None }]);
+        question.scenario = vec![
+            Atomv4 { body: "Что на фото?".to_string(), ..Atomv4::default() },
+            Atomv4 {
+                kind: AtomKindv4::Image, body: "@фото 1.png".to_string(), time: Some(5.0)
+            },
+        ];
+        let id = question.scenario[1].resource().unwrap();
+        package.resources.insert(id.clone(), Arc::from(b"png".as_slice()));
+
+        let json = package.to_json(JsonResourcesv4::Base64).unwrap();
+        assert_eq!(Packagev4::from_json(&json, None).unwrap(), package);
+
+        // the exported structure has exactly the properties of the schema
+        let schema = serde_json::from_str::<Value>(Packagev4::JSON_SCHEMA).unwrap();
+        let value = serde_json::from_str::<Value>(&json).unwrap();
+        let round = &value["rounds"][0];
+        let question = &round["themes"][0]["questions"][0];
+        assert_eq!(keys(&value), keys(&schema["properties"]));
+        assert_eq!(value["formatVersion"], schema["properties"]["formatVersion"]["const"]);
+        assert_eq!(keys(round), keys(&schema["$defs"]["round"]["properties"]));
+        assert_eq!(keys(&round["themes"][0]), keys(&schema["$defs"]["theme"]["properties"]));
+        assert_eq!(keys(question), keys(&schema["$defs"]["question"]["properties"]));
+        assert_eq!(keys(&question["scenario"][1]), keys(&schema["$defs"]["atom"]["properties"]));
+        assert_eq!(question["scenario"][1]["resource"], id.path());
+        assert_eq!(value["resources"][id.path()]["base64"], "cG5n");
+
+        let json = package.to_json(JsonResourcesv4::Files).unwrap();
+        assert!(Packagev4::from_json(&json, None).is_err());
+        let value = serde_json::from_str::<Value>(&json).unwrap();
+        assert_eq!(value["resources"][id.path()]["file"], id.path());
+
+        let dir = std::env::temp_dir().join(format!("opensi-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        package.export_json(dir.join("pack.json"), JsonResourcesv4::Files).unwrap();
+        assert_eq!(Packagev4::import_json(dir.join("pack.json")).unwrap(), package);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
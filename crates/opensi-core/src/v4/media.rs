@@ -0,0 +1,466 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
+
+use super::{AtomKindv4, Atomv4, Packagev4, ResourceIdv4};
+use crate::node::QuestionIdx;
+
+/// Settings of image optimisation, see [`ImageOptimizationv4::optimize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageOptimizationv4 {
+    /// Images are downscaled to fit into this width and height.
+    pub max_dimension: u32,
+    /// Quality of re-encoded JPEG images, from 1 to 100.
+    pub jpeg_quality: u8,
+}
+
+impl Default for ImageOptimizationv4 {
+    fn default() -> Self {
+        Self { max_dimension: 1920, jpeg_quality: 85 }
+    }
+}
+
+/// Image after [`ImageOptimizationv4::optimize`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptimizedImagev4 {
+    pub bytes: Vec<u8>,
+    /// Extension of the encoded format, either "jpg" or "png".
+    pub extension: &'static str,
+}
+
+impl OptimizedImagev4 {
+    /// File name with the extension of the optimized image.
+    pub fn file_name(&self, name: &str) -> String {
+        let stem = match name.rsplit_once('.') {
+            Some((stem, _)) => stem,
+            None => name,
+        };
+        format!("{stem}.{}", self.extension)
+    }
+}
+
+/// Sizes of images before and after [`Packagev4::optimize_images`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OptimizationReportv4 {
+    /// Amount of image resources in the package.
+    pub images: usize,
+    /// Amount of images which were replaced with optimized ones.
+    pub optimized: usize,
+    /// Images which couldn't be decoded, with the reason.
+    pub failed: Vec<(ResourceIdv4, String)>,
+    /// Total size of all images before optimisation.
+    pub bytes_before: usize,
+    /// Total size of all images after optimisation.
+    pub bytes_after: usize,
+}
+
+impl ImageOptimizationv4 {
+    /// Optimize an encoded image:
+    /// - Orientation from EXIF metadata is applied and the metadata is dropped;
+    /// - Images larger than [`Self::max_dimension`] are downscaled;
+    /// - Formats which SIGame doesn't support, e.g. WebP, BMP or GIF stills,
+    ///   are converted to JPEG, or PNG if they are transparent;
+    /// - JPEG images are re-encoded with [`Self::jpeg_quality`].
+    ///
+    /// Returns `None` if the image can't be made any smaller, or it's
+    /// an animated GIF, which would lose its animation.
+    pub fn optimize(&self, bytes: &[u8]) -> Result<Option<OptimizedImagev4>, Error> {
+        let reader = ImageReader::new(io::Cursor::new(bytes)).with_guessed_format()?;
+        let Some(format) = reader.format() else {
+            return Err(Error::new(ErrorKind::InvalidData, "Unknown image format"));
+        };
+        if format == ImageFormat::Gif && is_animated_gif(bytes) {
+            return Ok(None);
+        }
+
+        let mut decoder = reader.into_decoder().map_err(invalid_data)?;
+        let orientation = decoder.orientation().map_err(invalid_data)?;
+        let has_exif = decoder.exif_metadata().map_err(invalid_data)?.is_some();
+        let mut image = DynamicImage::from_decoder(decoder).map_err(invalid_data)?;
+
+        let is_supported = matches!(format, ImageFormat::Jpeg | ImageFormat::Png);
+        let is_large = image.width() > self.max_dimension || image.height() > self.max_dimension;
+        let must_change = !is_supported || is_large || has_exif;
+
+        image.apply_orientation(orientation);
+        if is_large {
+            image = image.resize(
+                self.max_dimension,
+                self.max_dimension,
+                image::imageops::FilterType::Lanczos3,
+            );
+        }
+
+        let keep_png = format == ImageFormat::Png || (!is_supported && image.color().has_alpha());
+        let optimized = if keep_png {
+            let mut bytes = vec![];
+            let encoder = image::codecs::png::PngEncoder::new_with_quality(
+                &mut bytes,
+                image::codecs::png::CompressionType::Best,
+                image::codecs::png::FilterType::Adaptive,
+            );
+            image.write_with_encoder(encoder).map_err(invalid_data)?;
+            OptimizedImagev4 { bytes, extension: "png" }
+        } else {
+            let mut bytes = vec![];
+            let quality = self.jpeg_quality.clamp(1, 100);
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, quality);
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(encoder)
+                .map_err(invalid_data)?;
+            OptimizedImagev4 { bytes, extension: "jpg" }
+        };
+
+        if !must_change && optimized.bytes.len() >= bytes.len() {
+            return Ok(None);
+        }
+        Ok(Some(optimized))
+    }
+}
+
+fn invalid_data(err: image::ImageError) -> Error {
+    Error::new(ErrorKind::InvalidData, err)
+}
+
+fn is_animated_gif(bytes: &[u8]) -> bool {
+    use image::AnimationDecoder;
+
+    image::codecs::gif::GifDecoder::new(io::Cursor::new(bytes))
+        .map(|decoder| decoder.into_frames().take(2).count() > 1)
+        .unwrap_or_default()
+}
+
+/// Size limits of package resources, in bytes. Resources bigger
+/// than the limit of their type aren't accepted by SIGame servers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaBudgetv4 {
+    pub max_image: usize,
+    pub max_audio: usize,
+    pub max_video: usize,
+    /// Limit of all resources of the package together.
+    pub max_total: usize,
+}
+
+impl Default for MediaBudgetv4 {
+    fn default() -> Self {
+        const MB: usize = 1024 * 1024;
+        Self { max_image: MB, max_audio: 5 * MB, max_video: 10 * MB, max_total: 100 * MB }
+    }
+}
+
+impl MediaBudgetv4 {
+    /// Size limit of a single resource, if there is one for its type.
+    pub fn limit(&self, id: &ResourceIdv4) -> Option<usize> {
+        match id {
+            ResourceIdv4::Image(_) => Some(self.max_image),
+            ResourceIdv4::Audio(_) => Some(self.max_audio),
+            ResourceIdv4::Video(_) => Some(self.max_video),
+            ResourceIdv4::Texts(_) => None,
+        }
+    }
+}
+
+/// Size of a single resource in [`BudgetReportv4`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceUsagev4 {
+    pub id: ResourceIdv4,
+    pub size: usize,
+    pub limit: Option<usize>,
+    /// Questions with atoms which use the resource.
+    pub questions: Vec<QuestionIdx>,
+}
+
+impl ResourceUsagev4 {
+    pub fn is_over_limit(&self) -> bool {
+        self.limit.is_some_and(|limit| self.size > limit)
+    }
+}
+
+/// Sizes of package resources compared to a [`MediaBudgetv4`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BudgetReportv4 {
+    pub budget: MediaBudgetv4,
+    pub images: usize,
+    pub audio: usize,
+    pub video: usize,
+    /// Size of all resources together.
+    pub total: usize,
+    /// All resources from the biggest to the smallest.
+    pub resources: Vec<ResourceUsagev4>,
+}
+
+impl BudgetReportv4 {
+    pub fn is_total_exceeded(&self) -> bool {
+        self.total > self.budget.max_total
+    }
+
+    /// Resources bigger than the limit of their type.
+    pub fn offenders(&self) -> impl Iterator<Item = &ResourceUsagev4> {
+        self.resources.iter().filter(|usage| usage.is_over_limit())
+    }
+
+    /// Package fits into the budget.
+    pub fn is_ok(&self) -> bool {
+        !self.is_total_exceeded() && self.offenders().next().is_none()
+    }
+}
+
+/// # Media resources of package.
+impl Packagev4 {
+    /// Compare sizes of resources with the `budget`.
+    pub fn check_budget(&self, budget: &MediaBudgetv4) -> BudgetReportv4 {
+        let mut questions = HashMap::<ResourceIdv4, Vec<QuestionIdx>>::new();
+        for (round_index, round) in self.rounds.iter().enumerate() {
+            for (theme_index, theme) in round.themes.iter().enumerate() {
+                for (index, question) in theme.questions.iter().enumerate() {
+                    let idx = QuestionIdx::from((round_index, theme_index, index));
+                    for id in question.scenario.iter().filter_map(|atom| atom.resource()) {
+                        let used_by = questions.entry(id).or_default();
+                        if !used_by.contains(&idx) {
+                            used_by.push(idx);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut report = BudgetReportv4 { budget: *budget, ..BudgetReportv4::default() };
+        for (id, bytes) in &self.resources {
+            let size = bytes.len();
+            match id {
+                ResourceIdv4::Image(_) => report.images += size,
+                ResourceIdv4::Audio(_) => report.audio += size,
+                ResourceIdv4::Video(_) => report.video += size,
+                ResourceIdv4::Texts(_) => {},
+            }
+            report.total += size;
+            report.resources.push(ResourceUsagev4 {
+                id: id.clone(),
+                size,
+                limit: budget.limit(id),
+                questions: questions.remove(id).unwrap_or_default(),
+            });
+        }
+        report
+            .resources
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.id.path().cmp(b.id.path())));
+
+        report
+    }
+
+    /// Replace every image resource with its optimized version. Images
+    /// converted to another format are renamed along with their atoms
+    /// and the package logo.
+    pub fn optimize_images(&mut self, options: &ImageOptimizationv4) -> OptimizationReportv4 {
+        let mut report = OptimizationReportv4::default();
+        let mut images = self
+            .resources
+            .iter()
+            .filter(|(id, _)| matches!(id, ResourceIdv4::Image(..)))
+            .map(|(id, bytes)| (id.clone(), bytes.clone()))
+            .collect::<Vec<_>>();
+        images.sort_by(|(a, _), (b, _)| a.path().cmp(b.path()));
+
+        let mut renames = HashMap::new();
+        for (id, bytes) in images {
+            report.images += 1;
+            report.bytes_before += bytes.len();
+
+            let optimized = match options.optimize(&bytes) {
+                Ok(Some(optimized)) => optimized,
+                Ok(None) => {
+                    report.bytes_after += bytes.len();
+                    continue;
+                },
+                Err(err) => {
+                    report.bytes_after += bytes.len();
+                    report.failed.push((id, err.to_string()));
+                    continue;
+                },
+            };
+
+            report.optimized += 1;
+            report.bytes_after += optimized.bytes.len();
+            let converted = ResourceIdv4::image(optimized.file_name(id.name()));
+            let mut new_id = converted.clone();
+            let mut counter = 1;
+            while new_id != id && self.resources.contains_key(&new_id) {
+                let Some(renamed) = converted.with_suffix(counter) else {
+                    break;
+                };
+                new_id = renamed;
+                counter += 1;
+            }
+
+            self.resources.remove(&id);
+            self.resources.insert(new_id.clone(), Arc::from(optimized.bytes.into_boxed_slice()));
+            if new_id != id {
+                renames.insert(id, new_id);
+            }
+        }
+
+        let mut logo =
+            self.logo.take().map(|logo| Atomv4 { kind: AtomKindv4::Image, body: logo, time: None });
+        let atoms = self
+            .rounds
+            .iter_mut()
+            .flat_map(|round| round.themes.iter_mut())
+            .flat_map(|theme| theme.questions.iter_mut())
+            .flat_map(|question| question.scenario.iter_mut())
+            .chain(logo.as_mut());
+        for atom in atoms {
+            if let Some(new_id) = atom.resource().and_then(|id| renames.get(&id)) {
+                atom.body = new_id.atom_body();
+            }
+        }
+        self.logo = logo.map(|logo| logo.body);
+
+        report
+    }
+}
+
+/// Media files read from a directory while a package is imported,
+/// e.g. from the YAML format or a spreadsheet.
+pub(super) struct MediaLoader<'a> {
+    pub(super) dir: &'a Path,
+    pub(super) resources: HashMap<ResourceIdv4, Arc<[u8]>>,
+}
+
+impl MediaLoader<'_> {
+    /// Read a media file at `path` and return the atom body, which
+    /// references it. Files with the same name but different content
+    /// get a numeric suffix.
+    pub(super) fn load(&mut self, kind: AtomKindv4, path: &str) -> Result<String, Error> {
+        let file = self.dir.join(path);
+        let bytes = fs::read(&file)
+            .map_err(|err| Error::new(err.kind(), format!("{}: {err}", file.display())))?;
+        let bytes = Arc::<[u8]>::from(bytes);
+        let name = Path::new(path).file_name().and_then(|name| name.to_str()).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput, format!("Invalid media path {path}"))
+        })?;
+
+        for suffix in 0.. {
+            let name = match (suffix, name.rsplit_once('.')) {
+                (0, _) => name.to_string(),
+                (_, Some((stem, extension))) => format!("{stem}_{suffix}.{extension}"),
+                (_, None) => format!("{name}_{suffix}"),
+            };
+            let atom = Atomv4 { kind, body: format!("@{name}"), time: None };
+            let id = atom.resource().ok_or_else(|| {
+                Error::new(ErrorKind::InvalidInput, format!("Text can't reference {path}"))
+            })?;
+            match self.resources.get(&id) {
+                Some(existing) if *existing != bytes => continue,
+                Some(_) => {},
+                None => {
+                    self.resources.insert(id, bytes);
+                },
+            }
+            return Ok(atom.body);
+        }
+        unreachable!("Suffixes are endless")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_trait::QuestionsContainer;
+    use crate::v4::PackageTemplatev4;
+
+    fn encode(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = vec![];
+        image.write_to(&mut io::Cursor::new(&mut bytes), format).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn optimize_image() {
+        let options = ImageOptimizationv4 { max_dimension: 100, jpeg_quality: 80 };
+
+        let large = encode(DynamicImage::new_rgb8(400, 200), ImageFormat::Png);
+        let optimized = options.optimize(&large).unwrap().unwrap();
+        assert_eq!(optimized.extension, "png");
+        let image = image::load_from_memory(&optimized.bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (100, 50));
+        // already optimized
+        assert_eq!(options.optimize(&optimized.bytes).unwrap(), None);
+
+        let bmp = encode(DynamicImage::new_rgb8(10, 10), ImageFormat::Bmp);
+        let optimized = options.optimize(&bmp).unwrap().unwrap();
+        assert_eq!(optimized.extension, "jpg");
+        assert_eq!(optimized.file_name("@photo.bmp"), "@photo.jpg");
+
+        let transparent = encode(DynamicImage::new_rgba8(10, 10), ImageFormat::Bmp);
+        assert_eq!(options.optimize(&transparent).unwrap().unwrap().extension, "png");
+
+        assert!(options.optimize(b"not an image").is_err());
+    }
+
+    #[test]
+    fn optimize_package_images() {
+        let mut package = PackageTemplatev4::default().build("Пак", "", "ru");
+        let bmp = encode(DynamicImage::new_rgb8(200, 200), ImageFormat::Bmp);
+        let bmp_size = bmp.len();
+        package.resources.insert(ResourceIdv4::image("a.bmp"), Arc::from(bmp.into_boxed_slice()));
+        package.resources.insert(ResourceIdv4::image("a.jpg"), Arc::from([0u8].as_slice()));
+        package.resources.insert(ResourceIdv4::audio("a.mp3"), Arc::from([0u8].as_slice()));
+        let question = package.get_question_mut((0, 0, 0)).unwrap();
+        question.scenario =
+            vec![Atomv4 { kind: AtomKindv4::Image, body: "@a.bmp".to_string(), time: None }];
+        package.logo = Some("@a.bmp".to_string());
+
+        let report = package.optimize_images(&ImageOptimizationv4::default());
+        assert_eq!(report.images, 2);
+        assert_eq!(report.optimized, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.bytes_before, bmp_size + 1);
+        assert!(report.bytes_after < report.bytes_before);
+
+        let atom = &package.get_question((0, 0, 0)).unwrap().scenario[0];
+        assert_eq!(atom.body, "@a_1.jpg");
+        assert!(package.get_resource(atom).is_some());
+        assert_eq!(package.logo.as_deref(), Some("@a_1.jpg"));
+        assert!(!package.resources.contains_key(&ResourceIdv4::image("a.bmp")));
+    }
+
+    #[test]
+    fn media_budget() {
+        let mut package = PackageTemplatev4::default().build("Пак", "", "ru");
+        let budget =
+            MediaBudgetv4 { max_image: 10, max_audio: 100, max_video: 100, max_total: 120 };
+        package.resources.insert(ResourceIdv4::image("big.png"), Arc::from([0u8; 20].as_slice()));
+        package.resources.insert(ResourceIdv4::image("small.png"), Arc::from([0u8; 5].as_slice()));
+        package.resources.insert(ResourceIdv4::audio("a.mp3"), Arc::from([0u8; 90].as_slice()));
+        for idx in [(0, 0, 0), (1, 2, 3)] {
+            package.get_question_mut(idx).unwrap().scenario =
+                vec![Atomv4 { kind: AtomKindv4::Image, body: "@big.png".to_string(), time: None }];
+        }
+
+        let report = package.check_budget(&budget);
+        assert_eq!((report.images, report.audio, report.video), (25, 90, 0));
+        assert_eq!(report.total, 115);
+        assert!(!report.is_total_exceeded());
+        assert_eq!(report.resources[0].id, ResourceIdv4::audio("a.mp3"));
+
+        let offenders = report.offenders().collect::<Vec<_>>();
+        assert_eq!(offenders.len(), 1);
+        assert_eq!(offenders[0].id, ResourceIdv4::image("big.png"));
+        assert_eq!(offenders[0].questions, vec![(0, 0, 0).into(), (1, 2, 3).into()]);
+        assert!(!report.is_ok());
+
+        let budget = MediaBudgetv4 { max_total: 100, ..MediaBudgetv4::default() };
+        let report = package.check_budget(&budget);
+        assert!(report.is_total_exceeded());
+        assert_eq!(report.offenders().count(), 0);
+    }
+}
@@ -1,7 +1,28 @@
 pub mod atom;
 pub mod components;
+pub mod diff;
+pub mod format;
+pub mod fragment;
+pub mod host_script;
+pub mod json;
+pub mod media;
 pub mod package;
+pub mod probe;
+pub mod spreadsheet;
+pub mod template;
+pub mod unpacked;
+pub mod waveform;
+pub mod yaml;
 
 pub use atom::*;
 pub use components::*;
+pub use diff::*;
+pub use format::*;
+pub use fragment::*;
+pub use json::*;
+pub use media::*;
 pub use package::*;
+pub use probe::*;
+pub use spreadsheet::*;
+pub use template::*;
+pub use waveform::*;
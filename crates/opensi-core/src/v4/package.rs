@@ -80,6 +80,12 @@ impl Packagev4 {
     }
 }
 
+impl Default for Packagev4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RoundContainer for Packagev4 {
     type Round = Roundv4;
 
@@ -95,13 +101,35 @@ impl RoundContainer for Packagev4 {
 /// # IO and resource methods
 impl Packagev4 {
     const CONTENT_TYPE_FILE_CONTENT: &'static str = r#"<?xml version="1.0" encoding="utf-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="xml" ContentType="si/xml" /></Types>"""#;
-    const XML_VERSION_ENCODING: &'static str = r#"<?xml version="1.0" encoding="utf-8"?>"#;
+    pub(super) const XML_VERSION_ENCODING: &'static str =
+        r#"<?xml version="1.0" encoding="utf-8"?>"#;
 
     pub fn get_resource(&self, atom: &Atomv4) -> Option<&Arc<[u8]>> {
         let resource = atom.resource()?;
         self.resources.get(&resource)
     }
 
+    /// Add a resource, renaming it with a numeric suffix if the package
+    /// already has a different resource with the same name. Returns the
+    /// id the resource was added with.
+    pub fn add_resource(&mut self, id: ResourceIdv4, bytes: Arc<[u8]>) -> ResourceIdv4 {
+        let mut new_id = id.clone();
+        let mut counter = 1;
+        while let Some(existing) = self.resources.get(&new_id) {
+            if existing == &bytes {
+                break;
+            }
+            let Some(renamed) = id.with_suffix(counter) else {
+                break;
+            };
+            new_id = renamed;
+            counter += 1;
+        }
+
+        self.resources.insert(new_id.clone(), bytes);
+        new_id
+    }
+
     // Expecting byte array of zip file
     pub fn from_zip_buffer(bytes: impl AsRef<[u8]>) -> Result<Packagev4, Error> {
         let cursor = io::Cursor::new(bytes);
@@ -171,9 +199,9 @@ impl Packagev4 {
         zip.write_all(Self::CONTENT_TYPE_FILE_CONTENT.as_ref())?;
 
         let resources = &self.resources;
-        for (key, value) in resources.into_iter() {
+        for (key, value) in resources.iter() {
             zip.start_file(key.path(), options)?;
-            zip.write_all(&value)?
+            zip.write_all(value)?
         }
 
         let result = zip.finish()?;
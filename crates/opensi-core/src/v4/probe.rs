@@ -0,0 +1,440 @@
+use super::{MediaFormatv4, Packagev4, ResourceIdv4};
+
+/// Codec of an audio or video stream.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Codecv4 {
+    Pcm,
+    FloatPcm,
+    Mp1,
+    Mp2,
+    Mp3,
+    Aac,
+    Ac3,
+    Vorbis,
+    Opus,
+    Flac,
+    H264,
+    H265,
+    Mpeg4,
+    Vp9,
+    Av1,
+    /// Codec unknown to the editor, with its tag.
+    Other(String),
+}
+
+impl Codecv4 {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Pcm => "PCM",
+            Self::FloatPcm => "PCM (float)",
+            Self::Mp1 => "MPEG Layer I",
+            Self::Mp2 => "MPEG Layer II",
+            Self::Mp3 => "MP3",
+            Self::Aac => "AAC",
+            Self::Ac3 => "AC-3",
+            Self::Vorbis => "Vorbis",
+            Self::Opus => "Opus",
+            Self::Flac => "FLAC",
+            Self::H264 => "H.264",
+            Self::H265 => "H.265",
+            Self::Mpeg4 => "MPEG-4 Visual",
+            Self::Vp9 => "VP9",
+            Self::Av1 => "AV1",
+            Self::Other(tag) => tag,
+        }
+    }
+
+    /// SIGame clients can play the codec on every platform.
+    pub fn is_supported(&self) -> bool {
+        matches!(self, Self::Pcm | Self::Mp3 | Self::Aac | Self::Vorbis | Self::Opus | Self::H264)
+    }
+
+    fn from_mp4_tag(tag: &[u8]) -> Self {
+        match tag {
+            b"avc1" | b"avc3" => Self::H264,
+            b"hvc1" | b"hev1" => Self::H265,
+            b"mp4v" => Self::Mpeg4,
+            b"vp09" => Self::Vp9,
+            b"av01" => Self::Av1,
+            b"mp4a" => Self::Aac,
+            b".mp3" => Self::Mp3,
+            b"Opus" => Self::Opus,
+            b"fLaC" => Self::Flac,
+            b"ac-3" | b"ec-3" => Self::Ac3,
+            _ => Self::Other(String::from_utf8_lossy(tag).trim().to_string()),
+        }
+    }
+}
+
+/// Technical information about an audio or video resource,
+/// read from its container headers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaProbev4 {
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+    /// Codecs of all streams.
+    pub codecs: Vec<Codecv4>,
+    /// Average bitrate in bits per second.
+    pub bitrate: Option<u64>,
+}
+
+impl MediaProbev4 {
+    /// Probe an MP3, Ogg, WAV, FLAC or MP4 file. Returns `None` for
+    /// other formats or broken headers.
+    pub fn probe(bytes: &[u8]) -> Option<Self> {
+        let mut probe = match MediaFormatv4::sniff(bytes)? {
+            MediaFormatv4::Mp3 => probe_mp3(bytes)?,
+            MediaFormatv4::Ogg => probe_ogg(bytes)?,
+            MediaFormatv4::Wav => probe_wav(bytes)?,
+            MediaFormatv4::Flac => probe_flac(bytes)?,
+            MediaFormatv4::Mp4 | MediaFormatv4::M4a => probe_mp4(bytes)?,
+            _ => return None,
+        };
+        if probe.bitrate.is_none() {
+            probe.bitrate = probe
+                .duration
+                .filter(|&duration| duration > 0.0)
+                .map(|duration| (bytes.len() as f64 * 8.0 / duration) as u64);
+        }
+        Some(probe)
+    }
+
+    /// Codecs which SIGame clients may fail to play.
+    pub fn unsupported_codecs(&self) -> impl Iterator<Item = &Codecv4> {
+        self.codecs.iter().filter(|codec| !codec.is_supported())
+    }
+}
+
+/// # Probing of audio and video resources.
+impl Packagev4 {
+    /// Technical information about an audio or video resource,
+    /// if the package has it and its headers are readable.
+    pub fn probe_resource(&self, id: &ResourceIdv4) -> Option<MediaProbev4> {
+        match id {
+            ResourceIdv4::Audio(_) | ResourceIdv4::Video(_) => {
+                MediaProbev4::probe(self.resources.get(id)?)
+            },
+            _ => None,
+        }
+    }
+}
+
+fn u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at.checked_add(2)?)?.try_into().ok()?))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+fn u64_be(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.get(at..at.checked_add(8)?)?.try_into().ok()?))
+}
+
+/// WAV: `fmt ` chunk describes the samples, `data` chunk holds them.
+fn probe_wav(bytes: &[u8]) -> Option<MediaProbev4> {
+    let mut format = None;
+    let mut data_size = None;
+    let mut at = 12;
+    while bytes.len().checked_sub(at).is_some_and(|rest| rest >= 8) {
+        let size = u32_le(bytes, at + 4)? as usize;
+        match &bytes[at..at + 4] {
+            b"fmt " => format = Some((u16_le(bytes, at + 8)?, u32_le(bytes, at + 16)?)),
+            b"data" => data_size = Some(size.min(bytes.len() - at - 8)),
+            _ => {},
+        }
+        at = at.saturating_add(size).saturating_add(8 + size % 2);
+    }
+
+    let (tag, byte_rate) = format?;
+    let codec = match tag {
+        0x0001 | 0xFFFE => Codecv4::Pcm,
+        0x0003 => Codecv4::FloatPcm,
+        0x0055 => Codecv4::Mp3,
+        tag => Codecv4::Other(format!("WAVE 0x{tag:04X}")),
+    };
+    let duration = data_size.filter(|_| byte_rate > 0).map(|size| size as f64 / byte_rate as f64);
+    Some(MediaProbev4 { duration, codecs: vec![codec], bitrate: Some(byte_rate as u64 * 8) })
+}
+
+/// MP3: the first frame header, with a Xing/Info header for VBR files.
+fn probe_mp3(bytes: &[u8]) -> Option<MediaProbev4> {
+    // skip ID3v2 tag
+    let mut start = 0;
+    if bytes.starts_with(b"ID3") {
+        let size = bytes.get(6..10)?.iter().fold(0usize, |size, &byte| size << 7 | byte as usize);
+        let footer = if bytes.get(5)? & 0x10 != 0 { 10 } else { 0 };
+        start = 10 + size + footer;
+    }
+    let offset = bytes
+        .get(start..)?
+        .windows(2)
+        .position(|sync| sync[0] == 0xFF && sync[1] & 0xE0 == 0xE0 && sync[1] & 0x06 != 0)?;
+    let at = start + offset;
+    let header = bytes.get(at..at + 4)?;
+
+    let version = (header[1] >> 3) & 0b11; // 0 - MPEG 2.5, 2 - MPEG 2, 3 - MPEG 1
+    let layer = (header[1] >> 1) & 0b11; // 1 - III, 2 - II, 3 - I
+    let bitrate_index = (header[2] >> 4) as usize;
+    let rate_index = ((header[2] >> 2) & 0b11) as usize;
+    let is_mono = header[3] >> 6 == 0b11;
+    if version == 1 || rate_index == 3 || bitrate_index == 0 || bitrate_index == 15 {
+        return None;
+    }
+
+    const BITRATES_V1: [[u32; 15]; 3] = [
+        [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+        [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+        [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+    ];
+    const BITRATES_V2: [[u32; 15]; 2] = [
+        [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    ];
+    let bitrate = match (version, layer) {
+        (3, layer) => BITRATES_V1[3 - layer as usize][bitrate_index],
+        (_, 3) => BITRATES_V2[0][bitrate_index],
+        _ => BITRATES_V2[1][bitrate_index],
+    } * 1000;
+    let sample_rate = [44100, 48000, 32000][rate_index]
+        >> match version {
+            3 => 0,
+            2 => 1,
+            _ => 2,
+        };
+    let samples_per_frame = match (version, layer) {
+        (_, 3) => 384,
+        (3, _) | (_, 2) => 1152,
+        _ => 576,
+    };
+    let codec = match layer {
+        3 => Codecv4::Mp1,
+        2 => Codecv4::Mp2,
+        _ => Codecv4::Mp3,
+    };
+
+    // Xing/Info header with the amount of frames follows the side information
+    let side_info = match (version == 3, is_mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = at + 4 + side_info;
+    let frames = match bytes.get(xing..xing + 4) {
+        Some(b"Xing" | b"Info") if u32_be(bytes, xing + 4)? & 1 != 0 => u32_be(bytes, xing + 8),
+        _ => None,
+    };
+
+    let (duration, bitrate) = match frames {
+        Some(frames) => (frames as f64 * samples_per_frame as f64 / sample_rate as f64, None),
+        None => ((bytes.len() - at) as f64 * 8.0 / bitrate as f64, Some(bitrate as u64)),
+    };
+    Some(MediaProbev4 { duration: Some(duration), codecs: vec![codec], bitrate })
+}
+
+/// Ogg: codec from the first packet, duration from the granule
+/// position of the last page.
+fn probe_ogg(bytes: &[u8]) -> Option<MediaProbev4> {
+    let segments = *bytes.get(26)? as usize;
+    let packet = bytes.get(27 + segments..)?;
+
+    let (codec, rate, pre_skip, bitrate) = if packet.starts_with(b"\x01vorbis") {
+        let bitrate =
+            u32_le(packet, 20).filter(|&bitrate| bitrate > 0 && bitrate < i32::MAX as u32);
+        (Codecv4::Vorbis, u32_le(packet, 12)?, 0, bitrate.map(u64::from))
+    } else if packet.starts_with(b"OpusHead") {
+        (Codecv4::Opus, 48000, u16_le(packet, 10)? as u64, None)
+    } else if packet.starts_with(b"\x7FFLAC") {
+        let info = packet.get(17..30)?;
+        let rate = (info[10] as u32) << 12 | (info[11] as u32) << 4 | (info[12] as u32) >> 4;
+        (Codecv4::Flac, rate, 0, None)
+    } else {
+        return Some(MediaProbev4 {
+            codecs: vec![Codecv4::Other("Ogg".to_string())],
+            ..MediaProbev4::default()
+        });
+    };
+
+    let last_page = bytes.windows(4).rposition(|capture| capture == b"OggS")?;
+    let granule = bytes.get(last_page + 6..last_page + 14)?;
+    let granule = u64::from_le_bytes(granule.try_into().ok()?);
+    let duration = (rate > 0 && granule != u64::MAX)
+        .then(|| granule.saturating_sub(pre_skip) as f64 / rate as f64);
+    Some(MediaProbev4 { duration, codecs: vec![codec], bitrate })
+}
+
+/// FLAC: sample rate and amount of samples from STREAMINFO.
+fn probe_flac(bytes: &[u8]) -> Option<MediaProbev4> {
+    let info = bytes.get(8..26)?;
+    let rate = (info[10] as u64) << 12 | (info[11] as u64) << 4 | (info[12] as u64) >> 4;
+    let samples = ((info[13] & 0x0F) as u64) << 32 | u32_be(info, 14)? as u64;
+    let duration = (rate > 0 && samples > 0).then(|| samples as f64 / rate as f64);
+    Some(MediaProbev4 { duration, codecs: vec![Codecv4::Flac], bitrate: None })
+}
+
+/// Boxes of an MP4 file in `bytes`, as their type and content.
+fn mp4_boxes(bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        let size = u32_be(bytes, at)? as usize;
+        let kind = bytes.get(at.checked_add(4)?..at.checked_add(8)?)?;
+        let (header, size) = match size {
+            0 => (8, bytes.len().checked_sub(at)?),
+            1 => (16, usize::try_from(u64_be(bytes, at.checked_add(8)?)?).ok()?),
+            size => (8, size),
+        };
+        if size < header {
+            return None;
+        }
+        let end = at.checked_add(size)?;
+        let content = bytes.get(at.checked_add(header)?..end.min(bytes.len()))?;
+        at = end;
+        Some((kind, content))
+    })
+}
+
+fn mp4_child<'a>(bytes: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    mp4_boxes(bytes).find(|(child, _)| *child == kind).map(|(_, content)| content)
+}
+
+/// MP4: duration from the movie header, codecs from sample
+/// descriptions of every track.
+fn probe_mp4(bytes: &[u8]) -> Option<MediaProbev4> {
+    let moov = mp4_child(bytes, b"moov")?;
+
+    let mvhd = mp4_child(moov, b"mvhd")?;
+    let (timescale, duration) = match mvhd.first()? {
+        1 => (u32_be(mvhd, 20)?, u64_be(mvhd, 24)?),
+        _ => (u32_be(mvhd, 12)?, u32_be(mvhd, 16)? as u64),
+    };
+    let duration = (timescale > 0).then(|| duration as f64 / timescale as f64);
+
+    let codecs = mp4_boxes(moov)
+        .filter(|(kind, _)| *kind == b"trak")
+        .filter_map(|(_, trak)| {
+            let stbl = mp4_child(mp4_child(mp4_child(trak, b"mdia")?, b"minf")?, b"stbl")?;
+            let stsd = mp4_child(stbl, b"stsd")?;
+            stsd.get(12..16).map(Codecv4::from_mp4_tag)
+        })
+        .collect();
+
+    Some(MediaProbev4 { duration, codecs, bitrate: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(kind: &[u8], content: &[u8]) -> Vec<u8> {
+        let mut bytes = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(content);
+        bytes
+    }
+
+    #[test]
+    fn probe_wav_file() {
+        let mut bytes = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+        // PCM, mono, 8000 Hz, 16000 bytes per second
+        bytes.extend_from_slice(&[1, 0, 1, 0, 0x40, 0x1F, 0, 0, 0x80, 0x3E, 0, 0, 2, 0, 16, 0]);
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&32000u32.to_le_bytes());
+        bytes.resize(bytes.len() + 32000, 0);
+
+        let probe = MediaProbev4::probe(&bytes).unwrap();
+        assert_eq!(probe.duration, Some(2.0));
+        assert_eq!(probe.codecs, vec![Codecv4::Pcm]);
+        assert_eq!(probe.bitrate, Some(128_000));
+        assert_eq!(probe.unsupported_codecs().count(), 0);
+    }
+
+    #[test]
+    fn probe_mp3_file() {
+        // MPEG 1 Layer III, 128 kbit/s, 44100 Hz, stereo
+        let frame = [0xFF, 0xFB, 0x90, 0x00];
+        let mut bytes = frame.to_vec();
+        bytes.resize(16_000, 0);
+
+        let probe = MediaProbev4::probe(&bytes).unwrap();
+        assert_eq!(probe.codecs, vec![Codecv4::Mp3]);
+        assert_eq!(probe.bitrate, Some(128_000));
+        assert_eq!(probe.duration, Some(1.0));
+
+        // VBR with Xing header and 100 frames
+        let mut bytes = frame.to_vec();
+        bytes.resize(36, 0);
+        bytes.extend_from_slice(b"Xing\0\0\0\x01\0\0\0\x64");
+        bytes.resize(16_000, 0);
+        let probe = MediaProbev4::probe(&bytes).unwrap();
+        let duration = probe.duration.unwrap();
+        assert!((duration - 100.0 * 1152.0 / 44100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn probe_ogg_file() {
+        let mut page = b"OggS\0\x02".to_vec();
+        page.extend_from_slice(&0u64.to_le_bytes());
+        page.resize(26, 0);
+        page.extend_from_slice(&[1, 30]);
+        page.extend_from_slice(b"\x01vorbis\0\0\0\0\x02");
+        page.extend_from_slice(&44100u32.to_le_bytes());
+        page.extend_from_slice(&0u32.to_le_bytes());
+        page.extend_from_slice(&96_000u32.to_le_bytes());
+        page.resize(27 + 1 + 30, 0);
+
+        let mut last = b"OggS\0\x04".to_vec();
+        last.extend_from_slice(&(44100u64 * 3).to_le_bytes());
+        last.resize(28, 0);
+
+        let bytes = [page, last].concat();
+        let probe = MediaProbev4::probe(&bytes).unwrap();
+        assert_eq!(probe.codecs, vec![Codecv4::Vorbis]);
+        assert_eq!(probe.duration, Some(3.0));
+        assert_eq!(probe.bitrate, Some(96_000));
+    }
+
+    #[test]
+    fn probe_mp4_file() {
+        let mut mvhd = vec![0; 12];
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        mvhd.extend_from_slice(&2500u32.to_be_bytes());
+        mvhd.resize(100, 0);
+
+        let track = |codec: &[u8]| {
+            let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 16];
+            stsd.extend_from_slice(codec);
+            stsd.resize(24, 0);
+            let stbl = mp4_box(b"stbl", &mp4_box(b"stsd", &stsd));
+            mp4_box(b"trak", &mp4_box(b"mdia", &mp4_box(b"minf", &stbl)))
+        };
+        let moov = [mp4_box(b"mvhd", &mvhd), track(b"hvc1"), track(b"mp4a")].concat();
+        let bytes = [
+            mp4_box(b"ftyp", b"isom\0\0\0\0"),
+            mp4_box(b"mdat", &[0; 64]),
+            mp4_box(b"moov", &moov),
+        ]
+        .concat();
+
+        let probe = MediaProbev4::probe(&bytes).unwrap();
+        assert_eq!(probe.duration, Some(2.5));
+        assert_eq!(probe.codecs, vec![Codecv4::H265, Codecv4::Aac]);
+        assert_eq!(probe.unsupported_codecs().collect::<Vec<_>>(), vec![&Codecv4::H265]);
+    }
+
+    #[test]
+    fn mp4_box_offsets_overflow() {
+        let mut bytes = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+        bytes.extend_from_slice(b"mdat");
+        bytes.extend_from_slice(&(u64::MAX - 18).to_be_bytes());
+        bytes.extend_from_slice(&[0; 16]);
+
+        let boxes = mp4_boxes(&bytes).map(|(kind, _)| kind).collect::<Vec<_>>();
+        assert_eq!(boxes, vec![b"ftyp", b"mdat"]);
+        assert!(MediaProbev4::probe(&bytes).is_none());
+    }
+}
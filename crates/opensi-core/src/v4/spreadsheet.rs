@@ -0,0 +1,782 @@
+use std::{
+    collections::HashMap,
+    io::{self, Error, ErrorKind, Read, Write},
+    path::Path,
+};
+
+use quick_xml::{Reader, events::Event};
+use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use super::{
+    AtomKindv4, Atomv4, Infov4, MediaFormatv4, Packagev4, Questionv4, Roundv4, Themev4,
+    media::MediaLoader,
+};
+
+/// Max amount of rows in an XLSX sheet.
+const MAX_ROWS: usize = 1_048_576;
+/// Max amount of columns in an XLSX sheet, up to `XFD`.
+const MAX_COLUMNS: usize = 16_384;
+
+/// Table of cells read from a CSV or XLSX file, row by row.
+/// Rows may have different length.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Spreadsheetv4 {
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Spreadsheetv4 {
+    /// Read an XLSX or a CSV file, depending on its content.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.starts_with(b"PK\x03\x04") {
+            Self::from_xlsx(bytes)
+        } else {
+            Self::from_csv(bytes)
+        }
+    }
+
+    /// Read a CSV file. The delimiter is `,`, `;` or a tab, whichever
+    /// appears the most in the first line.
+    pub fn from_csv(bytes: &[u8]) -> Result<Self, Error> {
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+        let first_line = bytes.split(|&byte| byte == b'\n').next().unwrap_or_default();
+        let delimiter = b",;\t"
+            .iter()
+            .copied()
+            .max_by_key(|&delimiter| first_line.iter().filter(|&&byte| byte == delimiter).count())
+            .unwrap_or(b',');
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .from_reader(bytes);
+        let mut rows = vec![];
+        for record in reader.byte_records() {
+            let record = record.map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            rows.push(
+                record.iter().map(|cell| String::from_utf8_lossy(cell).into_owned()).collect(),
+            );
+        }
+        Ok(Self { rows })
+    }
+
+    /// Write a CSV file with a BOM, so spreadsheet editors detect UTF-8.
+    pub fn to_csv(&self) -> Result<Vec<u8>, Error> {
+        let mut writer =
+            csv::WriterBuilder::new().flexible(true).from_writer(b"\xEF\xBB\xBF".to_vec());
+        for row in &self.rows {
+            writer.write_record(row).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        }
+        writer.into_inner().map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Read the first sheet of an XLSX file.
+    pub fn from_xlsx(bytes: &[u8]) -> Result<Self, Error> {
+        let mut zip = ZipArchive::new(io::Cursor::new(bytes))?;
+        let shared_strings = match read_zip_file(&mut zip, "xl/sharedStrings.xml") {
+            Ok(xml) => read_shared_strings(&xml)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err),
+        };
+        let sheet = first_sheet_path(&mut zip).unwrap_or("xl/worksheets/sheet1.xml".to_string());
+        let xml = read_zip_file(&mut zip, &sheet)?;
+        read_sheet(&xml, &shared_strings)
+    }
+
+    /// Write a single sheet XLSX file. Whole numbers are written
+    /// as numbers, everything else as text.
+    pub fn to_xlsx(&self) -> Result<Vec<u8>, Error> {
+        let mut sheet = String::from(XLSX_XML_HEADER);
+        sheet.push_str(
+            r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+        );
+        for (row_index, row) in self.rows.iter().enumerate() {
+            sheet.push_str(&format!(r#"<row r="{}">"#, row_index + 1));
+            for (column, cell) in row.iter().enumerate() {
+                if cell.is_empty() {
+                    continue;
+                }
+                let reference = format!("{}{}", column_name(column), row_index + 1);
+                if is_whole_number(cell) {
+                    sheet.push_str(&format!(r#"<c r="{reference}"><v>{cell}</v></c>"#));
+                } else {
+                    sheet.push_str(&format!(
+                        r#"<c r="{reference}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                        quick_xml::escape::escape(cell.as_str())
+                    ));
+                }
+            }
+            sheet.push_str("</row>");
+        }
+        sheet.push_str("</sheetData></worksheet>");
+
+        let files = [
+            ("[Content_Types].xml", XLSX_CONTENT_TYPES),
+            ("_rels/.rels", XLSX_RELS),
+            ("xl/workbook.xml", XLSX_WORKBOOK),
+            ("xl/_rels/workbook.xml.rels", XLSX_WORKBOOK_RELS),
+            ("xl/worksheets/sheet1.xml", sheet.as_str()),
+        ];
+        let mut zip = ZipWriter::new(io::Cursor::new(vec![]));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, contents) in files {
+            zip.start_file(name, options)?;
+            if !contents.starts_with(XLSX_XML_HEADER) {
+                zip.write_all(XLSX_XML_HEADER.as_bytes())?;
+            }
+            zip.write_all(contents.as_bytes())?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+
+    /// Amount of columns in the widest row.
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or_default()
+    }
+}
+
+const XLSX_XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n";
+const XLSX_CONTENT_TYPES: &str = r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#;
+const XLSX_RELS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+const XLSX_WORKBOOK: &str = r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Вопросы" sheetId="1" r:id="rId1"/></sheets></workbook>"#;
+const XLSX_WORKBOOK_RELS: &str = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#;
+
+fn read_zip_file(zip: &mut ZipArchive<io::Cursor<&[u8]>>, name: &str) -> Result<String, Error> {
+    let mut file = zip.by_name(name).map_err(|e| match e {
+        zip::result::ZipError::FileNotFound => Error::new(ErrorKind::NotFound, name.to_string()),
+        e => Error::new(ErrorKind::InvalidData, e),
+    })?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn xml_error(err: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::new(ErrorKind::InvalidData, err)
+}
+
+fn attribute(event: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+    event
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.as_ref() == name.as_bytes())
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// Path of the first sheet in the archive, found through the workbook relations.
+fn first_sheet_path(zip: &mut ZipArchive<io::Cursor<&[u8]>>) -> Option<String> {
+    let workbook = read_zip_file(zip, "xl/workbook.xml").ok()?;
+    let mut reader = Reader::from_str(&workbook);
+    let id = loop {
+        match reader.read_event().ok()? {
+            Event::Start(event) | Event::Empty(event)
+                if event.local_name().as_ref() == b"sheet" =>
+            {
+                break attribute(&event, "r:id")?;
+            },
+            Event::Eof => return None,
+            _ => {},
+        }
+    };
+
+    let relations = read_zip_file(zip, "xl/_rels/workbook.xml.rels").ok()?;
+    let mut reader = Reader::from_str(&relations);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(event) | Event::Empty(event)
+                if event.local_name().as_ref() == b"Relationship"
+                    && attribute(&event, "Id").as_ref() == Some(&id) =>
+            {
+                let target = attribute(&event, "Target")?;
+                return Some(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{target}"),
+                });
+            },
+            Event::Eof => return None,
+            _ => {},
+        }
+    }
+}
+
+fn read_shared_strings(xml: &str) -> Result<Vec<String>, Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut strings = vec![];
+    let mut in_text = false;
+    // phonetic hints of east asian text are not a part of the string
+    let mut in_phonetic = false;
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(event) => match event.local_name().as_ref() {
+                b"si" => strings.push(String::new()),
+                b"t" => in_text = !in_phonetic,
+                b"rPh" => in_phonetic = true,
+                _ => {},
+            },
+            Event::End(event) => match event.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"rPh" => in_phonetic = false,
+                _ => {},
+            },
+            Event::Empty(event) if event.local_name().as_ref() == b"si" => {
+                strings.push(String::new());
+            },
+            Event::Text(text) if in_text => {
+                if let Some(string) = strings.last_mut() {
+                    string.push_str(&text.unescape().map_err(xml_error)?);
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+    Ok(strings)
+}
+
+fn read_sheet(xml: &str, shared_strings: &[String]) -> Result<Spreadsheetv4, Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut rows: Vec<Vec<String>> = vec![];
+    let mut row = 0;
+    let mut column = 0;
+    let mut cell_type = None::<String>;
+    let mut value = None::<String>;
+    let mut in_value = false;
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(event) | Event::Empty(event) if event.local_name().as_ref() == b"row" => {
+                row = match attribute(&event, "r") {
+                    Some(r) => row_index(&r).ok_or_else(|| invalid_reference(&r))?,
+                    None => rows.len(),
+                };
+                column = 0;
+            },
+            Event::Start(event) if event.local_name().as_ref() == b"c" => {
+                if let Some(r) = attribute(&event, "r") {
+                    column = column_index(&r).ok_or_else(|| invalid_reference(&r))?;
+                }
+                cell_type = attribute(&event, "t");
+                value = None;
+            },
+            Event::Empty(event) if event.local_name().as_ref() == b"c" => {
+                if let Some(r) = attribute(&event, "r") {
+                    column = column_index(&r).ok_or_else(|| invalid_reference(&r))?;
+                }
+                column += 1;
+            },
+            Event::Start(event) if matches!(event.local_name().as_ref(), b"v" | b"t") => {
+                in_value = true;
+            },
+            Event::End(event) if matches!(event.local_name().as_ref(), b"v" | b"t") => {
+                in_value = false;
+            },
+            Event::Text(text) if in_value => {
+                value.get_or_insert_default().push_str(&text.unescape().map_err(xml_error)?);
+            },
+            Event::End(event) if event.local_name().as_ref() == b"c" => {
+                let cell = match (cell_type.as_deref(), value.take()) {
+                    (_, None) => String::new(),
+                    (Some("s"), Some(index)) => index
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| shared_strings.get(index))
+                        .cloned()
+                        .unwrap_or_default(),
+                    (Some("b"), Some(value)) => {
+                        if value == "1" {
+                            "TRUE".to_string()
+                        } else {
+                            "FALSE".to_string()
+                        }
+                    },
+                    (None | Some("n"), Some(value)) => number_text(&value),
+                    (_, Some(value)) => value,
+                };
+                if !cell.is_empty() {
+                    if row >= MAX_ROWS || column >= MAX_COLUMNS {
+                        return Err(Error::new(ErrorKind::InvalidData, "Sheet is too large"));
+                    }
+                    if rows.len() <= row {
+                        rows.resize(row + 1, vec![]);
+                    }
+                    let cells = &mut rows[row];
+                    if cells.len() <= column {
+                        cells.resize(column + 1, String::new());
+                    }
+                    cells[column] = cell;
+                }
+                column += 1;
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+    Ok(Spreadsheetv4 { rows })
+}
+
+/// Numbers are stored as floats, but prices should read as `100`, not `100.0`.
+fn number_text(value: &str) -> String {
+    match value.trim().parse::<f64>() {
+        Ok(number) if number.fract() == 0.0 && number.abs() < 1e15 => (number as i64).to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn is_whole_number(cell: &str) -> bool {
+    (cell == "0" || !cell.starts_with('0'))
+        && (1..=15).contains(&cell.len())
+        && cell.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// Zero-based row index of a row number, up to [`MAX_ROWS`].
+fn row_index(number: &str) -> Option<usize> {
+    number.parse::<usize>().ok().filter(|&number| number <= MAX_ROWS)?.checked_sub(1)
+}
+
+/// Zero-based column index of a cell reference, e.g. `AB12`,
+/// up to [`MAX_COLUMNS`].
+fn column_index(reference: &str) -> Option<usize> {
+    let mut letters = reference.bytes().take_while(u8::is_ascii_alphabetic);
+    let index = letters.try_fold(0usize, |index, letter| {
+        index.checked_mul(26)?.checked_add((letter.to_ascii_uppercase() - b'A') as usize + 1)
+    })?;
+    index.checked_sub(1).filter(|&index| index < MAX_COLUMNS)
+}
+
+fn invalid_reference(reference: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Invalid cell reference: {reference}"))
+}
+
+/// Column letters of a zero-based column index, e.g. `AB`.
+fn column_name(mut index: usize) -> String {
+    let mut name = vec![];
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Meaning of a spreadsheet column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpreadsheetColumnv4 {
+    Round,
+    Theme,
+    Price,
+    Question,
+    Answer,
+    /// Wrong answers separated by `;`.
+    WrongAnswers,
+    Author,
+    Source,
+    /// File name of a media file in the media folder.
+    Media,
+}
+
+impl SpreadsheetColumnv4 {
+    /// All columns in the order of exported spreadsheets.
+    pub const ALL: [Self; 9] = [
+        Self::Round,
+        Self::Theme,
+        Self::Price,
+        Self::Question,
+        Self::Answer,
+        Self::WrongAnswers,
+        Self::Author,
+        Self::Source,
+        Self::Media,
+    ];
+
+    /// Header of the column in exported spreadsheets.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Round => "Раунд",
+            Self::Theme => "Тема",
+            Self::Price => "Цена",
+            Self::Question => "Вопрос",
+            Self::Answer => "Ответ",
+            Self::WrongAnswers => "Неправильные ответы",
+            Self::Author => "Автор",
+            Self::Source => "Источник",
+            Self::Media => "Медиа",
+        }
+    }
+
+    /// Lowercase headers which are recognized as the column.
+    fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Self::Round => &["раунд", "round"],
+            Self::Theme => &["тема", "theme", "topic", "category"],
+            Self::Price => &["цена", "стоимость", "price", "value", "points"],
+            Self::Question => &["вопрос", "текст вопроса", "question", "text"],
+            Self::Answer => &["ответ", "правильный ответ", "answer", "right answer"],
+            Self::WrongAnswers => {
+                &["неправильные ответы", "неверные ответы", "wrong answers", "wrong"]
+            },
+            Self::Author => &["автор", "авторы", "author", "authors"],
+            Self::Source => &["источник", "источники", "source", "sources"],
+            Self::Media => &["медиа", "файл", "media", "file"],
+        }
+    }
+}
+
+/// Columns of a spreadsheet mapped onto their meaning.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnMappingv4 {
+    /// Meaning of every column, `None` for ignored ones.
+    pub columns: Vec<Option<SpreadsheetColumnv4>>,
+    /// The first row is a header and is not imported.
+    pub header: bool,
+}
+
+impl ColumnMappingv4 {
+    /// Guess the mapping by the first row. If no header is recognized,
+    /// columns are expected in the order of exported spreadsheets.
+    pub fn guess(sheet: &Spreadsheetv4) -> Self {
+        let width = sheet.width();
+        let headers = sheet.rows.first().cloned().unwrap_or_default();
+        let mut columns = vec![None; width];
+        for (column, header) in headers.iter().enumerate() {
+            let header = header.trim().to_lowercase();
+            let meaning = SpreadsheetColumnv4::ALL
+                .into_iter()
+                .find(|meaning| meaning.aliases().contains(&header.as_str()));
+            if meaning.is_some_and(|meaning| !columns.contains(&Some(meaning))) {
+                columns[column] = meaning;
+            }
+        }
+
+        if columns.iter().any(Option::is_some) {
+            return Self { columns, header: true };
+        }
+        let columns = (0..width).map(|column| SpreadsheetColumnv4::ALL.get(column).copied());
+        Self { columns: columns.collect(), header: false }
+    }
+
+    fn index(&self, column: SpreadsheetColumnv4) -> Option<usize> {
+        self.columns.iter().position(|meaning| *meaning == Some(column))
+    }
+}
+
+/// Result of a spreadsheet import.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpreadsheetReportv4 {
+    pub questions: usize,
+    pub rounds: usize,
+    pub themes: usize,
+    /// Media files which were not found or could not be read.
+    pub missing_media: Vec<String>,
+}
+
+/// Max price of an imported question, higher prices are capped.
+/// SIGame stores prices as 32-bit integers.
+const MAX_PRICE: usize = i32::MAX as usize;
+
+/// Separator of several answers, authors or sources in a cell.
+const LIST_SEPARATOR: char = ';';
+
+fn split_list(cell: &str) -> Vec<String> {
+    cell.split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// # Spreadsheet import and export.
+///
+/// Every row of a spreadsheet is a question. Empty round and theme
+/// cells repeat the previous row, so a theme can be written once.
+impl Packagev4 {
+    /// Add questions from a spreadsheet into matching rounds and themes,
+    /// creating missing ones. Media files are read from `media_dir`.
+    pub fn import_spreadsheet(
+        &mut self,
+        sheet: &Spreadsheetv4,
+        mapping: &ColumnMappingv4,
+        media_dir: Option<&Path>,
+    ) -> SpreadsheetReportv4 {
+        let mut report = SpreadsheetReportv4::default();
+        let mut media = media_dir.map(|dir| MediaLoader { dir, resources: HashMap::new() });
+        if let Some(media) = &mut media {
+            media.resources = std::mem::take(&mut self.resources);
+        }
+
+        let mut round_name = Roundv4::default().name;
+        let mut theme_name = Themev4::default().name;
+        for row in sheet.rows.iter().skip(mapping.header as usize) {
+            let cell = |column| {
+                mapping
+                    .index(column)
+                    .and_then(|index| row.get(index))
+                    .map_or("", |cell| cell.trim())
+            };
+            if row.iter().all(|cell| cell.trim().is_empty()) {
+                continue;
+            }
+            if !cell(SpreadsheetColumnv4::Round).is_empty() {
+                round_name = cell(SpreadsheetColumnv4::Round).to_string();
+            }
+            if !cell(SpreadsheetColumnv4::Theme).is_empty() {
+                theme_name = cell(SpreadsheetColumnv4::Theme).to_string();
+            }
+
+            let round_index = match self.rounds.iter().rposition(|round| round.name == round_name) {
+                Some(index) => index,
+                None => {
+                    report.rounds += 1;
+                    self.rounds.push(Roundv4 { name: round_name.clone(), ..Roundv4::default() });
+                    self.rounds.len() - 1
+                },
+            };
+            let round = &mut self.rounds[round_index];
+            let theme_index = match round.themes.iter().rposition(|theme| theme.name == theme_name)
+            {
+                Some(index) => index,
+                None => {
+                    report.themes += 1;
+                    round.themes.push(Themev4 {
+                        name: theme_name.clone(),
+                        questions: vec![],
+                        ..Themev4::default()
+                    });
+                    round.themes.len() - 1
+                },
+            };
+            let theme = &mut round.themes[theme_index];
+
+            let price = cell(SpreadsheetColumnv4::Price).replace(char::is_whitespace, "");
+            let price =
+                price.parse::<f64>().ok().filter(|price| price.is_finite() && *price >= 0.0);
+            let price = price.map_or_else(
+                || {
+                    theme
+                        .questions
+                        .last()
+                        .map_or(100, |question| question.price.saturating_add(100))
+                },
+                |price| price.round().min(MAX_PRICE as f64) as usize,
+            );
+
+            let mut scenario = vec![];
+            let text = cell(SpreadsheetColumnv4::Question);
+            if !text.is_empty() {
+                scenario.push(Atomv4 { body: text.to_string(), ..Atomv4::default() });
+            }
+            let file = cell(SpreadsheetColumnv4::Media);
+            if !file.is_empty() {
+                match media.as_mut().and_then(|media| media_atom(media, file)) {
+                    Some(atom) => scenario.push(atom),
+                    None => report.missing_media.push(file.to_string()),
+                }
+            }
+
+            let authors = split_list(cell(SpreadsheetColumnv4::Author));
+            let sources = split_list(cell(SpreadsheetColumnv4::Source));
+            let info = (!authors.is_empty() || !sources.is_empty()).then(|| Infov4 {
+                authors,
+                sources,
+                ..Infov4::default()
+            });
+
+            theme.questions.push(Questionv4 {
+                price,
+                scenario,
+                right: split_list(cell(SpreadsheetColumnv4::Answer)),
+                wrong: split_list(cell(SpreadsheetColumnv4::WrongAnswers)),
+                info,
+                ..Questionv4::default()
+            });
+            report.questions += 1;
+        }
+
+        if let Some(media) = media {
+            self.resources = media.resources;
+        }
+        report
+    }
+
+    /// Export every question into a spreadsheet with a header and
+    /// columns in the order of [`SpreadsheetColumnv4::ALL`]. Media
+    /// files are referenced by their names.
+    pub fn to_spreadsheet(&self) -> Spreadsheetv4 {
+        let header = SpreadsheetColumnv4::ALL.iter().map(|column| column.name().to_string());
+        let mut rows = vec![header.collect::<Vec<_>>()];
+        for round in &self.rounds {
+            for theme in &round.themes {
+                for question in &theme.questions {
+                    let text = question
+                        .scenario
+                        .iter()
+                        .filter(|atom| atom.kind == AtomKindv4::Text)
+                        .map(|atom| atom.body.as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    let media = question
+                        .scenario
+                        .iter()
+                        .find_map(|atom| atom.resource())
+                        .map(|id| {
+                            let name = id.name().strip_prefix('@').unwrap_or(id.name());
+                            percent_encoding::percent_decode_str(name)
+                                .decode_utf8_lossy()
+                                .into_owned()
+                        })
+                        .unwrap_or_default();
+                    let (authors, sources) = question
+                        .info
+                        .as_ref()
+                        .map(|info| (info.authors.join("; "), info.sources.join("; ")))
+                        .unwrap_or_default();
+                    rows.push(vec![
+                        round.name.clone(),
+                        theme.name.clone(),
+                        question.price.to_string(),
+                        text,
+                        question.right.join("; "),
+                        question.wrong.join("; "),
+                        authors,
+                        sources,
+                        media,
+                    ]);
+                }
+            }
+        }
+        Spreadsheetv4 { rows }
+    }
+}
+
+/// Read a media file of a spreadsheet row, the kind of the atom is
+/// guessed by the file extension or, failing that, by its content.
+fn media_atom(media: &mut MediaLoader, file: &str) -> Option<Atomv4> {
+    let extension = Path::new(file).extension().and_then(|extension| extension.to_str());
+    let format = extension.and_then(MediaFormatv4::from_extension).or_else(|| {
+        let bytes = std::fs::read(media.dir.join(file)).ok()?;
+        MediaFormatv4::sniff(&bytes)
+    })?;
+    let kind = format.atom_kind();
+    let body = media.load(kind, file).ok()?;
+    Some(Atomv4 { kind, body, time: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use super::*;
+    use crate::v4::PackageTemplatev4;
+
+    #[test]
+    fn spreadsheet_formats() {
+        let csv = "\u{FEFF}Тема;Цена;Вопрос\nКино;100;\"Кто снял; «Сталкер»?\"\n;200;Второй\n";
+        let sheet = Spreadsheetv4::parse(csv.as_bytes()).unwrap();
+        assert_eq!(sheet.rows[1], ["Кино", "100", "Кто снял; «Сталкер»?"]);
+        assert_eq!(sheet.rows[2][0], "");
+        assert_eq!(Spreadsheetv4::from_csv(&sheet.to_csv().unwrap()).unwrap(), sheet);
+
+        let xlsx = sheet.to_xlsx().unwrap();
+        let read = Spreadsheetv4::parse(&xlsx).unwrap();
+        assert_eq!(read.rows[1], sheet.rows[1]);
+        assert_eq!(read.rows[2], sheet.rows[2]);
+
+        assert_eq!(column_index("AB12"), Some(27));
+        assert_eq!(column_index("XFD1"), Some(MAX_COLUMNS - 1));
+        assert_eq!(column_index("XFE1"), None);
+        assert_eq!(column_index("ZZZZZZZZZZZZZZZZ1"), None);
+        assert_eq!(row_index("1048577"), None);
+        let cell = |row: &str, cell: &str| {
+            let xml = format!(
+                r#"<row r="{row}"><c r="{cell}" t="inlineStr"><is><t>x</t></is></c></row>"#
+            );
+            read_sheet(&xml, &[])
+        };
+        assert_eq!(cell("2", "B2").unwrap().rows[1], ["", "x"]);
+        assert!(cell("1048577", "A1048577").is_err());
+        assert!(cell("1", "XFE1").is_err());
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(number_text("100.0"), "100");
+    }
+
+    #[test]
+    fn import_and_export_spreadsheet() {
+        let dir = std::env::temp_dir().join(format!("opensi-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("кадр.png"), b"\x89PNGkadr").unwrap();
+
+        let sheet = Spreadsheetv4 {
+            rows: vec![
+                vec!["Theme".into(), "Answer".into(), "Price".into(), "Media".into()],
+                vec!["Кино".into(), "Тарковский".into(), "100".into(), "кадр.png".into()],
+                vec!["".into(), "Да; Конечно".into(), "".into(), "нет.png".into()],
+                vec!["Музыка".into(), "Бах".into(), "300".into(), "".into()],
+            ],
+        };
+        let mapping = ColumnMappingv4::guess(&sheet);
+        assert!(mapping.header);
+        assert_eq!(mapping.columns[1], Some(SpreadsheetColumnv4::Answer));
+
+        let mut package = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        let rounds = package.rounds.len();
+        let report = package.import_spreadsheet(&sheet, &mapping, Some(&dir));
+        assert_eq!(report.questions, 3);
+        assert_eq!((report.rounds, report.themes), (1, 2));
+        assert_eq!(report.missing_media, ["нет.png"]);
+
+        let round = &package.rounds[rounds];
+        assert_eq!(round.themes[0].name, "Кино");
+        assert_eq!(round.themes[0].questions[1].price, 200);
+        assert_eq!(round.themes[0].questions[1].right, ["Да", "Конечно"]);
+        assert_eq!(round.themes[1].questions[0].price, 300);
+        let atom = &round.themes[0].questions[0].scenario[0];
+        assert_eq!(atom.kind, AtomKindv4::Image);
+        assert_eq!(
+            package.resources.get(&atom.resource().unwrap()),
+            Some(&Arc::from(b"\x89PNGkadr".as_slice()))
+        );
+
+        let exported = package.to_spreadsheet();
+        let row = exported.rows.iter().find(|row| row[4] == "Тарковский").unwrap();
+        assert_eq!(row[..3], ["Новый раунд", "Кино", "100"]);
+        assert_eq!(row[8], "кадр.png");
+
+        let mut imported = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        imported.rounds.clear();
+        let report =
+            imported.import_spreadsheet(&exported, &ColumnMappingv4::guess(&exported), Some(&dir));
+        assert!(report.missing_media.is_empty());
+        assert_eq!(report.questions, exported.rows.len() - 1);
+        assert_eq!(imported.to_spreadsheet(), exported);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn import_out_of_range_prices() {
+        let sheet =
+            Spreadsheetv4::from_csv(b"A,inf,q1\n,,q2\nB,1e30,q3\n,,q4\nC,NaN,q5\n").unwrap();
+        let mapping = ColumnMappingv4 {
+            columns: vec![
+                Some(SpreadsheetColumnv4::Theme),
+                Some(SpreadsheetColumnv4::Price),
+                Some(SpreadsheetColumnv4::Question),
+            ],
+            header: false,
+        };
+
+        let mut package = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        package.rounds.clear();
+        assert_eq!(package.import_spreadsheet(&sheet, &mapping, None).questions, 5);
+
+        let prices = |theme: usize| {
+            package.rounds[0].themes[theme].questions.iter().map(|q| q.price).collect::<Vec<_>>()
+        };
+        assert_eq!(prices(0), [100, 200]);
+        assert_eq!(prices(1), [MAX_PRICE, MAX_PRICE + 100]);
+        assert_eq!(prices(2), [100]);
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Infov4, Packagev4, Questionv4, Roundv4, Themev4};
+
+/// Prices of questions in a theme: `start`, `start + step`, etc.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceLadderv4 {
+    pub start: usize,
+    pub step: usize,
+}
+
+impl PriceLadderv4 {
+    pub fn price(&self, index: usize) -> usize {
+        self.start.saturating_add(index.saturating_mul(self.step))
+    }
+}
+
+/// Structure of a new [`Packagev4`]: amount of rounds, themes and
+/// questions, and prices of questions in every round.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackageTemplatev4 {
+    /// Name of the template itself.
+    pub name: String,
+    pub themes_per_round: usize,
+    pub questions_per_theme: usize,
+    /// Price ladder of every regular round.
+    pub rounds: Vec<PriceLadderv4>,
+    /// Add a final round with a single question in every theme.
+    pub final_round: bool,
+}
+
+impl Default for PackageTemplatev4 {
+    /// Classic SIGame structure, where prices double with every round.
+    fn default() -> Self {
+        Self {
+            name: "Стандартный".to_string(),
+            themes_per_round: 6,
+            questions_per_theme: 5,
+            rounds: (1..=3)
+                .map(|round| PriceLadderv4 { start: round * 100, step: round * 100 })
+                .collect(),
+            final_round: true,
+        }
+    }
+}
+
+impl PackageTemplatev4 {
+    /// Change amount of regular rounds. New rounds continue the
+    /// ladder of the last round, doubling its prices.
+    pub fn set_round_count(&mut self, count: usize) {
+        while self.rounds.len() < count {
+            let ladder = self
+                .rounds
+                .last()
+                .map(|last| PriceLadderv4 {
+                    start: last.start.saturating_mul(2),
+                    step: last.step.saturating_mul(2),
+                })
+                .unwrap_or(PriceLadderv4 { start: 100, step: 100 });
+            self.rounds.push(ladder);
+        }
+        self.rounds.truncate(count);
+    }
+
+    /// Create a new package with this structure.
+    pub fn build(
+        &self,
+        name: impl Into<String>,
+        author: impl Into<String>,
+        language: impl Into<String>,
+    ) -> Packagev4 {
+        let author = author.into();
+        let mut package = Packagev4 {
+            name: name.into(),
+            language: language.into(),
+            info: Infov4 {
+                authors: if author.is_empty() { vec![] } else { vec![author] },
+                ..Infov4::default()
+            },
+            ..Packagev4::new()
+        };
+
+        for (index, ladder) in self.rounds.iter().enumerate() {
+            let themes = (1..=self.themes_per_round)
+                .map(|theme| Themev4 {
+                    name: format!("Тема {theme}"),
+                    questions: (0..self.questions_per_theme)
+                        .map(|question| Questionv4 {
+                            price: ladder.price(question),
+                            ..Questionv4::default()
+                        })
+                        .collect(),
+                    ..Themev4::default()
+                })
+                .collect();
+            package.rounds.push(Roundv4 {
+                name: format!("Раунд {}", index + 1),
+                themes,
+                ..Roundv4::default()
+            });
+        }
+
+        if self.final_round {
+            let themes = (1..=self.themes_per_round)
+                .map(|theme| Themev4 {
+                    name: format!("Тема {theme}"),
+                    questions: vec![Questionv4 { price: 0, ..Questionv4::default() }],
+                    ..Themev4::default()
+                })
+                .collect();
+            package.rounds.push(Roundv4 {
+                name: "Финал".to_string(),
+                kind: Some("final".to_string()),
+                themes,
+                ..Roundv4::default()
+            });
+        }
+
+        package
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_trait::{QuestionsContainer, RoundContainer, ThemesContainer};
+
+    #[test]
+    fn build_from_template() {
+        let mut template = PackageTemplatev4 {
+            themes_per_round: 4,
+            questions_per_theme: 3,
+            final_round: true,
+            ..PackageTemplatev4::default()
+        };
+        template.set_round_count(4);
+        assert_eq!(template.rounds[3], PriceLadderv4 { start: 600, step: 600 });
+
+        let package = template.build("Пак", "Автор", "ru");
+        assert_eq!(package.name, "Пак");
+        assert_eq!(package.info.authors, ["Автор"]);
+        assert_eq!(package.count_rounds(), 5);
+        assert_eq!(package.count_themes(1), 4);
+        assert_eq!(package.count_questions((1, 3)), 3);
+        assert_eq!(package.get_question((1, 0, 2)).unwrap().price, 600);
+
+        let final_round = package.get_round(4).unwrap();
+        assert_eq!(final_round.kind.as_deref(), Some("final"));
+        assert_eq!(package.count_questions((4, 0)), 1);
+
+        template.set_round_count(80);
+        assert_eq!(template.rounds[79], PriceLadderv4 { start: usize::MAX, step: usize::MAX });
+        assert_eq!(template.rounds[79].price(2), usize::MAX);
+
+        template.set_round_count(1);
+        template.final_round = false;
+        assert_eq!(template.build("", "", "").count_rounds(), 1);
+    }
+}
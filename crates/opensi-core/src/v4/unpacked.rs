@@ -0,0 +1,203 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use super::{Packagev4, ResourceIdv4};
+
+/// Folders of resources in an unpacked package, see [`ResourceIdv4::path`].
+const RESOURCE_FOLDERS: [&str; 4] = ["Images", "Audio", "Video", "Texts"];
+
+/// # Unpacked directory layout.
+///
+/// An unpacked package is a directory with `content.xml` and resources
+/// in `Images/`, `Audio/`, `Video/` and `Texts/` folders, the same way
+/// they are stored in a `.siq` archive. It's meant to keep packages
+/// under version control, so `content.xml` is indented.
+impl Packagev4 {
+    /// Open a package unpacked into the directory at `path`.
+    pub fn open_dir(path: impl AsRef<Path>) -> Result<Packagev4, Error> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path.join("content.xml"))?;
+        let package = quick_xml::de::from_str::<Packagev4>(&contents)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let mut resources = HashMap::new();
+        for folder in RESOURCE_FOLDERS {
+            let folder_path = path.join(folder);
+            if !folder_path.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(folder_path)? {
+                let entry = entry?;
+                let name = entry.file_name();
+                let Some(name) = name.to_str() else {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Resource name is not valid UTF-8: {name:?}"),
+                    ));
+                };
+                // skip dotfiles like `.gitkeep`
+                if name.starts_with('.') || !entry.file_type()?.is_file() {
+                    continue;
+                }
+                let Some(id) = ResourceIdv4::try_new(format!("{folder}/{name}")) else {
+                    continue;
+                };
+                resources.insert(id, Arc::from(fs::read(entry.path())?));
+            }
+        }
+
+        Ok(Packagev4 { resources, ..package })
+    }
+
+    /// Save the package unpacked into the directory at `path`. Only
+    /// changed resources are written, and resource files which the
+    /// package no longer has are removed.
+    ///
+    /// The directory must be empty or already contain an unpacked
+    /// package, so unrelated files are never removed.
+    pub fn save_dir(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        if !Self::is_dir_writable(path)? {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Directory {} isn't empty and isn't an unpacked package", path.display()),
+            ));
+        }
+        let is_unpacked = path.join("content.xml").is_file();
+        fs::create_dir_all(path)?;
+
+        let mut xml = Self::XML_VERSION_ENCODING.to_string();
+        xml.push('\n');
+        let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+        serializer.indent(' ', 2);
+        self.serialize(serializer).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        xml.push('\n');
+        write_atomically(&path.join("content.xml"), xml.as_bytes())?;
+
+        // only an existing unpacked package can have stale resources
+        let folders = if is_unpacked { RESOURCE_FOLDERS.as_slice() } else { &[] };
+        for folder in folders {
+            let folder_path = path.join(folder);
+            if !folder_path.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(folder_path)? {
+                let entry = entry?;
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if name.starts_with('.') || !entry.file_type()?.is_file() {
+                    continue;
+                }
+                let is_stale = ResourceIdv4::try_new(format!("{folder}/{name}")).is_none_or(|id| {
+                    id.path() != format!("{folder}/{name}") || !self.resources.contains_key(&id)
+                });
+                if is_stale {
+                    fs::remove_file(entry.path())?;
+                }
+            }
+        }
+
+        for (id, bytes) in &self.resources {
+            let file = path.join(id.path());
+            if fs::read(&file).is_ok_and(|existing| *existing == **bytes) {
+                continue;
+            }
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_atomically(&file, bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether [`Packagev4::save_dir`] can save into the directory at
+    /// `path`: it doesn't exist, is empty or has an unpacked package.
+    pub fn is_dir_writable(path: impl AsRef<Path>) -> Result<bool, Error> {
+        let path = path.as_ref();
+        if !path.exists() || path.join("content.xml").is_file() {
+            return Ok(true);
+        }
+        Ok(fs::read_dir(path)?.next().is_none())
+    }
+
+    /// Unpack a `.siq` file at `zip` into the directory at `dir`.
+    pub fn unpack(zip: impl AsRef<Path>, dir: impl AsRef<Path>) -> Result<(), Error> {
+        Self::open_zip_file(zip)?.save_dir(dir)
+    }
+
+    /// Pack the unpacked package at `dir` into a `.siq` file at `zip`.
+    pub fn pack(dir: impl AsRef<Path>, zip: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(zip, Self::open_dir(dir)?.to_bytes()?)
+    }
+}
+
+/// Write `bytes` into a hidden temporary file next to `file` and rename
+/// it into place, so `file` isn't left half-written if writing fails.
+fn write_atomically(file: &Path, bytes: &[u8]) -> Result<(), Error> {
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+    let temp = file.with_file_name(format!(".{file_name}.tmp"));
+    fs::write(&temp, bytes).and_then(|_| fs::rename(&temp, file)).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_trait::QuestionsContainer;
+    use crate::v4::{AtomKindv4, Atomv4, PackageTemplatev4};
+
+    #[test]
+    fn pack_and_unpack() {
+        let mut package = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        let question = package.get_question_mut((0, 0, 0)).unwrap();
+        question.scenario.push(Atomv4 {
+            kind: AtomKindv4::Image,
+            body: "@a.png".to_string(),
+            time: Some(2.5),
+        });
+        package.resources.insert(ResourceIdv4::image("a.png"), Arc::from(b"png".as_slice()));
+        package.resources.insert(ResourceIdv4::audio("b.mp3"), Arc::from(b"mp3".as_slice()));
+
+        let root = std::env::temp_dir().join(format!("opensi-{}", uuid::Uuid::new_v4()));
+        let dir = root.join("pack");
+        package.save_dir(&dir).unwrap();
+        assert!(dir.join("content.xml").is_file());
+        assert_eq!(fs::read(dir.join("Images/@a.png")).unwrap(), b"png");
+        assert_eq!(Packagev4::open_dir(&dir).unwrap(), package);
+
+        // removed resources are removed from the directory too
+        package.resources.remove(&ResourceIdv4::audio("b.mp3"));
+        fs::write(dir.join("Audio/.gitkeep"), b"").unwrap();
+        package.save_dir(&dir).unwrap();
+        assert!(!dir.join("Audio/@b.mp3").exists());
+        assert!(dir.join("Audio/.gitkeep").exists());
+
+        let zip = root.join("pack.siq");
+        Packagev4::pack(&dir, &zip).unwrap();
+        assert_eq!(Packagev4::open_zip_file(&zip).unwrap(), package);
+
+        let unpacked = root.join("unpacked");
+        Packagev4::unpack(&zip, &unpacked).unwrap();
+        assert_eq!(Packagev4::open_dir(&unpacked).unwrap(), package);
+
+        // unrelated files are never removed
+        let documents = root.join("documents");
+        fs::create_dir_all(documents.join("Images")).unwrap();
+        fs::write(documents.join("Images/photo.png"), b"png").unwrap();
+        assert!(!Packagev4::is_dir_writable(&documents).unwrap());
+        assert!(package.save_dir(&documents).is_err());
+        assert!(documents.join("Images/photo.png").exists());
+        assert!(!documents.join("content.xml").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
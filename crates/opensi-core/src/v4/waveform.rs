@@ -0,0 +1,146 @@
+use std::io::{self, Error, ErrorKind};
+
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, errors::Error as DecodeError,
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+/// Peak amplitude below which audio is considered silent.
+const SILENCE: f32 = 0.01;
+
+/// Amount of samples of a single channel reduced into one peak
+/// while decoding, before the peaks are downsampled to buckets.
+const CHUNK: usize = 256;
+
+/// Downsampled amplitude envelope of an audio track.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Waveformv4 {
+    /// Peak amplitude of every bucket, from `0.0` to `1.0`.
+    pub peaks: Vec<f32>,
+    /// Duration of the decoded audio in seconds.
+    pub duration: f64,
+}
+
+impl Waveformv4 {
+    /// Decode the first audio track of an MP3, Ogg, WAV, FLAC or MP4
+    /// file into at most `buckets` peaks.
+    pub fn decode(bytes: &[u8], buckets: usize) -> Result<Self, Error> {
+        let source =
+            MediaSourceStream::new(Box::new(io::Cursor::new(bytes.to_vec())), Default::default());
+        let probed = symphonia::default::get_probe()
+            .format(&Hint::new(), source, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(invalid_data)?;
+        let mut format = probed.format;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.sample_rate.is_some())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No audio track"))?;
+        let track_id = track.id;
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(invalid_data)?;
+
+        let mut chunks = vec![];
+        let mut peak = 0.0f32;
+        let mut frames = 0usize;
+        let mut sample_rate = track.codec_params.sample_rate.unwrap_or_default();
+        let mut buffer = None::<SampleBuffer<f32>>;
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(DecodeError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(DecodeError::ResetRequired) => break,
+                Err(err) => return Err(invalid_data(err)),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // skip broken packets like players do
+                Err(DecodeError::DecodeError(_)) => continue,
+                Err(err) => return Err(invalid_data(err)),
+            };
+
+            let spec = *decoded.spec();
+            sample_rate = spec.rate;
+            let channels = spec.channels.count().max(1);
+            let buffer = match &mut buffer {
+                Some(buffer) if buffer.capacity() >= decoded.capacity() * channels => buffer,
+                buffer => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            buffer.copy_interleaved_ref(decoded);
+            for frame in buffer.samples().chunks(channels) {
+                let amplitude = frame.iter().fold(0.0f32, |max, sample| max.max(sample.abs()));
+                peak = peak.max(amplitude);
+                frames += 1;
+                if frames.is_multiple_of(CHUNK) {
+                    chunks.push(peak);
+                    peak = 0.0;
+                }
+            }
+        }
+        if !frames.is_multiple_of(CHUNK) {
+            chunks.push(peak);
+        }
+
+        let buckets = buckets.clamp(1, chunks.len().max(1));
+        let peaks = (0..buckets)
+            .map(|bucket| {
+                let range = bucket * chunks.len() / buckets..(bucket + 1) * chunks.len() / buckets;
+                chunks[range].iter().fold(0.0f32, |max, &peak| max.max(peak)).min(1.0)
+            })
+            .collect();
+        let duration = if sample_rate > 0 { frames as f64 / sample_rate as f64 } else { 0.0 };
+        Ok(Self { peaks, duration })
+    }
+
+    /// The whole track is quieter than the silence threshold.
+    pub fn is_silent(&self) -> bool {
+        self.peaks.iter().all(|&peak| peak < SILENCE)
+    }
+}
+
+fn invalid_data(err: DecodeError) -> Error {
+    Error::new(ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(samples: impl Iterator<Item = i16>) -> Vec<u8> {
+        let data = samples.flat_map(i16::to_le_bytes).collect::<Vec<_>>();
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt \x10\0\0\0");
+        // PCM, mono, 8000 Hz, 16 bit
+        bytes.extend_from_slice(&[1, 0, 1, 0, 0x40, 0x1F, 0, 0, 0x80, 0x3E, 0, 0, 2, 0, 16, 0]);
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn decode_waveform() {
+        // one second of silence, then one second of a loud square wave
+        let samples = (0..16000).map(|n| match n {
+            ..8000 => 0,
+            n if n % 2 == 0 => i16::MAX,
+            _ => -i16::MAX,
+        });
+        let waveform = Waveformv4::decode(&wav(samples), 10).unwrap();
+        assert_eq!(waveform.duration, 2.0);
+        assert_eq!(waveform.peaks.len(), 10);
+        assert!(waveform.peaks[..4].iter().all(|&peak| peak == 0.0));
+        assert!(waveform.peaks[6..].iter().all(|&peak| peak > 0.99));
+        assert!(!waveform.is_silent());
+
+        let silent = Waveformv4::decode(&wav(std::iter::repeat_n(0, 8000)), 10).unwrap();
+        assert!(silent.is_silent());
+
+        assert!(Waveformv4::decode(b"plain text", 10).is_err());
+    }
+}
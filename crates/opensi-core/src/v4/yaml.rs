@@ -0,0 +1,539 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{Error, ErrorKind},
+    path::{Component, Path},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AtomKindv4, Atomv4, Infov4, Packagev4, Paramv4, QuestionTypev4, Questionv4, ResourceIdv4,
+    Roundv4, Themev4, media::MediaLoader,
+};
+
+/// Package in the YAML authoring format. It maps one-to-one onto
+/// [`Packagev4`], but media atoms reference files by a path relative
+/// to the YAML file, e.g. `Images/photo.jpg`.
+///
+/// ```yaml
+/// name: Мой пак
+/// rounds:
+///   - name: Первый раунд
+///     themes:
+///       - name: Кино
+///         questions:
+///           - price: 100
+///             scenario:
+///               - Кто снял этот фильм?
+///               - image: Images/poster.jpg
+///                 time: 5
+///             right: [Тарковский]
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PackageYaml {
+    name: String,
+    version: f32,
+    id: String,
+    date: String,
+    publisher: String,
+    difficulty: u8,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logo: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    restriction: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    namespace: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "InfoYaml::is_empty")]
+    info: InfoYaml,
+    rounds: Vec<RoundYaml>,
+}
+
+impl Default for PackageYaml {
+    fn default() -> Self {
+        let package = Packagev4::new();
+        Self {
+            name: package.name,
+            version: package.version,
+            id: package.id,
+            date: package.date,
+            publisher: package.publisher,
+            difficulty: package.difficulty,
+            language: package.language,
+            logo: package.logo,
+            restriction: package.restriction,
+            namespace: package.namespace,
+            tags: package.tags,
+            info: InfoYaml::default(),
+            rounds: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct InfoYaml {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authors: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sources: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    comments: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    extension: String,
+}
+
+impl InfoYaml {
+    fn is_empty(&self) -> bool {
+        self.authors.is_empty()
+            && self.sources.is_empty()
+            && self.comments.is_empty()
+            && self.extension.is_empty()
+    }
+}
+
+impl From<&Infov4> for InfoYaml {
+    fn from(info: &Infov4) -> Self {
+        Self {
+            authors: info.authors.clone(),
+            sources: info.sources.clone(),
+            comments: info.comments.clone(),
+            extension: info.extension.clone(),
+        }
+    }
+}
+
+impl From<InfoYaml> for Infov4 {
+    fn from(info: InfoYaml) -> Self {
+        Self {
+            authors: info.authors,
+            sources: info.sources,
+            comments: info.comments,
+            extension: info.extension,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RoundYaml {
+    name: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    info: Option<InfoYaml>,
+    themes: Vec<ThemeYaml>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeYaml {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    info: Option<InfoYaml>,
+    questions: Vec<QuestionYaml>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct QuestionYaml {
+    price: usize,
+    #[serde(rename = "type", skip_serializing_if = "String::is_empty")]
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<Vec<ParamYaml>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    scenario: Vec<AtomYaml>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    right: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    wrong: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    info: Option<InfoYaml>,
+}
+
+impl Default for QuestionYaml {
+    fn default() -> Self {
+        let question = Questionv4::default();
+        Self {
+            price: question.price,
+            kind: question.question_type.name,
+            params: None,
+            scenario: vec![],
+            right: vec![],
+            wrong: vec![],
+            info: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParamYaml {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+/// Scenario atom: either a plain string for text, or a map with
+/// exactly one of `text`, `image`, `voice` or `video` and optional `time`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum AtomYaml {
+    Text(String),
+    Full(AtomFieldsYaml),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AtomFieldsYaml {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    voice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    video: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<f64>,
+}
+
+/// Path of a media file relative to the YAML file, e.g. `Images/photo.jpg`.
+fn media_path(kind: AtomKindv4, body: &str) -> String {
+    let folder = match kind {
+        AtomKindv4::Image => "Images",
+        AtomKindv4::Voice => "Audio",
+        AtomKindv4::Video => "Video",
+        AtomKindv4::Text => "Texts",
+    };
+    format!("{folder}/{}", body.strip_prefix('@').unwrap_or(body))
+}
+
+/// Path of a resource file relative to the YAML file. Names which
+/// would lead out of their folder once decoded are rejected.
+fn resource_path(id: &ResourceIdv4) -> Result<String, Error> {
+    let (folder, _) = id.path().split_once('/').unwrap_or_default();
+    let name = id.name().strip_prefix('@').unwrap_or(id.name());
+    let name = percent_encoding::percent_decode_str(name).decode_utf8_lossy();
+
+    let mut components = Path::new(name.as_ref()).components();
+    let is_file_name = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\']);
+    if !is_file_name {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid resource name {}", id.name()),
+        ));
+    }
+    Ok(format!("{folder}/{name}"))
+}
+
+fn invalid_data(err: serde_norway::Error) -> Error {
+    Error::new(ErrorKind::InvalidData, err)
+}
+
+/// Atom of a YAML package, with its media file loaded.
+fn atom_from_yaml(media: &mut MediaLoader, atom: AtomYaml) -> Result<Atomv4, Error> {
+    let fields = match atom {
+        AtomYaml::Text(body) => return Ok(Atomv4 { body, ..Atomv4::default() }),
+        AtomYaml::Full(fields) => fields,
+    };
+    let (kind, body) = match fields {
+        AtomFieldsYaml { text: Some(text), image: None, voice: None, video: None, .. } => {
+            (AtomKindv4::Text, text)
+        },
+        AtomFieldsYaml { text: None, image: Some(path), voice: None, video: None, .. } => {
+            (AtomKindv4::Image, media.load(AtomKindv4::Image, &path)?)
+        },
+        AtomFieldsYaml { text: None, image: None, voice: Some(path), video: None, .. } => {
+            (AtomKindv4::Voice, media.load(AtomKindv4::Voice, &path)?)
+        },
+        AtomFieldsYaml { text: None, image: None, voice: None, video: Some(path), .. } => {
+            (AtomKindv4::Video, media.load(AtomKindv4::Video, &path)?)
+        },
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Atom must have exactly one of `text`, `image`, `voice` or `video`",
+            ));
+        },
+    };
+    Ok(Atomv4 { kind, body, time: fields.time })
+}
+
+/// Question of a YAML package, with its media files loaded.
+fn question_from_yaml(
+    media: &mut MediaLoader,
+    question: QuestionYaml,
+) -> Result<Questionv4, Error> {
+    let scenario = question
+        .scenario
+        .into_iter()
+        .map(|atom| atom_from_yaml(media, atom))
+        .collect::<Result<Vec<_>, _>>()?;
+    let params = question.params.map(|params| {
+        params.into_iter().map(|param| Paramv4 { name: param.name, body: param.value }).collect()
+    });
+    Ok(Questionv4 {
+        price: question.price,
+        question_type: QuestionTypev4 { name: question.kind, params },
+        scenario,
+        right: question.right,
+        wrong: question.wrong,
+        info: question.info.map(Infov4::from),
+        ..Questionv4::default()
+    })
+}
+
+/// # YAML authoring format.
+impl Packagev4 {
+    /// Write the package in the YAML authoring format.
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        let info = |info: &Option<Infov4>| info.as_ref().map(InfoYaml::from);
+        let atom = |atom: &Atomv4| match atom.kind {
+            AtomKindv4::Text if atom.time.is_none() => AtomYaml::Text(atom.body.clone()),
+            AtomKindv4::Text => AtomYaml::Full(AtomFieldsYaml {
+                text: Some(atom.body.clone()),
+                time: atom.time,
+                ..AtomFieldsYaml::default()
+            }),
+            kind => {
+                let path = Some(media_path(kind, &atom.body));
+                AtomYaml::Full(AtomFieldsYaml {
+                    image: path.clone().filter(|_| kind == AtomKindv4::Image),
+                    voice: path.clone().filter(|_| kind == AtomKindv4::Voice),
+                    video: path.filter(|_| kind == AtomKindv4::Video),
+                    time: atom.time,
+                    ..AtomFieldsYaml::default()
+                })
+            },
+        };
+        let question = |question: &Questionv4| QuestionYaml {
+            price: question.price,
+            kind: question.question_type.name.clone(),
+            params: question.question_type.params.as_ref().map(|params| {
+                params
+                    .iter()
+                    .map(|param| ParamYaml { name: param.name.clone(), value: param.body.clone() })
+                    .collect()
+            }),
+            scenario: question.scenario.iter().map(atom).collect(),
+            right: question.right.clone(),
+            wrong: question.wrong.clone(),
+            info: info(&question.info),
+        };
+        let theme = |theme: &Themev4| ThemeYaml {
+            name: theme.name.clone(),
+            info: info(&theme.info),
+            questions: theme.questions.iter().map(question).collect(),
+        };
+        let round = |round: &Roundv4| RoundYaml {
+            name: round.name.clone(),
+            kind: round.kind.clone(),
+            info: info(&round.info),
+            themes: round.themes.iter().map(theme).collect(),
+        };
+
+        let package = PackageYaml {
+            name: self.name.clone(),
+            version: self.version,
+            id: self.id.clone(),
+            date: self.date.clone(),
+            publisher: self.publisher.clone(),
+            difficulty: self.difficulty,
+            language: self.language.clone(),
+            logo: self.logo.as_ref().map(|logo| media_path(AtomKindv4::Image, logo)),
+            restriction: self.restriction.clone(),
+            namespace: self.namespace.clone(),
+            tags: self.tags.clone(),
+            info: InfoYaml::from(&self.info),
+            rounds: self.rounds.iter().map(round).collect(),
+        };
+        serde_norway::to_string(&package).map_err(invalid_data)
+    }
+
+    /// Read a package in the YAML authoring format. Media files are
+    /// read relative to `media_dir`.
+    pub fn from_yaml(yaml: &str, media_dir: impl AsRef<Path>) -> Result<Packagev4, Error> {
+        let package = serde_norway::from_str::<PackageYaml>(yaml).map_err(invalid_data)?;
+        let mut media = MediaLoader { dir: media_dir.as_ref(), resources: HashMap::new() };
+
+        let logo = package.logo.map(|logo| media.load(AtomKindv4::Image, &logo)).transpose()?;
+        let mut rounds = vec![];
+        for round in package.rounds {
+            let mut themes = vec![];
+            for theme in round.themes {
+                let questions = theme
+                    .questions
+                    .into_iter()
+                    .map(|question| question_from_yaml(&mut media, question))
+                    .collect::<Result<Vec<_>, _>>()?;
+                themes.push(Themev4 {
+                    name: theme.name,
+                    info: theme.info.map(Infov4::from),
+                    questions,
+                    ..Themev4::default()
+                });
+            }
+            rounds.push(Roundv4 {
+                name: round.name,
+                kind: round.kind,
+                info: round.info.map(Infov4::from),
+                themes,
+                ..Roundv4::default()
+            });
+        }
+
+        Ok(Packagev4 {
+            name: package.name,
+            version: package.version,
+            id: package.id,
+            date: package.date,
+            publisher: package.publisher,
+            difficulty: package.difficulty,
+            language: package.language,
+            logo,
+            restriction: package.restriction,
+            namespace: package.namespace,
+            info: package.info.into(),
+            rounds,
+            tags: package.tags,
+            resources: media.resources,
+        })
+    }
+
+    /// Write the package as a YAML file at `path` with its media
+    /// files next to it. Resources which no atom references aren't
+    /// written, since YAML can't reference them.
+    pub fn export_yaml(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let logo =
+            self.logo.clone().map(|body| Atomv4 { kind: AtomKindv4::Image, body, time: None });
+        let atoms = self
+            .rounds
+            .iter()
+            .flat_map(|round| &round.themes)
+            .flat_map(|theme| &theme.questions)
+            .flat_map(|question| &question.scenario)
+            .chain(logo.as_ref());
+
+        let mut written = HashSet::new();
+        for id in atoms.filter_map(Atomv4::resource) {
+            let Some(bytes) = self.resources.get(&id) else {
+                continue;
+            };
+            let file = dir.join(resource_path(&id)?);
+            if !written.insert(id) {
+                continue;
+            }
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(file, bytes)?;
+        }
+        fs::write(path, self.to_yaml()?)
+    }
+
+    /// Read a YAML file at `path` with media files next to it.
+    pub fn import_yaml(path: impl AsRef<Path>) -> Result<Packagev4, Error> {
+        let path = path.as_ref();
+        let yaml = fs::read_to_string(path)?;
+        Self::from_yaml(&yaml, path.parent().unwrap_or(Path::new("")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::package_trait::QuestionsContainer;
+    use crate::v4::PackageTemplatev4;
+
+    #[test]
+    fn yaml_round_trip() {
+        let mut package = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        package.tags = vec!["кино".to_string()];
+        let question = package.get_question_mut((0, 0, 0)).unwrap();
+        question.question_type = QuestionTypev4 {
+            name: "cat".to_string(),
+            params: Some(vec![Paramv4 { name: "cost".to_string(), body: Some("100".to_string()) }]),
+        };
+        question.scenario = vec![
+            Atomv4 { body: "Что на фото?".to_string(), ..Atomv4::default() },
+            Atomv4 {
+                kind: AtomKindv4::Image, body: "@фото 1.png".to_string(), time: Some(5.0)
+            },
+            Atomv4 { kind: AtomKindv4::Voice, body: "@a.mp3".to_string(), time: None },
+        ];
+        question.right = vec!["Кот".to_string()];
+        question.wrong = vec!["Пёс".to_string()];
+        for atom in question.scenario.clone() {
+            if let Some(id) = atom.resource() {
+                package.resources.insert(id, Arc::from(atom.body.as_bytes()));
+            }
+        }
+
+        let yaml = package.to_yaml().unwrap();
+        assert!(yaml.contains("image: Images/фото 1.png"), "{yaml}");
+
+        let dir = std::env::temp_dir().join(format!("opensi-{}", uuid::Uuid::new_v4()));
+        package.export_yaml(dir.join("pack.yaml")).unwrap();
+        assert_eq!(Packagev4::import_yaml(dir.join("pack.yaml")).unwrap(), package);
+
+        let draft = "
+name: Черновик
+rounds:
+  - name: Раунд
+    themes:
+      - name: Тема
+        questions:
+          - scenario: [Вопрос, {image: Images/фото 1.png}]
+            right: [Ответ]
+          - price: 200
+            scenario: [{text: Вопрос, video: Video/a.mp4}]
+";
+        let err = Packagev4::from_yaml(draft, &dir).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let draft = draft.replace(", video: Video/a.mp4", "");
+        let draft = Packagev4::from_yaml(&draft, &dir).unwrap();
+        assert_eq!(draft.difficulty, 5);
+        let question = draft.get_question((0, 0, 0)).unwrap();
+        assert_eq!(question.price, 100);
+        assert_eq!(question.scenario[1].body, "@фото 1.png");
+        assert_eq!(draft.get_question((0, 0, 1)).unwrap().price, 200);
+        assert_eq!(draft.resources.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn yaml_export_resources() {
+        let mut package = PackageTemplatev4::default().build("Пак", "Автор", "ru");
+        let dir = std::env::temp_dir().join(format!("opensi-{}", uuid::Uuid::new_v4()));
+
+        fs::create_dir_all(&dir).unwrap();
+        package.resources.insert(ResourceIdv4::texts("@notes.txt"), Arc::from(&b"notes"[..]));
+        package.export_yaml(dir.join("pack.yaml")).unwrap();
+        assert!(!dir.join("Texts").exists());
+        assert!(Packagev4::import_yaml(dir.join("pack.yaml")).unwrap().resources.is_empty());
+
+        let atom = Atomv4 { kind: AtomKindv4::Image, body: "@..%2F..%2Fx".to_string(), time: None };
+        package.resources.insert(atom.resource().unwrap(), Arc::from(&b"x"[..]));
+        package.get_question_mut((0, 0, 0)).unwrap().scenario.push(atom);
+        let err = package.export_yaml(dir.join("nested").join("pack.yaml")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(!dir.join("x").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
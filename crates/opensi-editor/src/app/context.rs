@@ -1,15 +1,27 @@
 #![allow(unused)]
 
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use derive_more::{Deref, DerefMut};
+use itertools::Itertools;
+use log::{info, warn};
 use opensi_core::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use tokio_with_wasm::alias as tokio;
+
+use tokio::sync::oneshot;
 
 use crate::{
     EditorApp,
     app::{
         PackageState,
         files::{self, FileError, FileLoader, LoadingResult},
+        history::{EditKind, History},
+        spreadsheet::spreadsheet_loader,
+        storage::SharedPackageBytesStorage,
     },
 };
 
@@ -123,7 +135,18 @@ impl<'a> PackageContext<'a> {
         }
     }
 
+    /// Current position of the selected node.
     pub fn selected(&self) -> Option<PackageNode> {
+        match self.app.package_state {
+            PackageState::Active { ref package, selected, .. } => {
+                selected.and_then(|id| package.find_node(id))
+            },
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    /// Stable id of the selected node.
+    pub fn selected_id(&self) -> Option<NodeId> {
         match self.app.package_state {
             PackageState::Active { selected, .. } => selected,
             _ => unimplemented!("Package state mismatch for PackageContext"),
@@ -132,14 +155,321 @@ impl<'a> PackageContext<'a> {
 
     pub fn select(&mut self, node: PackageNode) {
         match self.app.package_state {
-            PackageState::Active { ref mut selected, .. } => *selected = Some(node),
+            PackageState::Active { ref package, ref mut selected, ref mut marked, .. } => {
+                *selected = package.node_id(node);
+                marked.clear();
+            },
             _ => unimplemented!("Package state mismatch for PackageContext"),
         }
     }
 
     pub fn deselect(&mut self) {
         match self.app.package_state {
-            PackageState::Active { ref mut selected, .. } => *selected = None,
+            PackageState::Active { ref mut selected, ref mut marked, .. } => {
+                *selected = None;
+                marked.clear();
+            },
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    /// Current positions of the nodes marked for bulk operations.
+    pub fn marked(&self) -> Vec<PackageNode> {
+        let PackageState::Active { ref package, ref marked, .. } = self.app.package_state else {
+            unimplemented!("Package state mismatch for PackageContext");
+        };
+        if marked.is_empty() {
+            return vec![];
+        }
+
+        let positions = package.node_positions();
+        let mut nodes = marked.iter().filter_map(|id| positions.get(id).copied()).collect_vec();
+        nodes.sort();
+        nodes
+    }
+
+    /// Nodes to apply an operation to: either marked nodes, or the selected one.
+    pub fn targets(&self) -> Vec<PackageNode> {
+        let marked = self.marked();
+        if marked.is_empty() { self.selected().into_iter().collect() } else { marked }
+    }
+
+    /// Mark nodes for bulk operations, either in addition
+    /// to already marked ones, or instead of them.
+    pub fn mark(&mut self, nodes: &[PackageNode], extend: bool) {
+        let PackageState::Active { ref package, ref mut marked, .. } = self.app.package_state
+        else {
+            unimplemented!("Package state mismatch for PackageContext");
+        };
+        if !extend {
+            marked.clear();
+        }
+        for id in nodes.iter().filter_map(|&node| package.node_id(node)) {
+            if !marked.contains(&id) {
+                marked.push(id);
+            }
+        }
+    }
+
+    /// Handle a click on the node's widget: select the node, toggle its
+    /// mark with Ctrl, or mark a range of nodes up to it with Shift.
+    pub fn handle_click(&mut self, node: PackageNode, response: &egui::Response) {
+        if !response.clicked() {
+            return;
+        }
+
+        let modifiers = response.ctx.input(|input| input.modifiers);
+        if !modifiers.shift && !modifiers.command {
+            self.select(node);
+            return;
+        }
+
+        let PackageState::Active { ref package, selected, ref mut marked, .. } =
+            self.app.package_state
+        else {
+            unimplemented!("Package state mismatch for PackageContext");
+        };
+        let Some(id) = package.node_id(node) else {
+            return;
+        };
+        let positions = package.node_positions();
+        let same_kind = |id: &NodeId| {
+            positions
+                .get(id)
+                .is_some_and(|other| std::mem::discriminant(other) == std::mem::discriminant(&node))
+        };
+
+        // selected node is the first one to be marked, if it fits
+        if marked.is_empty() {
+            marked.extend(selected.filter(|selected| *selected != id && same_kind(selected)));
+        }
+
+        let anchor = marked.last().copied().filter(same_kind);
+        match anchor {
+            Some(anchor) if modifiers.shift => {
+                let mut nodes = positions
+                    .iter()
+                    .filter(|(id, _)| same_kind(id))
+                    .map(|(&id, &position)| (position, id))
+                    .collect_vec();
+                nodes.sort();
+                let range = [positions[&anchor], node];
+                let (start, end) = (range[0].min(range[1]), range[0].max(range[1]));
+
+                marked.clear();
+                marked.extend(
+                    nodes
+                        .into_iter()
+                        .filter(|(position, _)| (start..=end).contains(position))
+                        .map(|(_, id)| id)
+                        .filter(|&id| id != anchor),
+                );
+                marked.push(anchor);
+            },
+            _ => match marked.iter().position(|&marked| marked == id) {
+                Some(index) => {
+                    marked.remove(index);
+                },
+                None => marked.push(id),
+            },
+        }
+    }
+
+    pub fn history(&self) -> &History {
+        match self.app.package_state {
+            PackageState::Active { ref history, .. } => history,
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    /// Record package changes made during this frame into the history.
+    pub fn track_edits(&mut self, ui_ctx: &egui::Context) {
+        let PackageState::Active { ref package, ref mut history, ref mut dirty, .. } =
+            self.app.package_state
+        else {
+            unimplemented!("Package state mismatch for PackageContext");
+        };
+
+        let kind = EditKind::guess(ui_ctx);
+        if history.record(package, kind.unwrap_or(EditKind::Structure)) {
+            *dirty = true;
+        }
+        if kind.is_none() {
+            history.seal();
+        }
+    }
+
+    /// Paste nodes of a [`Fragment`] relative to the `target` node
+    /// and select the first of them.
+    pub fn paste(&mut self, fragment: Fragment, target: Option<PackageNode>) {
+        let PackageState::Active { ref mut package, ref mut selected, .. } = self.app.package_state
+        else {
+            unimplemented!("Package state mismatch for PackageContext");
+        };
+
+        let pasted = package.paste_fragment(fragment, target);
+        if let Some(&first) = pasted.first() {
+            *selected = package.node_id(first);
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let storage = self.app.storage.clone();
+        match self.app.package_state {
+            PackageState::Active {
+                ref mut package,
+                ref mut history,
+                ref mut dirty,
+                ref storage_key,
+                ..
+            } => {
+                let undone = history.undo(package);
+                if undone {
+                    storage.sync_package(storage_key, package);
+                }
+                *dirty |= undone;
+                undone
+            },
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let storage = self.app.storage.clone();
+        match self.app.package_state {
+            PackageState::Active {
+                ref mut package,
+                ref mut history,
+                ref mut dirty,
+                ref storage_key,
+                ..
+            } => {
+                let redone = history.redo(package);
+                if redone {
+                    storage.sync_package(storage_key, package);
+                }
+                *dirty |= redone;
+                redone
+            },
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    /// Optimize all images of the package in the background, see
+    /// [`Package::optimize_images`], and run `done` with the report.
+    /// The report is `None` if the package was changed meanwhile,
+    /// in which case the optimized package is dropped.
+    pub fn optimize_images(
+        &mut self,
+        options: ImageOptimization,
+        done: impl FnOnce(Result<OptimizationReport, String>) + 'static,
+    ) {
+        let PackageState::Active { ref package, ref storage_key, .. } = self.app.package_state
+        else {
+            unimplemented!("Package state mismatch for PackageContext");
+        };
+
+        let snapshot = package.clone();
+        let storage_key = storage_key.clone();
+        let done = OptimizationDone(Some(done));
+        let queue = files::process(
+            move || {
+                let mut optimized = snapshot.clone();
+                let report = optimized.optimize_images(&options);
+                Ok((snapshot, optimized, report))
+            },
+            move |(snapshot, optimized, report), app: &mut EditorApp| {
+                let storage = app.storage.clone();
+                let Some(PackageState::Active {
+                    ref mut package,
+                    ref mut history,
+                    ref mut dirty,
+                    ..
+                }) = app.find_package_mut(&storage_key)
+                else {
+                    done.finish(Err("Пакет был закрыт во время оптимизации.".into()));
+                    return Err(FileError::LoaderError("No open package to optimize".into()));
+                };
+                if *package != snapshot {
+                    done.finish(Err(
+                        "Пакет изменился во время оптимизации, изменения не применены.".into(),
+                    ));
+                    return Err(FileError::LoaderError(
+                        "Package was changed while its images were optimized".into(),
+                    ));
+                }
+
+                *package = optimized;
+                if history.record(package, EditKind::Media) {
+                    *dirty = true;
+                }
+                storage.sync_package(&storage_key, package);
+                done.finish(Ok(report));
+                Ok(())
+            },
+        );
+        self.app.files_queue.push(queue);
+    }
+
+    /// Import questions from a spreadsheet in the background, see
+    /// [`Package::import_spreadsheet`]. The received import is applied
+    /// with [`ImportedSpreadsheet::apply`].
+    pub fn import_spreadsheet(
+        &mut self,
+        sheet: Spreadsheet,
+        mapping: ColumnMapping,
+        media_dir: Option<PathBuf>,
+    ) -> oneshot::Receiver<ImportedSpreadsheet> {
+        let PackageState::Active { ref package, ref storage_key, .. } = self.app.package_state
+        else {
+            unimplemented!("Package state mismatch for PackageContext");
+        };
+
+        let snapshot = package.clone();
+        let storage_key = storage_key.clone();
+        files::spawn_work(move || {
+            let mut package = snapshot.clone();
+            let report = package.import_spreadsheet(&sheet, &mapping, media_dir.as_deref());
+            ImportedSpreadsheet { storage_key, snapshot, package, report }
+        })
+    }
+
+    /// Size limits of package resources.
+    pub fn media_budget(&self) -> MediaBudget {
+        self.app.media.budget
+    }
+
+    /// Key of package resources in [`SharedPackageBytesStorage`].
+    pub fn storage_key(&self) -> &str {
+        match self.app.package_state {
+            PackageState::Active { ref storage_key, .. } => storage_key,
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    /// Format metadata and media probe of a package resource,
+    /// see [`SharedPackageBytesStorage::resource_info`].
+    pub fn resource_info(&self, id: &ResourceId) -> Option<(ResourceMeta, Option<MediaProbe>)> {
+        match self.app.package_state {
+            PackageState::Active { ref package, ref storage_key, .. } => {
+                self.app.storage.resource_info(storage_key, package, id)
+            },
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    /// File the package was opened from or saved to.
+    pub fn path(&self) -> Option<&Path> {
+        match self.app.package_state {
+            PackageState::Active { ref path, .. } => path.as_deref(),
+            _ => unimplemented!("Package state mismatch for PackageContext"),
+        }
+    }
+
+    /// Package has unsaved changes.
+    pub fn is_dirty(&self) -> bool {
+        match self.app.package_state {
+            PackageState::Active { dirty, .. } => dirty,
             _ => unimplemented!("Package state mismatch for PackageContext"),
         }
     }
@@ -166,26 +496,226 @@ impl AppContext<'_> {
         self.app.files_queue.push(loader);
     }
 
+    /// Pick a folder with an unpacked package, see [`Package::open_dir`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pick_package_folder(&mut self) {
+        let loader = files::pick_folder("Выбрать папку с распакованным пакетом", package_loader);
+        self.app.files_queue.push(loader);
+    }
+
+    /// Pick a package in the YAML authoring format, see [`Package::import_yaml`].
+    /// It's opened as a new package, so it's saved as `.siq`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pick_yaml_package(&mut self) {
+        let loader = files::pick_file(
+            "Выбрать пакет в формате YAML",
+            ("YAML", ["yaml", "yml"]),
+            yaml_package_loader,
+        );
+        self.app.files_queue.push(loader);
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn load_new_package(&mut self, path: impl AsRef<Path>) {
         let loader = files::load_file(path, package_loader);
         self.app.files_queue.push(loader);
     }
 
+    /// Save the package to the file it was opened from,
+    /// or pick a new file if there is none.
     pub fn save_package(&mut self) {
+        let PackageState::Active { ref package, ref path, ref storage_key, .. } =
+            self.app.package_state
+        else {
+            return;
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = path.as_ref().filter(|path| path.is_dir()) {
+            let saver = package_saver(package.clone(), storage_key.clone());
+            let package = package.clone();
+            let loader = files::save_folder(path, move |path| package.save_dir(path), saver);
+            self.app.files_queue.push(loader);
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = path {
+            let saver = package_saver(package.clone(), storage_key.clone());
+            let package = package.clone();
+            let loader = files::save_file(path, move || package.to_bytes().ok(), saver);
+            self.app.files_queue.push(loader);
+            return;
+        }
+
+        self.save_package_as();
+    }
+
+    /// Save the package to a newly picked file.
+    pub fn save_package_as(&mut self) {
+        let PackageState::Active { ref package, ref path, ref storage_key, .. } =
+            self.app.package_state
+        else {
+            return;
+        };
+
+        let file_name = path
+            .as_ref()
+            .filter(|path| !path.is_dir())
+            .and_then(|path| path.file_name())
+            .map(|file_name| file_name.to_string_lossy().to_string())
+            .unwrap_or_else(|| default_file_name(package));
+        let saver = package_saver(package.clone(), storage_key.clone());
+        let package = package.clone();
+        let loader = files::save_to(
+            "Сохранить пакет с вопросами",
+            file_name,
+            move || package.to_bytes().ok(),
+            saver,
+        );
+        self.app.files_queue.push(loader);
+    }
+
+    /// Save the package unpacked into a newly picked folder, see
+    /// [`Package::save_dir`]. A folder with other files gets a new
+    /// subfolder named after the package.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_package_to_folder(&mut self) {
+        let PackageState::Active { ref package, ref storage_key, .. } = self.app.package_state
+        else {
+            return;
+        };
+
+        let saver = package_saver(package.clone(), storage_key.clone());
+        let package = package.clone();
+        let loader = files::save_to_folder(
+            "Сохранить распакованный пакет в папку",
+            move |path| {
+                let path = if Package::is_dir_writable(path)? {
+                    path.to_owned()
+                } else {
+                    let file_name = default_file_name(&package);
+                    path.join(file_name.trim_end_matches(".siq"))
+                };
+                package.save_dir(&path).map(|_| path)
+            },
+            saver,
+        );
+        self.app.files_queue.push(loader);
+    }
+
+    /// Pick a spreadsheet to import questions from into the active
+    /// package, see [`Package::import_spreadsheet`].
+    pub fn pick_spreadsheet(&mut self) {
+        let loader = files::pick_file(
+            "Выбрать таблицу с вопросами",
+            ("Таблица", ["xlsx", "csv"]),
+            spreadsheet_loader,
+        );
+        self.app.files_queue.push(loader);
+    }
+
+    /// Pick a folder with media files of the imported spreadsheet.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pick_spreadsheet_media(&mut self) {
+        let loader = files::pick_folder(
+            "Выбрать папку с медиафайлами",
+            |_: Vec<u8>, path: &Path, app: &mut EditorApp| -> LoadingResult<()> {
+                if let Some(import) = &mut app.spreadsheet_import {
+                    import.set_media_dir(path.to_owned());
+                }
+                Ok(())
+            },
+        );
+        self.app.files_queue.push(loader);
+    }
+
+    /// Export questions of the package into an XLSX or a CSV file
+    /// with the `extension`, see [`Package::to_spreadsheet`].
+    pub fn export_spreadsheet(&mut self, extension: &'static str) {
         let PackageState::Active { ref package, .. } = self.app.package_state else {
             return;
         };
 
+        let file_name = Path::new(&default_file_name(package)).with_extension(extension);
+        let sheet = package.to_spreadsheet();
+        let loader = files::save_to(
+            "Экспорт вопросов в таблицу",
+            file_name.display(),
+            move || match extension {
+                "csv" => sheet.to_csv().ok(),
+                _ => sheet.to_xlsx().ok(),
+            },
+            |_: Vec<u8>, _: &Path, _: &mut EditorApp| -> LoadingResult<()> { Ok(()) },
+        );
+        self.app.files_queue.push(loader);
+    }
+
+    /// Export a printable host script of the package into an HTML
+    /// or a Markdown file with the `extension`, see
+    /// [`Package::to_host_script_html`].
+    pub fn export_host_script(&mut self, extension: &'static str) {
+        let PackageState::Active { ref package, .. } = self.app.package_state else {
+            return;
+        };
+
+        let file_name = Path::new(&default_file_name(package)).with_extension(extension);
         let package = package.clone();
-        files::save_to("Сохранить пакет с вопросами", "pack.siq", move || package.to_bytes().ok());
+        let loader = files::save_to(
+            "Экспорт сценария ведущего",
+            file_name.display(),
+            move || match extension {
+                "md" => Some(package.to_host_script_markdown().into_bytes()),
+                _ => Some(package.to_host_script_html().into_bytes()),
+            },
+            |_: Vec<u8>, _: &Path, _: &mut EditorApp| -> LoadingResult<()> { Ok(()) },
+        );
+        self.app.files_queue.push(loader);
     }
 
-    pub fn pick_new_image_for(&mut self, idx: QuestionIdx) {
+    /// Export the package in the YAML authoring format with its media
+    /// files next to it, see [`Package::export_yaml`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_yaml(&mut self) {
+        let PackageState::Active { ref package, .. } = self.app.package_state else {
+            return;
+        };
+
+        let file_name = Path::new(&default_file_name(package)).with_extension("yaml");
+        let package = package.clone();
+        let loader = files::save_to_path(
+            "Экспорт пакета в YAML",
+            file_name.display(),
+            move |path| package.export_yaml(path),
+            |_: Vec<u8>, _: &Path, _: &mut EditorApp| -> LoadingResult<()> { Ok(()) },
+        );
+        self.app.files_queue.push(loader);
+    }
+
+    /// Pick a resource file for a [`Question`] atom. It either replaces
+    /// resource of an atom at `atom_index`, or pushes a new atom.
+    pub fn pick_atom_resource(
+        &mut self,
+        idx: QuestionIdx,
+        kind: AtomKind,
+        atom_index: Option<usize>,
+    ) {
+        let (title, filter): (_, (_, &'static [&'static str])) = match kind {
+            AtomKind::Image => {
+                ("Выберите изображение", ("Image", &["png", "jpg", "jpeg", "webp", "bmp", "gif"]))
+            },
+            AtomKind::Voice => ("Выберите аудио", ("Audio", &["mp3", "ogg", "wav"])),
+            AtomKind::Video => ("Выберите видео", ("Video", &["mp4", "webm"])),
+            AtomKind::Text => return,
+        };
+        let PackageState::Active { ref storage_key, .. } = self.app.package_state else {
+            return;
+        };
+        let storage_key = storage_key.clone();
         let loader = files::pick_file(
-            "Выберите изображение",
-            ("Image", ["png", "jpg", "jpeg"]),
-            image_loader(idx),
+            title,
+            (filter.0, filter.1.iter().copied()),
+            resource_loader(idx, kind, atom_index, storage_key),
         );
         self.app.files_queue.push(loader);
     }
@@ -193,55 +723,222 @@ impl AppContext<'_> {
 
 /// Adapter for [`Package`] to use with [`FileLoader`].
 fn package_loader(buffer: Vec<u8>, path: &Path, app: &mut EditorApp) -> LoadingResult<()> {
-    let package = Package::from_zip_buffer(buffer).map_err(FileError::ArchiveError)?;
-
-    // load all images into memory
-    for (id, bytes) in &package.resources {
-        app.storage.insert(id, &package, bytes.clone());
+    let package =
+        if path.is_dir() { Package::open_dir(path) } else { Package::from_zip_buffer(buffer) };
+    let package = package.map_err(FileError::ArchiveError)?;
+
+    // file is already open in another tab
+    match app.find_tab(path) {
+        Some(index) => app.activate_tab(index),
+        None => app.open_package(PackageState::open(package, Some(path.to_owned()))),
     }
+    remember_recent_file(app, path);
+
+    Ok(())
+}
 
-    app.package_state = PackageState::Active { package, selected: None };
+/// Adapter for a [`Package`] in the YAML authoring format to use with
+/// [`FileLoader`]. Media files are read next to the YAML file.
+#[cfg(not(target_arch = "wasm32"))]
+fn yaml_package_loader(buffer: Vec<u8>, path: &Path, app: &mut EditorApp) -> LoadingResult<()> {
+    let yaml = String::from_utf8(buffer).map_err(|err| {
+        FileError::ArchiveError(std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    })?;
+    let media_dir = path.parent().unwrap_or(Path::new(""));
+    let package = Package::from_yaml(&yaml, media_dir).map_err(FileError::ArchiveError)?;
+    app.open_package(PackageState::open(package, None));
+
+    Ok(())
+}
 
-    // update recent files
+/// Adapter for a saved [`Package`] to use with [`FileLoader`]. The package
+/// is clean only if it hasn't changed since the `snapshot` was saved.
+fn package_saver(snapshot: Package, storage_key: String) -> impl FileLoader {
+    move |_: Vec<u8>, path: &Path, app: &mut EditorApp| -> LoadingResult<()> {
+        let Some(PackageState::Active {
+            ref package,
+            path: ref mut package_path,
+            ref mut dirty,
+            ..
+        }) = app.find_package_mut(&storage_key)
+        else {
+            return Err(FileError::LoaderError("Saved package is no longer open".into()));
+        };
+
+        *package_path = Some(path.to_owned());
+        *dirty = *package != snapshot;
+        remember_recent_file(app, path);
+
+        Ok(())
+    }
+}
+
+/// Put the file on top of recent files.
+fn remember_recent_file(app: &mut EditorApp, path: &Path) {
     app.recent_files.remove(path);
     app.recent_files.insert(path.to_owned());
     app.recent_files = std::mem::take(&mut app.recent_files).into_iter().take(10).collect();
+}
 
-    Ok(())
+/// File name for a package which was never saved.
+fn default_file_name(package: &Package) -> String {
+    let name = package
+        .name
+        .trim()
+        .chars()
+        .map(|char| if r#"\/:*?"<>|"#.contains(char) || char.is_control() { '_' } else { char })
+        .collect::<String>();
+    if name.is_empty() { "pack.siq".to_string() } else { format!("{name}.siq") }
 }
 
-/// Adapter for [`Atom`] images to use with [`FileLoader`].
-fn image_loader(idx: QuestionIdx) -> impl FileLoader {
+/// Adapter for [`Atom`] resources to use with [`FileLoader`].
+/// Images are optimized in the background before they are added.
+fn resource_loader(
+    idx: QuestionIdx,
+    kind: AtomKind,
+    atom_index: Option<usize>,
+    storage_key: String,
+) -> impl FileLoader {
     move |bytes: Vec<u8>, path: &Path, app: &mut EditorApp| -> LoadingResult<()> {
-        let PackageState::Active { ref mut package, .. } = app.package_state else {
-            return Err(FileError::LoaderError("No active package to load an image".into()));
-        };
+        let name = path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().to_string())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if kind != AtomKind::Image || !app.media.optimize_on_import {
+            return add_resource(app, idx, kind, atom_index, &storage_key, bytes, name);
+        }
 
-        let id = ResourceId::image(
-            path.file_name()
-                .map(|file_name| file_name.to_string_lossy().to_string())
-                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        let options = app.media.optimization;
+        let storage_key = storage_key.clone();
+        let queue = files::process(
+            move || Ok(optimize_image(&options, bytes, name)),
+            move |(bytes, name), app: &mut EditorApp| {
+                add_resource(app, idx, kind, atom_index, &storage_key, bytes, name)
+            },
         );
-        let bytes = Arc::from(bytes.into_boxed_slice());
-        package.resources.insert(id.clone(), bytes);
+        app.files_queue.push(queue);
+        Ok(())
+    }
+}
 
-        let Some(body) =
-            app.storage.insert(&id, package, package.resources.get(&id).unwrap().clone())
+/// Spreadsheet imported by [`PackageContext::import_spreadsheet`].
+#[derive(Debug)]
+pub struct ImportedSpreadsheet {
+    storage_key: String,
+    /// The package before the import.
+    snapshot: Package,
+    package: Package,
+    report: SpreadsheetReport,
+}
+
+impl ImportedSpreadsheet {
+    /// Replace the package with the imported one, unless
+    /// it was changed or closed during the import.
+    pub fn apply(self, app: &mut EditorApp) -> Result<SpreadsheetReport, String> {
+        let storage = app.storage.clone();
+        let Some(PackageState::Active { ref mut package, ref mut history, ref mut dirty, .. }) =
+            app.find_package_mut(&self.storage_key)
         else {
-            return Err(FileError::LoaderError("Can't load image bytes into memory".into()));
+            return Err("Пакет был закрыт во время импорта.".into());
         };
+        if *package != self.snapshot {
+            return Err("Пакет изменился во время импорта, вопросы не добавлены.".into());
+        }
 
-        let Some(question) = package.get_question_mut(idx) else {
-            return Err(FileError::LoaderError(
-                format!("Can't add image '{body}' to question with idx {idx}").into(),
-            ));
-        };
-        question.scenario.push(Atom {
-            kind: AtomKind::Image,
-            body: body.to_string(),
-            ..Atom::default()
-        });
+        *package = self.package;
+        if history.record(package, EditKind::Structure) {
+            *dirty = true;
+        }
+        storage.sync_package(&self.storage_key, package);
+        Ok(self.report)
+    }
+}
 
-        Ok(())
+/// Callback of [`PackageContext::optimize_images`], which reports
+/// a failure if it's dropped before the optimisation is done,
+/// e.g. when the background work panicked.
+struct OptimizationDone<F: FnOnce(Result<OptimizationReport, String>)>(Option<F>);
+
+impl<F: FnOnce(Result<OptimizationReport, String>)> OptimizationDone<F> {
+    fn finish(mut self, result: Result<OptimizationReport, String>) {
+        if let Some(done) = self.0.take() {
+            done(result);
+        }
     }
 }
+
+impl<F: FnOnce(Result<OptimizationReport, String>)> Drop for OptimizationDone<F> {
+    fn drop(&mut self) {
+        if let Some(done) = self.0.take() {
+            done(Err("Не удалось оптимизировать изображения.".into()));
+        }
+    }
+}
+
+/// Optimize an added image, see [`ImageOptimization::optimize`].
+/// Returns the original image if it's already optimized or can't be.
+fn optimize_image(options: &ImageOptimization, bytes: Vec<u8>, name: String) -> (Vec<u8>, String) {
+    match options.optimize(&bytes) {
+        Ok(Some(optimized)) => {
+            info!("Optimized image '{name}': {} → {} bytes", bytes.len(), optimized.bytes.len());
+            let name = optimized.file_name(&name);
+            (optimized.bytes, name)
+        },
+        Ok(None) => (bytes, name),
+        Err(err) => {
+            warn!("Unable to optimize image '{name}': {err}");
+            (bytes, name)
+        },
+    }
+}
+
+/// Add a resource with `name` to the question at `idx`, either as
+/// a new atom or into the existing atom at `atom_index`.
+fn add_resource(
+    app: &mut EditorApp,
+    idx: QuestionIdx,
+    kind: AtomKind,
+    atom_index: Option<usize>,
+    storage_key: &str,
+    bytes: Vec<u8>,
+    name: String,
+) -> LoadingResult<()> {
+    let storage = app.storage.clone();
+    let Some(PackageState::Active { ref mut package, ref mut history, ref mut dirty, .. }) =
+        app.find_package_mut(storage_key)
+    else {
+        return Err(FileError::LoaderError("No open package to load a resource".into()));
+    };
+
+    let atom = Atom { kind, body: format!("@{name}"), ..Atom::default() };
+    let Some(id) = atom.resource() else {
+        return Err(FileError::LoaderError(
+            format!("Atom of kind {kind:?} can't have resources").into(),
+        ));
+    };
+
+    if let Some(mismatch) = id.inspect(&bytes).mismatch {
+        warn!("Resource '{}' doesn't fit its name: {mismatch:?}", id.name());
+    }
+    // an optimized image is renamed, so it can collide with another resource
+    let bytes: Arc<[u8]> = Arc::from(bytes.into_boxed_slice());
+    let id = package.add_resource(id, bytes.clone());
+    storage.insert(&id, storage_key, bytes);
+
+    let Some(question) = package.get_question_mut(idx) else {
+        return Err(FileError::LoaderError(
+            format!("Can't add resource '{}' to question with idx {idx}", id.name()).into(),
+        ));
+    };
+    match atom_index.and_then(|index| question.scenario.get_mut(index)) {
+        Some(existing) => {
+            existing.kind = atom.kind;
+            existing.body = id.atom_body();
+        },
+        None => question.scenario.push(Atom { body: id.atom_body(), ..atom }),
+    }
+    history.record(package, EditKind::Media);
+    *dirty = true;
+
+    Ok(())
+}
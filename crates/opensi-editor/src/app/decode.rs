@@ -0,0 +1,165 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use log::error;
+
+/// Amount of threads which decode resources at once, so a grid of
+/// large photos doesn't decode all of them at full size together.
+#[cfg(not(target_arch = "wasm32"))]
+const DECODE_WORKERS: usize = 2;
+
+/// Generation of the latest decoding job, see [`Decoding::Pending`].
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Value which is decoded or being decoded.
+#[derive(Debug)]
+enum Decoding<T> {
+    /// Value is decoded by the job of the generation. Results of
+    /// older jobs are dropped, if the value was forgotten and
+    /// requested again in the meantime.
+    Pending(u64),
+    Ready(Arc<T>),
+    Failed(String),
+}
+
+/// The value for `uri` is still waiting for the job of the `generation`.
+fn is_pending<T>(
+    entries: &dashmap::DashMap<String, Decoding<T>>,
+    uri: &str,
+    generation: u64,
+) -> bool {
+    matches!(entries.get(uri).as_deref(), Some(Decoding::Pending(current)) if *current == generation)
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Run a job on one of [`DECODE_WORKERS`] background threads.
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn(job: Job) {
+    use std::sync::{Mutex, OnceLock, mpsc};
+
+    static QUEUE: OnceLock<mpsc::Sender<Job>> = OnceLock::new();
+    let queue = QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..DECODE_WORKERS {
+            let receiver = receiver.clone();
+            let worker = move || {
+                loop {
+                    let job = receiver.lock().unwrap_or_else(|err| err.into_inner()).recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                }
+            };
+            if let Err(err) =
+                std::thread::Builder::new().name(format!("decode-{index}")).spawn(worker)
+            {
+                error!("Unable to start a decoding thread: {err}");
+            }
+        }
+        sender
+    });
+    if let Err(mpsc::SendError(job)) = queue.send(job) {
+        // no workers are running
+        job();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn(job: Job) {
+    job();
+}
+
+/// Values decoded from package resources in the background, by
+/// resource uri, e.g. image previews or audio waveforms.
+#[derive(Debug)]
+pub struct DecodeCache<T> {
+    entries: Arc<dashmap::DashMap<String, Decoding<T>>>,
+}
+
+impl<T> Default for DecodeCache<T> {
+    fn default() -> Self {
+        Self { entries: Arc::default() }
+    }
+}
+
+impl<T> Clone for DecodeCache<T> {
+    fn clone(&self) -> Self {
+        Self { entries: self.entries.clone() }
+    }
+}
+
+impl<T: Send + Sync + 'static> DecodeCache<T> {
+    /// State of the value for `uri`: `None` if it was never requested,
+    /// `Some(Ok(None))` while it's being decoded.
+    pub fn get(&self, uri: &str) -> Option<Result<Option<Arc<T>>, String>> {
+        let entry = self.entries.get(uri)?;
+        Some(match &*entry {
+            Decoding::Pending(_) => Ok(None),
+            Decoding::Ready(value) => Ok(Some(value.clone())),
+            Decoding::Failed(err) => Err(err.clone()),
+        })
+    }
+
+    /// Remember that the value for `uri` can't be decoded, until it's forgotten.
+    pub fn fail(&self, uri: &str, err: String) {
+        self.entries.insert(uri.to_string(), Decoding::Failed(err));
+    }
+
+    /// Decode the value for `uri` in the background and repaint once it's done.
+    pub fn decode(
+        &self,
+        ctx: &egui::Context,
+        uri: &str,
+        decode: impl FnOnce() -> Result<T, String> + Send + 'static,
+    ) {
+        let generation = GENERATION.fetch_add(1, Ordering::Relaxed);
+        self.entries.insert(uri.to_string(), Decoding::Pending(generation));
+        let entries = self.entries.clone();
+        let uri = uri.to_string();
+        let ctx = ctx.clone();
+        spawn(Box::new(move || {
+            // the value could be forgotten while the job was queued
+            if !is_pending(&entries, &uri, generation) {
+                return;
+            }
+            let decoded = match decode() {
+                Ok(value) => Decoding::Ready(Arc::new(value)),
+                Err(err) => {
+                    error!("Unable to decode {uri}: {err}");
+                    Decoding::Failed(err)
+                },
+            };
+            // or forgotten and requested again while it was decoded
+            if let Some(mut entry) = entries.get_mut(&uri) {
+                if matches!(*entry, Decoding::Pending(current) if current == generation) {
+                    *entry = decoded;
+                }
+            }
+            ctx.request_repaint();
+        }));
+    }
+
+    pub fn forget(&self, uri: &str) {
+        self.entries.remove(uri);
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+
+    /// Memory used by decoded values, measured with `size`.
+    pub fn byte_size(&self, size: impl Fn(&T) -> usize) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| match &*entry {
+                Decoding::Ready(value) => size(value),
+                Decoding::Pending(_) | Decoding::Failed(_) => 0,
+            })
+            .sum()
+    }
+}
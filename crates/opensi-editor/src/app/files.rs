@@ -3,9 +3,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use log::{error, warn};
-#[cfg(not(target_arch = "wasm32"))]
-use tokio;
+use log::error;
 #[cfg(target_arch = "wasm32")]
 use tokio_with_wasm::alias as tokio;
 
@@ -13,6 +11,16 @@ use tokio::sync::oneshot;
 
 use crate::EditorApp;
 
+/// Shortcut to save the package in place.
+pub const SAVE_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::S);
+
+/// Shortcut to save the package to a new file.
+pub const SAVE_AS_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::S,
+);
+
 /// Result for in-progress loading.
 pub type LoadingResult<T> = Result<T, FileError>;
 
@@ -30,31 +38,53 @@ pub enum FileError {
     NoFileSelected,
     #[error("Archive error: {0}")]
     ArchiveError(std::io::Error),
+    #[error("File writing error: {0}")]
+    WriteError(std::io::Error),
 }
 
 /// Async file loader queue that can mutate [`EditorApp`] upon loading.
 pub struct FilesQueue {
-    receiver: LoadingFileReceiver,
-    op: Option<Box<dyn FileLoader>>,
+    poll: Box<dyn FnMut(&mut EditorApp) -> bool>,
 }
 
 impl FilesQueue {
+    /// Queue which runs `op` with the result from `receiver` once it's received.
+    fn new<T: 'static>(
+        mut receiver: oneshot::Receiver<LoadingResult<T>>,
+        op: impl FnOnce(T, &mut EditorApp) -> LoadingResult<()> + 'static,
+    ) -> Self {
+        let mut op = Some(op);
+        let poll = move |app: &mut EditorApp| {
+            match receiver.try_recv() {
+                Ok(Ok(result)) => {
+                    if let Some(op) = op.take() {
+                        let _ = op(result, app).inspect_err(|err| {
+                            error!("Error running a loader: {err}");
+                        });
+                    }
+                    return true;
+                },
+                Ok(Err(err)) => {
+                    error!("Error loading file: {err}");
+                    return true;
+                },
+                Err(oneshot::error::TryRecvError::Closed) => return true,
+                Err(oneshot::error::TryRecvError::Empty) => {},
+            }
+            false
+        };
+        Self { poll: Box::new(poll) }
+    }
+
+    /// Queue which runs `loader` with the file from `receiver` once it's received.
+    fn with_loader(receiver: LoadingFileReceiver, loader: impl FileLoader + 'static) -> Self {
+        Self::new(receiver, move |(bytes, path): (Vec<u8>, PathBuf), app: &mut EditorApp| {
+            loader.load(bytes, &path, app)
+        })
+    }
+
     pub fn update(&mut self, app: &mut EditorApp) -> bool {
-        match self.receiver.try_recv() {
-            Ok(Ok((data, file))) => {
-                if let Some(op) = self.op.take() {
-                    let _ = op.load(data, file.as_path(), app).inspect_err(|err| {
-                        error!("Error running a loader: {err}");
-                    });
-                }
-                return true;
-            },
-            Ok(Err(err)) => {
-                error!("Error loading file: {err}");
-            },
-            Err(_) => {},
-        }
-        false
+        (self.poll)(app)
     }
 }
 
@@ -79,6 +109,10 @@ where
 pub fn load_file(path: impl AsRef<Path>, loader: impl FileLoader + 'static) -> FilesQueue {
     fn read_file(file: impl AsRef<Path>) -> LoadingFileResult {
         let file = file.as_ref();
+        // folders are read by the loader itself
+        if file.is_dir() {
+            return Ok((vec![], file.to_owned()));
+        }
         let buffer = std::fs::read(file).map_err(FileError::ArchiveError)?;
         Ok((buffer, file.to_owned()))
     }
@@ -89,7 +123,7 @@ pub fn load_file(path: impl AsRef<Path>, loader: impl FileLoader + 'static) -> F
         Err(_) => error!("Error sending imported package !"),
     };
 
-    FilesQueue { receiver, op: Some(Box::new(loader)) }
+    FilesQueue::with_loader(receiver, loader)
 }
 
 /// Show a file picker and return a [`FileLoader`] with this file:
@@ -134,33 +168,224 @@ pub fn pick_file(
             Err(_) => error!("Error sending picked file"),
         };
     });
-    FilesQueue { receiver, op: Some(Box::new(loader)) }
+    FilesQueue::with_loader(receiver, loader)
 }
 
-/// Show a dialog to save file.
-pub fn save_to(
+/// Show a folder picker and return a [`FileLoader`] with this
+/// folder and no bytes: it will run `op` once the folder is picked.
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use = "Use loader to properly load a folder"]
+pub fn pick_folder(title: impl ToString, loader: impl FileLoader + 'static) -> FilesQueue {
+    let title = title.to_string();
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let _handle = tokio::spawn(async move {
+        let result = rfd::AsyncFileDialog::new()
+            .set_title(title)
+            .set_directory(default_directory())
+            .pick_folder()
+            .await
+            .map(|folder| (vec![], folder.path().to_owned()))
+            .ok_or(FileError::NoFileSelected);
+        match sender.send(result) {
+            Ok(_) => {},
+            Err(_) => error!("Error sending picked folder"),
+        };
+    });
+    FilesQueue::with_loader(receiver, loader)
+}
+
+/// Write a folder directly with `save` and return a [`FileLoader`]:
+/// it will run `op` with no bytes once the folder is saved. The folder
+/// is written off the UI thread.
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use = "Use loader to properly save a folder"]
+pub fn save_folder(
+    path: impl AsRef<Path>,
+    save: impl FnOnce(&Path) -> std::io::Result<()> + Send + 'static,
+    loader: impl FileLoader + 'static,
+) -> FilesQueue {
+    let path = path.as_ref().to_owned();
+    let receiver =
+        spawn_work(move || save(&path).map(|_| (vec![], path)).map_err(FileError::WriteError));
+    FilesQueue::with_loader(receiver, loader)
+}
+
+/// Show a folder picker, write into the folder with `save` and return
+/// a [`FileLoader`]: it will run `op` with no bytes and the folder
+/// returned by `save` once it's saved.
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use = "Use loader to properly save a folder"]
+pub fn save_to_folder(
+    title: impl ToString,
+    save: impl FnOnce(&Path) -> std::io::Result<PathBuf> + Send + 'static,
+    loader: impl FileLoader + 'static,
+) -> FilesQueue {
+    let title = title.to_string();
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let _handle = tokio::spawn(async move {
+        let result = match rfd::AsyncFileDialog::new()
+            .set_title(title)
+            .set_directory(default_directory())
+            .set_can_create_directories(true)
+            .pick_folder()
+            .await
+        {
+            Some(folder) => {
+                save(folder.path()).map(|path| (vec![], path)).map_err(FileError::WriteError)
+            },
+            None => Err(FileError::NoFileSelected),
+        };
+        match sender.send(result) {
+            Ok(_) => {},
+            Err(_) => error!("Error sending saved folder"),
+        };
+    });
+    FilesQueue::with_loader(receiver, loader)
+}
+
+/// Show a dialog to save file, write it with `save` and return
+/// a [`FileLoader`]: it will run `op` with no bytes once it's saved.
+/// Unlike [`save_to`], `save` can write other files next to it.
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use = "Use loader to properly save a file"]
+pub fn save_to_path(
     title: impl ToString,
     file_name: impl ToString,
-    generate_data: impl FnOnce() -> Option<Vec<u8>> + Send + Sync + 'static,
-) {
+    save: impl FnOnce(&Path) -> std::io::Result<()> + Send + 'static,
+    loader: impl FileLoader + 'static,
+) -> FilesQueue {
     let title = title.to_string();
     let file_name = file_name.to_string();
 
+    let (sender, receiver) = tokio::sync::oneshot::channel();
     let _handle = tokio::spawn(async move {
+        let result = match rfd::AsyncFileDialog::new()
+            .set_title(title)
+            .set_directory(default_directory())
+            .set_file_name(file_name)
+            .save_file()
+            .await
+        {
+            Some(file) => {
+                let path = file.path().to_owned();
+                save(&path).map(|_| (vec![], path)).map_err(FileError::WriteError)
+            },
+            None => Err(FileError::NoFileSelected),
+        };
+        match sender.send(result) {
+            Ok(_) => {},
+            Err(_) => error!("Error sending saved file"),
+        };
+    });
+    FilesQueue::with_loader(receiver, loader)
+}
+
+/// Write a file directly on systems that support direct file
+/// systems and return a [`FileLoader`]: it will run `op` once
+/// the file is saved. The file is written off the UI thread.
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use = "Use loader to properly save a file"]
+pub fn save_file(
+    path: impl AsRef<Path>,
+    generate_data: impl FnOnce() -> Option<Vec<u8>> + Send + 'static,
+    loader: impl FileLoader + 'static,
+) -> FilesQueue {
+    let file = path.as_ref().to_owned();
+    let receiver = spawn_work(move || {
+        let buffer = generate_data().ok_or(FileError::LoaderError("No bytes to save".into()))?;
+        write_file_atomically(&file, &buffer).map_err(FileError::WriteError)?;
+        Ok((buffer, file))
+    });
+    FilesQueue::with_loader(receiver, loader)
+}
+
+/// Write `bytes` into a temporary file next to `file` and rename it
+/// into place, so `file` isn't left half-written if writing fails.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_file_atomically(file: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+    let temp = file.with_file_name(format!(".{file_name}.tmp"));
+    std::fs::write(&temp, bytes).and_then(|_| std::fs::rename(&temp, file)).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Show a dialog to save file and return a [`FileLoader`]
+/// with this file: it will run `op` once the file is saved.
+#[must_use = "Use loader to properly save a file"]
+pub fn save_to(
+    title: impl ToString,
+    file_name: impl ToString,
+    generate_data: impl FnOnce() -> Option<Vec<u8>> + Send + Sync + 'static,
+    loader: impl FileLoader + 'static,
+) -> FilesQueue {
+    async fn show_save_dialog(
+        title: String,
+        file_name: String,
+        generate_data: impl FnOnce() -> Option<Vec<u8>>,
+    ) -> LoadingFileResult {
         let file = rfd::AsyncFileDialog::new()
             .set_title(title)
             .set_directory(default_directory())
             .set_file_name(file_name)
             .save_file()
-            .await?;
+            .await
+            .ok_or(FileError::NoFileSelected)?;
 
-        if let Some(bytes) = generate_data() {
-            file.write(&bytes).await.ok()
-        } else {
-            warn!("File saving interrupted: no bytes");
-            Some(())
-        }
+        let buffer = generate_data().ok_or(FileError::LoaderError("No bytes to save".into()))?;
+        file.write(&buffer).await.map_err(FileError::WriteError)?;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let path = file.path().to_owned();
+        #[cfg(target_arch = "wasm32")]
+        let path = file.file_name().into();
+
+        Ok((buffer, path))
+    }
+
+    let title = title.to_string();
+    let file_name = file_name.to_string();
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let _handle = tokio::spawn(async move {
+        let result = show_save_dialog(title, file_name, generate_data).await;
+        match sender.send(result) {
+            Ok(_) => {},
+            Err(_) => error!("Error sending saved file"),
+        };
     });
+    FilesQueue::with_loader(receiver, loader)
+}
+
+/// Run `work` off the UI thread and return a [`FilesQueue`]:
+/// it will run `op` with the result once the work is done.
+#[must_use = "Use queue to apply the result of the work"]
+pub fn process<T: Send + 'static>(
+    work: impl FnOnce() -> LoadingResult<T> + Send + 'static,
+    op: impl FnOnce(T, &mut EditorApp) -> LoadingResult<()> + 'static,
+) -> FilesQueue {
+    FilesQueue::new(spawn_work(work), op)
+}
+
+/// Run `work` off the UI thread and receive its result once it's done.
+pub fn spawn_work<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> oneshot::Receiver<T> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let job = move || {
+        if sender.send(work()).is_err() {
+            error!("Error sending processed result");
+        }
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let _handle = tokio::task::spawn_blocking(job);
+    // web workers don't share memory with the app, so the work
+    // runs on the event loop after the current frame instead
+    #[cfg(target_arch = "wasm32")]
+    let _handle = tokio::spawn(async move { job() });
+    receiver
 }
 
 /// Get default directory for file pickers.
@@ -0,0 +1,98 @@
+use crate::{
+    app::EditorApp,
+    element::{ModalExt, ModalWrapper},
+    icon_str,
+};
+
+/// Action which drops the active package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardedAction {
+    /// Close the active package.
+    Close,
+    /// Close the app window, once every package is either saved
+    /// or discarded one by one.
+    Quit,
+}
+
+/// Stage of a [`GuardedAction`] on its way to execution.
+#[derive(Debug, Default)]
+enum GuardState {
+    #[default]
+    Idle,
+    /// User decides whether to save, discard or cancel.
+    Asking(GuardedAction),
+    /// Package is being saved before the action.
+    Saving(GuardedAction),
+    /// Action can be executed.
+    Ready(GuardedAction),
+}
+
+/// Guard against losing unsaved changes of the active package: every
+/// action which drops it goes through save / discard / cancel prompt.
+#[derive(Debug, Default)]
+pub struct UnsavedGuard {
+    state: GuardState,
+}
+
+impl UnsavedGuard {
+    /// Request an action: it is ready right away without unsaved
+    /// changes, otherwise user is asked what to do with them.
+    pub fn request(&mut self, action: GuardedAction, dirty: bool) {
+        self.state = if dirty { GuardState::Asking(action) } else { GuardState::Ready(action) };
+    }
+
+    /// Take an action which is ready to be executed. Saving is finished
+    /// once there are no more `pending_files`: the action is dropped if
+    /// the package is still dirty after that.
+    pub fn poll(&mut self, dirty: bool, pending_files: bool) -> Option<GuardedAction> {
+        match std::mem::take(&mut self.state) {
+            GuardState::Ready(action) => Some(action),
+            GuardState::Saving(action) if pending_files => {
+                self.state = GuardState::Saving(action);
+                None
+            },
+            GuardState::Saving(action) if !dirty => Some(action),
+            GuardState::Saving(_) => None,
+            state => {
+                self.state = state;
+                None
+            },
+        }
+    }
+}
+
+/// Prompt to save unsaved changes before a [`GuardedAction`].
+pub fn unsaved_changes_modal(app: &mut EditorApp, ctx: &egui::Context) {
+    let GuardState::Asking(ref action) = app.guard.state else {
+        return;
+    };
+    let action = action.clone();
+
+    let mut modal = ModalWrapper::new(ctx, "unsaved-changes-modal");
+    modal.open();
+    modal.show(ctx, |ui| {
+        ui.modal_title(icon_str!(WARNING, "Несохранённые изменения"));
+        let name = app.package_ctx().map(|mut ctx| ctx.package().name.clone()).unwrap_or_default();
+        ui.label(format!("В пакете «{name}» есть несохранённые изменения. Сохранить их?"));
+
+        ui.modal_buttons(|ui| {
+            if ui.modal_confirm(icon_str!(FLOPPY_DISK_BACK, "Сохранить")).clicked() {
+                app.ctx().save_package();
+                app.guard.state = GuardState::Saving(action.clone());
+            }
+            if ui.modal_danger(icon_str!(TRASH, "Не сохранять")).clicked() {
+                app.guard.state = GuardState::Ready(action.clone());
+            }
+            if ui.modal_button(icon_str!(PROHIBIT, "Отмена")).clicked() {
+                app.guard.state = GuardState::Idle;
+            }
+        });
+    });
+
+    // closed by escape or click outside
+    if !ModalWrapper::new(ctx, "unsaved-changes-modal").is_open() {
+        if let GuardState::Asking(_) = app.guard.state {
+            app.guard.state = GuardState::Idle;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use opensi_core::prelude::*;
+
+/// Max amount of stored undo steps.
+const HISTORY_LIMIT: usize = 100;
+
+/// Shortcut to undo the last edit.
+pub const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+
+/// Shortcut to redo the last undone edit.
+pub const REDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Z,
+);
+
+/// Kind of a single [`Package`] edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// Adding, removing, duplicating or moving nodes.
+    Structure,
+    /// Change of a single property. Consecutive changes of the same
+    /// widget are merged into one edit.
+    Property(egui::Id),
+    /// Import or optimisation of media resources.
+    Media,
+}
+
+impl EditKind {
+    /// Guess kind of the current edit by the widget user interacts with.
+    pub fn guess(ctx: &egui::Context) -> Option<Self> {
+        if egui::DragAndDrop::has_any_payload(ctx) {
+            return Some(Self::Structure);
+        }
+
+        let focused = ctx
+            .memory(|memory| memory.focused())
+            .filter(|&id| egui::TextEdit::load_state(ctx, id).is_some());
+        focused.or_else(|| ctx.dragged_id()).map(Self::Property)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Structure => "изменение структуры",
+            Self::Property(_) => "изменение свойства",
+            Self::Media => "изменение медиа",
+        }
+    }
+}
+
+/// Package snapshot before an edit.
+#[derive(Debug)]
+struct Edit {
+    kind: EditKind,
+    package: Package,
+}
+
+/// Undo/redo history of [`Package`] edits.
+///
+/// Each edit stores a snapshot of the package before it. Snapshots
+/// are cheap, since resources are shared between them.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    last: Option<Package>,
+    merging: Option<EditKind>,
+}
+
+impl History {
+    /// Record a new edit, if package has changed since the last one.
+    /// Returns `true` if it has.
+    pub fn record(&mut self, package: &Package, kind: EditKind) -> bool {
+        let Some(last) = self.last.as_mut() else {
+            self.last = Some(package.clone());
+            return false;
+        };
+        if last == package {
+            return false;
+        }
+
+        let previous = std::mem::replace(last, package.clone());
+        if self.merging != Some(kind) {
+            self.undo.push(Edit { kind, package: previous });
+            if self.undo.len() > HISTORY_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.merging = matches!(kind, EditKind::Property(_)).then_some(kind);
+        true
+    }
+
+    /// Stop merging property changes into the last edit.
+    pub fn seal(&mut self) {
+        self.merging = None;
+    }
+
+    /// Revert package to the state before the last edit.
+    pub fn undo(&mut self, package: &mut Package) -> bool {
+        let Some(edit) = self.undo.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(package, edit.package);
+        self.redo.push(Edit { kind: edit.kind, package: current });
+        self.last = Some(package.clone());
+        self.merging = None;
+        true
+    }
+
+    /// Reapply the last reverted edit.
+    pub fn redo(&mut self, package: &mut Package) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(package, edit.package);
+        self.undo.push(Edit { kind: edit.kind, package: current });
+        self.last = Some(package.clone());
+        self.merging = None;
+        true
+    }
+
+    /// Kind of the edit which would be reverted with [`History::undo`].
+    pub fn next_undo(&self) -> Option<EditKind> {
+        self.undo.last().map(|edit| edit.kind)
+    }
+
+    /// Kind of the edit which would be reapplied with [`History::redo`].
+    pub fn next_redo(&self) -> Option<EditKind> {
+        self.redo.last().map(|edit| edit.kind)
+    }
+}
@@ -0,0 +1,250 @@
+use opensi_core::prelude::*;
+
+use crate::{
+    app::{EditorApp, context::PackageContext},
+    element::{ModalExt, ModalWrapper, format_mismatch_text, size_name},
+    icon_str, icon_string,
+};
+
+/// Amount of the biggest resources to show when the whole
+/// package exceeds its budget.
+const BIGGEST_RESOURCES: usize = 5;
+
+const MEGABYTE: f64 = 1024.0 * 1024.0;
+
+/// Settings of media resources of packages.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MediaSettings {
+    /// Optimize images when they are added to a package.
+    pub optimize_on_import: bool,
+    pub optimization: ImageOptimization,
+    pub budget: MediaBudget,
+}
+
+impl Default for MediaSettings {
+    fn default() -> Self {
+        Self {
+            optimize_on_import: true,
+            optimization: ImageOptimization::default(),
+            budget: MediaBudget::default(),
+        }
+    }
+}
+
+/// Settings menu of media resources.
+pub fn media_settings(settings: &mut MediaSettings, ui: &mut egui::Ui) {
+    ui.checkbox(&mut settings.optimize_on_import, "Оптимизировать изображения при добавлении");
+    ui.add_enabled_ui(settings.optimize_on_import, |ui| {
+        optimization_edit(&mut settings.optimization, ui);
+    });
+
+    ui.separator();
+    ui.label("Ограничения размера");
+    egui::Grid::new("media-budget").num_columns(2).show(ui, |ui| {
+        let limits = [
+            ("Изображение", &mut settings.budget.max_image),
+            ("Аудио", &mut settings.budget.max_audio),
+            ("Видео", &mut settings.budget.max_video),
+            ("Весь пакет", &mut settings.budget.max_total),
+        ];
+        for (name, limit) in limits {
+            ui.label(name);
+            ui.add(
+                egui::DragValue::from_get_set(|value| {
+                    if let Some(value) = value {
+                        *limit = (value * MEGABYTE) as usize;
+                    }
+                    *limit as f64 / MEGABYTE
+                })
+                .range(0.1..=4096.0)
+                .speed(0.1)
+                .max_decimals(1)
+                .suffix(" МБ"),
+            );
+            ui.end_row();
+        }
+    });
+}
+
+fn optimization_edit(optimization: &mut ImageOptimization, ui: &mut egui::Ui) {
+    egui::Grid::new(ui.id().with("image-optimization")).num_columns(2).show(ui, |ui| {
+        ui.label("Максимальный размер");
+        ui.add(
+            egui::DragValue::new(&mut optimization.max_dimension)
+                .range(256..=8192)
+                .speed(16)
+                .suffix(" пикс."),
+        );
+        ui.end_row();
+
+        ui.label("Качество JPEG");
+        ui.add(egui::Slider::new(&mut optimization.jpeg_quality, 1..=100));
+        ui.end_row();
+    });
+}
+
+/// State of the image optimisation started from [`optimize_media_modal`].
+#[derive(Debug, Clone)]
+enum Optimization {
+    Running,
+    Done(OptimizationReport),
+    /// The optimisation wasn't applied, e.g. the package was
+    /// changed before it was done.
+    Failed(String),
+}
+
+/// Optimize all images of the active package and report
+/// the size before and after.
+pub fn optimize_media_modal(app: &mut EditorApp, modal: ModalWrapper, ctx: &egui::Context) {
+    let state_id = egui::Id::new("optimize-media-state");
+    modal.show(ctx, |ui| {
+        ui.modal_title(icon_str!(IMAGES, "Оптимизация медиа"));
+        ui.label(
+            "Изображения будут уменьшены, пересжаты и очищены от метаданных. \
+             Неподдерживаемые форматы будут преобразованы в JPEG или PNG.",
+        );
+        ui.add_space(8.0);
+        let state = ui.data(|data| data.get_temp::<Optimization>(state_id));
+        let is_running = matches!(state, Some(Optimization::Running));
+        ui.add_enabled_ui(!is_running, |ui| {
+            optimization_edit(&mut app.media.optimization, ui);
+        });
+
+        match &state {
+            Some(Optimization::Running) => {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Изображения оптимизируются…");
+                });
+            },
+            Some(Optimization::Done(report)) => {
+                ui.separator();
+                optimization_report(report, ui);
+            },
+            Some(Optimization::Failed(err)) => {
+                ui.separator();
+                ui.colored_label(ui.visuals().warn_fg_color, err);
+            },
+            None => {},
+        }
+
+        ui.modal_buttons(|ui| {
+            if ui.modal_button(icon_str!(X, "Закрыть")).clicked() && !is_running {
+                ui.data_mut(|data| data.remove::<Optimization>(state_id));
+            }
+            let options = app.media.optimization;
+            if let Some(mut pkg_ctx) = app.package_ctx() {
+                let button = egui::Button::new(icon_str!(MAGIC_WAND, "Оптимизировать"));
+                if ui.add_enabled(!is_running, button).clicked() {
+                    let ctx = ui.ctx().clone();
+                    pkg_ctx.optimize_images(options, move |report| {
+                        let state = report.map_or_else(Optimization::Failed, Optimization::Done);
+                        ctx.data_mut(|data| data.insert_temp(state_id, state));
+                    });
+                    ui.data_mut(|data| data.insert_temp(state_id, Optimization::Running));
+                }
+            }
+        });
+    });
+}
+
+fn optimization_report(report: &OptimizationReport, ui: &mut egui::Ui) {
+    if report.images == 0 {
+        ui.label("В пакете нет изображений.");
+        return;
+    }
+
+    ui.label(format!("Оптимизировано изображений: {} из {}", report.optimized, report.images));
+    let saved = report.bytes_before.saturating_sub(report.bytes_after);
+    let percent = saved as f64 / report.bytes_before.max(1) as f64 * 100.0;
+    ui.label(format!(
+        "Размер: {} → {} (−{percent:.0}%)",
+        size_name(report.bytes_before),
+        size_name(report.bytes_after),
+    ));
+
+    if !report.failed.is_empty() {
+        ui.colored_label(ui.visuals().warn_fg_color, "Не удалось обработать:");
+        egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+            for (id, err) in &report.failed {
+                ui.weak(format!("• {}: {err}", id.name()));
+            }
+        });
+    }
+}
+
+/// Live meter of package resources compared to the media budget,
+/// with resources which exceed it.
+pub fn media_budget(ctx: &mut PackageContext, ui: &mut egui::Ui) {
+    let budget = ctx.media_budget();
+    let report = ctx.package().check_budget(&budget);
+
+    let fraction = report.total as f32 / report.budget.max_total.max(1) as f32;
+    let color = if report.is_total_exceeded() {
+        ui.visuals().error_fg_color
+    } else if fraction > 0.8 {
+        ui.visuals().warn_fg_color
+    } else {
+        ui.visuals().selection.bg_fill
+    };
+    ui.add(egui::ProgressBar::new(fraction.min(1.0)).fill(color).text(format!(
+        "{} из {}",
+        size_name(report.total),
+        size_name(report.budget.max_total),
+    )));
+    ui.horizontal_wrapped(|ui| {
+        ui.weak(icon_string!(IMAGE, size_name(report.images)));
+        ui.weak(icon_string!(HEADPHONES, size_name(report.audio)));
+        ui.weak(icon_string!(VIDEO, size_name(report.video)));
+    });
+
+    let mismatches = ctx.package().format_mismatches();
+    if !mismatches.is_empty() {
+        ui.add_space(4.0);
+        ui.colored_label(ui.visuals().warn_fg_color, icon_str!(WARNING, "Неверный формат:"));
+        for (id, mismatch) in mismatches {
+            ui.label(id.name()).on_hover_text(format_mismatch_text(&id, mismatch));
+        }
+    }
+
+    let offenders = report.offenders().collect::<Vec<_>>();
+    let (title, resources) = if !offenders.is_empty() {
+        ("Превышают ограничение:", offenders)
+    } else if report.is_total_exceeded() {
+        ("Самые большие ресурсы:", report.resources.iter().take(BIGGEST_RESOURCES).collect())
+    } else {
+        return;
+    };
+
+    ui.add_space(4.0);
+    ui.colored_label(ui.visuals().warn_fg_color, icon_string!(WARNING, title));
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for usage in resources {
+            resource_usage(ctx, usage, ui);
+        }
+    });
+}
+
+fn resource_usage(ctx: &mut PackageContext, usage: &ResourceUsage, ui: &mut egui::Ui) {
+    let size = match usage.limit {
+        Some(limit) if usage.is_over_limit() => {
+            format!("{} (лимит {})", size_name(usage.size), size_name(limit))
+        },
+        _ => size_name(usage.size),
+    };
+    ui.label(format!("{} — {size}", usage.id.name()));
+    ui.horizontal_wrapped(|ui| {
+        if usage.questions.is_empty() {
+            ui.weak("Не используется");
+        }
+        for &idx in &usage.questions {
+            let price = ctx.package().get_question(idx).map(|question| question.price);
+            let text = icon_string!(NOTE, price.unwrap_or_default());
+            if ui.link(text).on_hover_text(format!("Вопрос {idx}")).clicked() {
+                ctx.select(idx.into());
+            }
+        }
+    });
+}
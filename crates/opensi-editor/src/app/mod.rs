@@ -1,11 +1,23 @@
 mod context;
+mod decode;
 mod files;
+mod guard;
+mod history;
+mod media;
+mod new_package;
 mod package_tab;
 mod package_tree;
+mod preview;
+mod pricing;
 mod question_tab;
+mod recovery;
 mod round_tab;
+mod selection_tab;
+mod spreadsheet;
 mod storage;
+mod tabs;
 mod theme_tab;
+mod waveform;
 mod workarea;
 
 use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
@@ -17,15 +29,23 @@ use opensi_core::prelude::*;
 use crate::{
     app::{
         context::{AppContext, PackageContext},
-        files::FilesQueue,
+        files::{FilesQueue, SAVE_AS_SHORTCUT, SAVE_SHORTCUT},
+        guard::{GuardedAction, UnsavedGuard},
+        history::{EditKind, History, REDO_SHORTCUT, UNDO_SHORTCUT},
+        media::MediaSettings,
+        preview::PreviewLoader,
+        recovery::{AUTOSAVE_INTERVAL, Autosave, Recovery},
+        spreadsheet::SpreadsheetImport,
         storage::{EguiPackageBytesLoader, SharedPackageBytesStorage},
     },
-    element::{ModalExt, ModalWrapper, empty_label},
+    element::{ModalExt, ModalWrapper, clipboard, empty_label, selection},
     icon, icon_format, icon_str, icon_string, style,
 };
 
-pub const FONT_REGULAR_ID: &'static str = "regular";
-pub const FONT_BOLD_ID: &'static str = "bold";
+pub use preview::question_thumbnail;
+
+pub const FONT_REGULAR_ID: &str = "regular";
+pub const FONT_BOLD_ID: &str = "bold";
 
 /// Main context for the whole app.
 /// Serialized fields are saved and restored.
@@ -36,24 +56,47 @@ pub struct EditorApp {
     show_tree: bool,
     show_properties: bool,
     recent_files: BTreeSet<PathBuf>,
+    templates: Vec<PackageTemplate>,
+    media: MediaSettings,
     #[serde(skip)]
     package_state: PackageState,
+    /// Tabs of open packages, see [`EditorApp::open_package`].
+    #[serde(skip)]
+    tabs: Vec<PackageState>,
+    #[serde(skip)]
+    active_tab: usize,
     #[serde(skip)]
     storage: SharedPackageBytesStorage,
     #[serde(skip)]
     files_queue: Vec<FilesQueue>,
+    #[serde(skip)]
+    guard: UnsavedGuard,
+    #[serde(skip)]
+    autosave: Autosave,
+    #[serde(skip)]
+    recovery: Vec<Recovery>,
+    #[serde(skip)]
+    spreadsheet_import: Option<SpreadsheetImport>,
 }
 
 impl Default for EditorApp {
     fn default() -> Self {
         Self {
             package_state: PackageState::None,
+            tabs: vec![],
+            active_tab: 0,
             storage: SharedPackageBytesStorage::default(),
             theme_name: style::default_theme().name().to_string(),
             show_tree: true,
             show_properties: true,
             recent_files: BTreeSet::new(),
+            templates: vec![],
+            media: MediaSettings::default(),
             files_queue: vec![],
+            guard: UnsavedGuard::default(),
+            autosave: Autosave::default(),
+            recovery: vec![],
+            spreadsheet_import: None,
         }
     }
 }
@@ -68,6 +111,7 @@ impl EditorApp {
         } else {
             Default::default()
         };
+        app.recovery = cc.storage.map(Recovery::load).unwrap_or_default();
 
         let mut fonts = egui::FontDefinitions::default();
         fonts.font_data.insert(
@@ -92,11 +136,12 @@ impl EditorApp {
 
         egui_extras::install_image_loaders(&cc.egui_ctx);
         cc.egui_ctx.add_bytes_loader(Arc::new(EguiPackageBytesLoader::new(&app.storage)));
+        cc.egui_ctx.add_image_loader(Arc::new(PreviewLoader::default()));
 
         if let Some(theme) = style::choose(&app.theme_name) {
             theme.apply(&cc.egui_ctx);
         } else {
-            error!("Unknown theme: {}", &app.theme_name);
+            error!("Unknown theme: {}", app.theme_name);
             app.theme_name = style::default_theme().name().to_string();
             style::default_theme().apply(&cc.egui_ctx);
         }
@@ -104,32 +149,152 @@ impl EditorApp {
         app
     }
 
-    pub fn ctx(&mut self) -> AppContext {
+    pub fn ctx(&mut self) -> AppContext<'_> {
         self.into()
     }
 
-    pub fn package_ctx(&mut self) -> Option<PackageContext> {
+    pub fn package_ctx(&mut self) -> Option<PackageContext<'_>> {
         PackageContext::try_new(self)
     }
 
     pub fn has_active_package(&self) -> bool {
         matches!(self.package_state, PackageState::Active { .. })
     }
+
+    /// Package is active and has unsaved changes.
+    fn is_dirty(&self) -> bool {
+        matches!(self.package_state, PackageState::Active { dirty: true, .. })
+    }
+
+    /// Run an action which drops the active package, after user
+    /// decides what to do with its unsaved changes.
+    fn guarded(&mut self, action: GuardedAction) {
+        if action == GuardedAction::Quit {
+            if let Some(index) = self.dirty_tab() {
+                self.activate_tab(index);
+            }
+        }
+        let dirty = self.is_dirty();
+        self.guard.request(action, dirty);
+    }
+
+    /// Forget replaced resources and their previews in egui and cache resources
+    /// which egui requested from open packages.
+    fn update_resources(&self, ctx: &egui::Context) {
+        for uri in self.storage.take_evicted() {
+            for preview in preview::preview_uris(&uri) {
+                ctx.forget_image(&preview);
+            }
+            ctx.forget_image(&uri);
+            waveform::forget_waveform(ctx, &uri);
+        }
+        if self.storage.has_missing() {
+            self.storage.restore_missing(self.package_states().filter_map(|state| match state {
+                PackageState::Active { package, storage_key, .. } => {
+                    Some((storage_key.as_str(), package))
+                },
+                PackageState::None => None,
+            }));
+        }
+    }
+
+    /// Show name of the active package and its unsaved
+    /// changes in the window title.
+    fn update_title(&self, ctx: &egui::Context) {
+        let title = match self.package_state {
+            PackageState::Active { ref package, dirty, .. } => {
+                let marker = if dirty { "*" } else { "" };
+                format!("{}{marker} — OpenSI Editor", package.name)
+            },
+            PackageState::None => "OpenSI Editor".to_string(),
+        };
+        let title_id = egui::Id::new("window-title");
+        if ctx.data(|data| data.get_temp::<String>(title_id)).as_ref() != Some(&title) {
+            ctx.data_mut(|data| data.insert_temp(title_id, title.clone()));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+        }
+    }
 }
 
 impl eframe::App for EditorApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
+        // keep the previous autosave until user decides what to do with it
+        if self.recovery.is_empty() {
+            let mut autosave = std::mem::take(&mut self.autosave);
+            autosave.update(&self.package_states().collect::<Vec<_>>(), storage);
+            self.autosave = autosave;
+        }
+    }
+
+    fn auto_save_interval(&self) -> std::time::Duration {
+        AUTOSAVE_INTERVAL
     }
 
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.update_resources(ctx);
+
         let mut files_queue = std::mem::take(&mut self.files_queue);
         files_queue.retain_mut(|queue| !queue.update(self));
         self.files_queue.extend(files_queue);
+        if let Some(storage) = frame.storage_mut() {
+            self.autosave.poll(storage);
+        }
+        if !self.files_queue.is_empty() || self.autosave.is_pending() {
+            // files and background work are polled once per frame
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        if ctx.input_mut(|input| input.consume_shortcut(&SAVE_AS_SHORTCUT)) {
+            self.ctx().save_package_as();
+        } else if ctx.input_mut(|input| input.consume_shortcut(&SAVE_SHORTCUT)) {
+            self.ctx().save_package();
+        }
+        self.update_title(ctx);
+
+        if let Some(mut pkg_ctx) = self.package_ctx() {
+            let has_focus = ctx.memory(|memory| memory.focused().is_some());
+            if !has_focus {
+                if ctx.input_mut(|input| input.consume_shortcut(&REDO_SHORTCUT)) {
+                    pkg_ctx.redo();
+                } else if ctx.input_mut(|input| input.consume_shortcut(&UNDO_SHORTCUT)) {
+                    pkg_ctx.undo();
+                } else if ctx
+                    .input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::Delete))
+                {
+                    let targets = pkg_ctx.targets();
+                    pkg_ctx.package().remove_nodes(&targets);
+                    pkg_ctx.mark(&[], false);
+                }
+                handle_clipboard(&mut pkg_ctx, ctx);
+            }
+            selection::set_marked(pkg_ctx.marked(), ctx);
+        } else {
+            selection::set_marked(vec![], ctx);
+        }
 
         let mut new_pack_modal = ModalWrapper::new(ctx, "new-pack-modal");
         let mut authors_modal = ModalWrapper::new(ctx, "authors-modal");
+        let mut optimize_media_modal = ModalWrapper::new(ctx, "optimize-media-modal");
+
+        if ctx.input(|input| input.viewport().close_requested()) && self.dirty_tab().is_some() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.guarded(GuardedAction::Quit);
+        }
+        let (dirty, pending_files) = (self.is_dirty(), !self.files_queue.is_empty());
+        match self.guard.poll(dirty, pending_files) {
+            Some(GuardedAction::Close) => self.close_package(),
+            Some(GuardedAction::Quit) => {
+                self.close_package();
+                if self.dirty_tab().is_some() {
+                    self.guarded(GuardedAction::Quit);
+                } else {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            },
+            None => {},
+        }
 
         egui::TopBottomPanel::top("top_panel")
             .frame(egui::Frame::side_top_panel(&ctx.style()).inner_margin(egui::Margin::symmetric(20, 8)))
@@ -137,17 +302,7 @@ impl eframe::App for EditorApp {
                 egui::menu::bar(ui, |ui| {
                     ui.menu_button("Файл", |ui| {
                         if ui.button(icon_str!(FOLDER_SIMPLE_PLUS, "Новый")).clicked() {
-                            match self.package_state {
-                                PackageState::Active { .. } => {
-                                    new_pack_modal.open();
-                                },
-                                _ => {
-                                    self.package_state = PackageState::Active {
-                                        package: Package::new(),
-                                        selected: None,
-                                    };
-                                },
-                            }
+                            new_pack_modal.open();
                             ui.close_menu();
                         }
                         ui.separator();
@@ -155,10 +310,45 @@ impl eframe::App for EditorApp {
                             self.ctx().pick_new_package();
                             ui.close_menu();
                         }
-                        if ui.button(icon_str!(FLOPPY_DISK_BACK, "Сохранить")).clicked() {
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui.button(icon_str!(FOLDER_NOTCH_OPEN, "Открыть папку…")).clicked() {
+                            self.ctx().pick_package_folder();
+                            ui.close_menu();
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui
+                            .button(icon_str!(FILE_CODE, "Открыть YAML…"))
+                            .on_hover_text("Пакет в формате YAML с медиафайлами рядом, сохраняется как .siq")
+                            .clicked()
+                        {
+                            self.ctx().pick_yaml_package();
+                            ui.close_menu();
+                        }
+                        let has_package = self.has_active_package();
+                        let save_button = egui::Button::new(icon_str!(FLOPPY_DISK_BACK, "Сохранить"))
+                            .shortcut_text(ui.ctx().format_shortcut(&SAVE_SHORTCUT));
+                        if ui.add_enabled(has_package, save_button).clicked() {
                             self.ctx().save_package();
                             ui.close_menu();
                         }
+                        let save_as_button = egui::Button::new(icon_str!(FLOPPY_DISK, "Сохранить как…"))
+                            .shortcut_text(ui.ctx().format_shortcut(&SAVE_AS_SHORTCUT));
+                        if ui.add_enabled(has_package, save_as_button).clicked() {
+                            self.ctx().save_package_as();
+                            ui.close_menu();
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            let save_folder_button = egui::Button::new(icon_str!(FOLDER_SIMPLE, "Сохранить в папку…"));
+                            if ui
+                                .add_enabled(has_package, save_folder_button)
+                                .on_hover_text("Распакованный пакет удобно хранить в системе контроля версий")
+                                .clicked()
+                            {
+                                self.ctx().save_package_to_folder();
+                                ui.close_menu();
+                            }
+                        }
 
                         #[cfg(not(target_arch = "wasm32"))]
                         {
@@ -187,10 +377,76 @@ impl eframe::App for EditorApp {
                             }
                         }
                     });
+                    if let Some(mut pkg_ctx) = self.package_ctx() {
+                        ui.menu_button("Правка", |ui| {
+                            let undo = pkg_ctx.history().next_undo();
+                            let undo_text = match undo {
+                                Some(kind) => icon_format!(ARROW_U_UP_LEFT, "Отменить {}", kind.name()),
+                                None => icon_string!(ARROW_U_UP_LEFT, "Отменить"),
+                            };
+                            let undo_button = egui::Button::new(undo_text)
+                                .shortcut_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT));
+                            if ui.add_enabled(undo.is_some(), undo_button).clicked() {
+                                pkg_ctx.undo();
+                                ui.close_menu();
+                            }
+
+                            let redo = pkg_ctx.history().next_redo();
+                            let redo_text = match redo {
+                                Some(kind) => icon_format!(ARROW_U_UP_RIGHT, "Повторить {}", kind.name()),
+                                None => icon_string!(ARROW_U_UP_RIGHT, "Повторить"),
+                            };
+                            let redo_button = egui::Button::new(redo_text)
+                                .shortcut_text(ui.ctx().format_shortcut(&REDO_SHORTCUT));
+                            if ui.add_enabled(redo.is_some(), redo_button).clicked() {
+                                pkg_ctx.redo();
+                                ui.close_menu();
+                            }
+                        });
+                    }
                     if let PackageState::Active { .. } = self.package_state {
                         ui.menu_button("Пак", |ui| {
+                            if ui.button(icon_str!(IMAGES, "Оптимизировать медиа…")).clicked() {
+                                optimize_media_modal.open();
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui.button(icon_str!(TABLE, "Импорт из таблицы…")).clicked() {
+                                self.ctx().pick_spreadsheet();
+                                ui.close_menu();
+                            }
+                            ui.separator();
                             if ui.button(icon_str!(X, "Закрыть")).clicked() {
-                                self.package_state = PackageState::None;
+                                self.guarded(GuardedAction::Close);
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                    if let PackageState::Active { .. } = self.package_state {
+                        ui.menu_button("Экспорт", |ui| {
+                            ui.menu_button(icon_str!(SCROLL, "Сценарий ведущего"), |ui| {
+                                if ui.button("HTML…").clicked() {
+                                    self.ctx().export_host_script("html");
+                                    ui.close_menu();
+                                }
+                                if ui.button("Markdown…").clicked() {
+                                    self.ctx().export_host_script("md");
+                                    ui.close_menu();
+                                }
+                            });
+                            ui.menu_button(icon_str!(TABLE, "Таблица"), |ui| {
+                                if ui.button("XLSX…").clicked() {
+                                    self.ctx().export_spreadsheet("xlsx");
+                                    ui.close_menu();
+                                }
+                                if ui.button("CSV…").clicked() {
+                                    self.ctx().export_spreadsheet("csv");
+                                    ui.close_menu();
+                                }
+                            });
+                            #[cfg(not(target_arch = "wasm32"))]
+                            if ui.button(icon_str!(FILE_CODE, "YAML…")).clicked() {
+                                self.ctx().export_yaml();
                                 ui.close_menu();
                             }
                         });
@@ -221,6 +477,9 @@ impl eframe::App for EditorApp {
                                 }
                             }
                         });
+                        ui.menu_button(icon_str!(IMAGES, "Медиа"), |ui| {
+                            media::media_settings(&mut self.media, ui);
+                        });
                     });
 
                     ui.menu_button("Справка", |ui| {
@@ -235,6 +494,10 @@ impl eframe::App for EditorApp {
                         ui.toggle_value(&mut self.show_tree, icon!(TREE_VIEW)).on_hover_text(
                             "Включить/выключить левую панель с деревом пакета вопросов",
                         );
+                        if let Some(err) = self.autosave.error() {
+                            ui.colored_label(ui.visuals().warn_fg_color, icon!(WARNING))
+                                .on_hover_text(format!("Автосохранение не удалось:\n{err}"));
+                        }
                     });
                 });
             });
@@ -267,6 +530,10 @@ impl eframe::App for EditorApp {
                     .fill(ctx.style().visuals.widgets.noninteractive.weak_bg_fill),
             )
             .show(ctx, |ui| {
+                if self.has_active_package() {
+                    tabs::tab_bar(self, ui);
+                    ui.separator();
+                }
                 ui.with_layout(
                     egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
                     |ui| {
@@ -283,16 +550,15 @@ impl eframe::App for EditorApp {
                 );
             });
 
-        new_pack_modal.show(ctx, |ui| {
-            ui.modal_title(icon_str!(PENCIL_SIMPLE_LINE, "Перезаписать текущий пак ?"));
-            ui.modal_buttons(|ui| {
-                if ui.modal_danger(icon_str!(PROHIBIT, "Отмена")).clicked() {}
-                if ui.modal_confirm(icon_str!(CHECK, "Перезаписать")).clicked() {
-                    self.package_state =
-                        PackageState::Active { package: Package::new(), selected: None };
-                }
-            });
-        });
+        new_package::new_package_wizard(self, new_pack_modal, ctx);
+        media::optimize_media_modal(self, optimize_media_modal, ctx);
+        spreadsheet::spreadsheet_import_modal(self, ctx);
+        guard::unsaved_changes_modal(self, ctx);
+        recovery::recovery_modal(self, ctx, frame);
+
+        if let Some(mut pkg_ctx) = self.package_ctx() {
+            pkg_ctx.track_edits(ctx);
+        }
 
         authors_modal.show(ctx, |ui| {
             ui.modal_title(icon_str!(GRADUATION_CAP, "OpenSI Editor"));
@@ -329,12 +595,73 @@ impl eframe::App for EditorApp {
     }
 }
 
+/// Copy, cut or paste nodes of the package, while no widget is focused.
+fn handle_clipboard(pkg_ctx: &mut PackageContext, ctx: &egui::Context) {
+    // selected label text is copied by egui itself
+    if egui::text_selection::LabelSelectionState::load(ctx).has_selection() {
+        return;
+    }
+
+    let events = ctx.input(|input| input.events.clone());
+    for event in events {
+        match event {
+            egui::Event::Copy => {
+                let targets = pkg_ctx.targets();
+                clipboard::copy_nodes(pkg_ctx.package(), &targets, ctx);
+            },
+            egui::Event::Cut => {
+                let targets = pkg_ctx.targets();
+                clipboard::cut_nodes(pkg_ctx.package(), &targets, ctx);
+                pkg_ctx.mark(&[], false);
+            },
+            egui::Event::Paste(text) => {
+                let target = clipboard::take_paste_target(ctx).or_else(|| pkg_ctx.selected());
+                if let Some(fragment) = clipboard::parse_fragment(&text) {
+                    pkg_ctx.paste(fragment, target);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
 #[derive(Default, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum PackageState {
     #[default]
     None,
     Active {
         package: Package,
-        selected: Option<PackageNode>,
+        selected: Option<NodeId>,
+        /// Nodes marked for bulk operations, the last one is an anchor
+        /// for range marking.
+        marked: Vec<NodeId>,
+        history: History,
+        /// File the package was opened from or saved to.
+        path: Option<PathBuf>,
+        /// Package has changes since it was opened or saved.
+        dirty: bool,
+        /// Key of package resources in [`SharedPackageBytesStorage`].
+        storage_key: String,
     },
 }
+
+impl PackageState {
+    fn new(package: Package) -> Self {
+        Self::open(package, None)
+    }
+
+    fn open(package: Package, path: Option<PathBuf>) -> Self {
+        let mut history = History::default();
+        history.record(&package, EditKind::Structure);
+        Self::Active {
+            package,
+            selected: None,
+            marked: vec![],
+            history,
+            path,
+            dirty: false,
+            storage_key: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use opensi_core::prelude::*;
+
+use crate::{
+    app::{EditorApp, PackageState, pricing::MAX_PRICE},
+    element::{ModalExt, ModalWrapper},
+    icon, icon_str,
+};
+
+/// Transient state of the new package wizard.
+#[derive(Debug, Clone)]
+struct WizardState {
+    name: String,
+    author: String,
+    language: String,
+    template: PackageTemplate,
+}
+
+impl Default for WizardState {
+    fn default() -> Self {
+        Self {
+            name: "Новый пакет вопросов".to_string(),
+            author: String::new(),
+            language: "ru".to_string(),
+            template: PackageTemplate::default(),
+        }
+    }
+}
+
+/// Wizard to create a new [`Package`] from a [`PackageTemplate`].
+pub fn new_package_wizard(app: &mut EditorApp, modal: ModalWrapper, ctx: &egui::Context) {
+    let state_id = egui::Id::new("new-package-wizard");
+    modal.show(ctx, |ui| {
+        let mut state: WizardState = ui.data(|data| data.get_temp(state_id)).unwrap_or_default();
+
+        ui.modal_title(icon_str!(FOLDER_SIMPLE_PLUS, "Новый пакет вопросов"));
+        egui::Grid::new("new-package-info").num_columns(2).spacing([20.0, 8.0]).show(ui, |ui| {
+            ui.label(icon_str!(STICKER, "Название"));
+            ui.text_edit_singleline(&mut state.name);
+            ui.end_row();
+
+            ui.label(icon_str!(USER, "Автор"));
+            ui.text_edit_singleline(&mut state.author);
+            ui.end_row();
+
+            ui.label(icon_str!(TRANSLATE, "Язык"));
+            ui.text_edit_singleline(&mut state.language);
+            ui.end_row();
+        });
+
+        ui.separator();
+        template_choice(&mut app.templates, &mut state, ui);
+        ui.add_space(8.0);
+        template_edit(&mut state.template, ui);
+        ui.add_space(8.0);
+        template_save(&mut app.templates, &mut state.template, ui);
+
+        ui.modal_buttons(|ui| {
+            if ui.modal_confirm(icon_str!(CHECK, "Создать")).clicked() {
+                let package = state.template.build(&state.name, &state.author, &state.language);
+                app.open_package(PackageState::new(package));
+            }
+            ui.modal_danger(icon_str!(PROHIBIT, "Отмена"));
+        });
+
+        ui.data_mut(|data| data.insert_temp(state_id, state));
+    });
+}
+
+/// Pick one of the templates to start with.
+fn template_choice(
+    templates: &mut Vec<PackageTemplate>,
+    state: &mut WizardState,
+    ui: &mut egui::Ui,
+) {
+    ui.horizontal(|ui| {
+        ui.label(icon_str!(LAYOUT, "Шаблон"));
+        egui::ComboBox::from_id_salt("new-package-template")
+            .selected_text(&state.template.name)
+            .show_ui(ui, |ui| {
+                let standard = PackageTemplate::default();
+                if ui.button(&standard.name).clicked() {
+                    state.template = standard;
+                }
+                let mut removed = None;
+                for (index, template) in templates.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.button(&template.name).clicked() {
+                            state.template = template.clone();
+                        }
+                        let remove = ui
+                            .add(egui::Button::new(icon!(TRASH)).small().frame(false))
+                            .on_hover_text("Удалить шаблон");
+                        if remove.clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = removed {
+                    templates.remove(index);
+                }
+            });
+    });
+}
+
+/// Structure of the package: rounds, themes, questions and prices.
+fn template_edit(template: &mut PackageTemplate, ui: &mut egui::Ui) {
+    egui::Grid::new("new-package-structure").num_columns(2).spacing([20.0, 8.0]).show(ui, |ui| {
+        ui.label("Раундов");
+        let mut rounds = template.rounds.len();
+        if ui.add(egui::DragValue::new(&mut rounds).range(1..=20)).changed() {
+            template.set_round_count(rounds);
+        }
+        ui.end_row();
+
+        ui.label("Тем в раунде");
+        ui.add(egui::DragValue::new(&mut template.themes_per_round).range(1..=20));
+        ui.end_row();
+
+        ui.label("Вопросов в теме");
+        ui.add(egui::DragValue::new(&mut template.questions_per_theme).range(1..=20));
+        ui.end_row();
+
+        ui.label("Финальный раунд");
+        ui.checkbox(&mut template.final_round, "");
+        ui.end_row();
+    });
+
+    ui.add_space(8.0);
+    egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+        egui::Grid::new("new-package-prices")
+            .num_columns(3)
+            .spacing([20.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Раунд");
+                ui.strong("Начальная цена");
+                ui.strong("Шаг");
+                ui.end_row();
+
+                for (index, ladder) in template.rounds.iter_mut().enumerate() {
+                    ui.label(format!("Раунд {}", index + 1));
+                    ui.add(egui::DragValue::new(&mut ladder.start).range(0..=MAX_PRICE).speed(10));
+                    ui.add(egui::DragValue::new(&mut ladder.step).range(0..=MAX_PRICE).speed(10));
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+/// Save current structure as a user template.
+fn template_save(
+    templates: &mut Vec<PackageTemplate>,
+    template: &mut PackageTemplate,
+    ui: &mut egui::Ui,
+) {
+    let name_id = ui.id().with("new-template-name");
+    let mut name = ui.data(|data| data.get_temp::<String>(name_id)).unwrap_or_default();
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut name).hint_text("Название шаблона"));
+        let save = ui.add_enabled(
+            !name.trim().is_empty(),
+            egui::Button::new(icon_str!(FLOPPY_DISK, "Сохранить шаблон")),
+        );
+        if save.clicked() {
+            template.name = name.trim().to_string();
+            match templates.iter_mut().find(|existing| existing.name == template.name) {
+                Some(existing) => *existing = template.clone(),
+                None => templates.push(template.clone()),
+            }
+            name.clear();
+        }
+    });
+    if templates.iter().any(|existing| existing.name == name.trim()) {
+        ui.weak("Шаблон с таким названием будет перезаписан");
+    }
+    ui.data_mut(|data| data.insert_temp(name_id, name));
+}
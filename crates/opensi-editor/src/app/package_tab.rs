@@ -1,7 +1,7 @@
 use opensi_core::prelude::*;
 
 use crate::{
-    app::context::PackageContext,
+    app::{context::PackageContext, media},
     element::{
         PropertyTable, Sections,
         card::{CardStyle, CardTable},
@@ -12,24 +12,34 @@ use crate::{
 
 /// Workarea tab to edit package info.
 pub fn package_tab(ctx: &mut PackageContext, ui: &mut egui::Ui) {
-    CardTable::new("package-rounds").show(ui, (1, ctx.package().rounds.len() + 1), |mut row| {
-        let idx = row.index();
-        if ctx.package().contains_round(idx) {
-            if row.round(ctx.package(), idx, CardStyle::Important).clicked() {
-                ctx.select(idx.into());
+    let marked = CardTable::new("package-rounds").show(
+        ui,
+        (1, ctx.package().rounds.len() + 1),
+        |mut row| {
+            let idx = row.index();
+            if ctx.package().contains_round(idx) {
+                let response = row.round(ctx.package(), idx, CardStyle::Important);
+                ctx.handle_click(idx.into(), &response);
+            } else {
+                if row
+                    .custom(icon_str!(ROWS_PLUS_BOTTOM, "Добавить раунд"), CardStyle::Weak)
+                    .clicked()
+                {
+                    ctx.package().allocate_round();
+                }
             }
-        } else {
-            if row.custom(icon_str!(ROWS_PLUS_BOTTOM, "Добавить раунд"), CardStyle::Weak).clicked()
-            {
-                ctx.package().allocate_round();
-            }
-        }
-    });
+        },
+    );
+    if let Some(nodes) = marked {
+        let extend = ui.input(|input| input.modifiers.command);
+        ctx.mark(&nodes, extend);
+    }
 }
 
 pub fn package_properties(ctx: &mut PackageContext, ui: &mut egui::Ui) {
     Sections::new("package-properties")
-        .line(egui_extras::Size::relative(0.75), 1)
+        .line(egui_extras::Size::relative(0.55), 1)
+        .line(egui_extras::Size::exact(130.0), 1)
         .line(egui_extras::Size::remainder(), 1)
         .show(ui, |mut body| {
             body.line(|mut line| {
@@ -42,6 +52,11 @@ pub fn package_properties(ctx: &mut PackageContext, ui: &mut egui::Ui) {
                     package_metadata_edit(ctx.package(), ui);
                 });
             });
+            body.line(|mut line| {
+                line.section("Размер медиа", |ui| {
+                    media::media_budget(ctx, ui);
+                });
+            });
         });
 }
 
@@ -2,18 +2,32 @@ use egui::collapsing_header::CollapsingState;
 use opensi_core::prelude::*;
 
 use crate::{
-    app::context::PackageContext,
-    element::{node_context::PackageNodeContextMenu, node_name},
+    app::{context::PackageContext, question_thumbnail},
+    element::{
+        drag::{DropAxis, node_drag_and_drop},
+        node_context::PackageNodeContextMenu,
+        node_name,
+        selection::{self, RubberBand},
+    },
 };
 
 /// Ui for a whole [`Package`] in a form of a tree.
 ///
 /// It can add new rounds, themes and questions, edit
-/// names/prices of existing ones and select them.
+/// names/prices of existing ones, select and mark them.
 pub fn package_tree(ctx: &mut PackageContext, ui: &mut egui::Ui) {
     ui.vertical_centered_justified(|ui| {
-        let text = egui::RichText::new(&ctx.package().name).heading();
-        if ui.add(egui::Label::new(text).sense(egui::Sense::click()).selectable(false)).clicked() {
+        let is_dirty = ctx.is_dirty();
+        let name = &ctx.package().name;
+        let text =
+            egui::RichText::new(if is_dirty { format!("{name}*") } else { name.clone() }).heading();
+        let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()).selectable(false));
+        let response = if is_dirty {
+            response.on_hover_text("Есть несохранённые изменения")
+        } else {
+            response
+        };
+        if response.clicked() {
             ctx.deselect();
         }
     });
@@ -21,31 +35,41 @@ pub fn package_tree(ctx: &mut PackageContext, ui: &mut egui::Ui) {
     ui.separator();
 
     egui::ScrollArea::vertical().show(ui, |ui| {
+        let band = RubberBand::begin("package-tree-rubber-band", ui);
         tree_node_ui(ctx, None, ui);
+        if let Some(nodes) = band.end(ui) {
+            let extend = ui.input(|input| input.modifiers.command);
+            ctx.mark(&nodes, extend);
+        }
     });
 }
 
 /// Recursive [`PackageNode`] ui.
-fn tree_node_ui<'a>(ctx: &mut PackageContext, node: Option<PackageNode>, ui: &mut egui::Ui) {
+fn tree_node_ui(ctx: &mut PackageContext, node: Option<PackageNode>, ui: &mut egui::Ui) {
     fn node_button(
         ctx: &mut PackageContext,
         node: PackageNode,
         is_selected: bool,
         ui: &mut egui::Ui,
-    ) -> bool {
+    ) {
         let node_name = node_name(node, ctx.package());
-        let button =
-            egui::Button::new(node_name.as_ref()).frame(false).fill(egui::Color32::TRANSPARENT);
+        let button = egui::Button::new(node_name.as_ref())
+            .frame(false)
+            .fill(egui::Color32::TRANSPARENT)
+            .sense(egui::Sense::click_and_drag());
         let response = ui.add(button);
-        let response = if is_selected { response.highlight() } else { response };
+        let is_marked = selection::is_marked(node, ui.ctx());
+        let response = if is_selected || is_marked { response.highlight() } else { response };
+        selection::register_node(node, response.rect, ui);
 
+        node_drag_and_drop(ctx.package(), node, &response, DropAxis::Vertical, ui);
         PackageNodeContextMenu { package: ctx.package(), node }.show(&response, ui);
 
-        return response.clicked();
+        ctx.handle_click(node, &response);
     }
 
     let Some(node) = node else {
-        ui.push_id(format!("package-tree"), |ui| {
+        ui.push_id("package-tree".to_string(), |ui| {
             if ctx.package().rounds.is_empty() {
                 ui.weak("Нет раундов");
             } else {
@@ -55,7 +79,7 @@ fn tree_node_ui<'a>(ctx: &mut PackageContext, node: Option<PackageNode>, ui: &mu
             }
         });
         ui.allocate_response(ui.available_size(), egui::Sense::click()).context_menu(|ui| {
-            if ui.button(format!("➕ Добавить раунд")).clicked() {
+            if ui.button("➕ Добавить раунд".to_string()).clicked() {
                 ctx.package().allocate_round();
                 ui.close_menu();
             }
@@ -63,15 +87,14 @@ fn tree_node_ui<'a>(ctx: &mut PackageContext, node: Option<PackageNode>, ui: &mu
         return;
     };
 
-    let id = egui::Id::new(node.index()).with(ui.id());
-    let is_selected = ctx.selected().is_some_and(|selected| selected == node);
+    let node_id = ctx.package().node_id(node);
+    let id = egui::Id::new(node_id).with(ui.id());
+    let is_selected = node_id.is_some() && ctx.selected_id() == node_id;
     match node {
         PackageNode::Round(idx) => {
             let mut state = CollapsingState::load_with_default_open(ui.ctx(), id, true)
                 .show_header(ui, |ui| {
-                    if node_button(ctx, node, is_selected, ui) {
-                        ctx.select(node);
-                    };
+                    node_button(ctx, node, is_selected, ui);
                 });
 
             if !state.is_open() && ctx.selected().is_some_and(|selected| {
@@ -99,9 +122,7 @@ fn tree_node_ui<'a>(ctx: &mut PackageContext, node: Option<PackageNode>, ui: &mu
         PackageNode::Theme(idx) => {
             let mut state = CollapsingState::load_with_default_open(ui.ctx(), id, false)
                 .show_header(ui, |ui| {
-                    if node_button(ctx, node, is_selected, ui) {
-                        ctx.select(node);
-                    };
+                    node_button(ctx, node, is_selected, ui);
                 });
 
             if !state.is_open() && ctx.selected().is_some_and(|selected| {
@@ -126,8 +147,24 @@ fn tree_node_ui<'a>(ctx: &mut PackageContext, node: Option<PackageNode>, ui: &mu
             });
         },
         PackageNode::Question(idx) => {
-            if node_button(ctx, idx.into(), is_selected, ui) {
-                ctx.select(node);
+            let storage_key = ctx.storage_key().to_string();
+            let thumbnail = ctx
+                .package()
+                .get_question(idx)
+                .and_then(|question| question_thumbnail(&storage_key, question));
+            match thumbnail {
+                Some(uri) => {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::Image::new(uri)
+                                .fit_to_exact_size(egui::vec2(16.0, 16.0))
+                                .maintain_aspect_ratio(true)
+                                .corner_radius(2.0),
+                        );
+                        node_button(ctx, node, is_selected, ui);
+                    });
+                },
+                None => node_button(ctx, node, is_selected, ui),
             }
         },
     }
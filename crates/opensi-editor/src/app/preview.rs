@@ -0,0 +1,102 @@
+use opensi_core::prelude::*;
+
+use crate::app::{decode::DecodeCache, storage::resource_uri};
+
+/// Max dimension of image previews in the question scenario.
+pub const PREVIEW_SIZE: u32 = 1024;
+/// Max dimension of image thumbnails on cards and in the package tree.
+pub const THUMBNAIL_SIZE: u32 = 96;
+
+/// Uri of a downscaled preview of a resource for [`PreviewLoader`].
+pub fn preview_uri(key: &str, id: &ResourceId, size: u32) -> String {
+    let uri = resource_uri(key, id);
+    format!("package-preview://{size}/{}", uri.trim_start_matches("package://"))
+}
+
+/// Uris of all previews of a resource with the `uri`.
+pub fn preview_uris(uri: &str) -> impl Iterator<Item = String> + '_ {
+    let path = uri.trim_start_matches("package://");
+    [PREVIEW_SIZE, THUMBNAIL_SIZE]
+        .into_iter()
+        .map(move |size| format!("package-preview://{size}/{path}"))
+}
+
+/// Uri of a thumbnail of the first image in the question scenario.
+pub fn question_thumbnail(key: &str, question: &Question) -> Option<String> {
+    question
+        .scenario
+        .iter()
+        .filter(|atom| atom.kind == AtomKind::Image && !atom.body.is_empty())
+        .find_map(Atom::resource)
+        .map(|id| preview_uri(key, &id, THUMBNAIL_SIZE))
+}
+
+/// [`egui::load::ImageLoader`] implementation, which decodes resources
+/// of packages downscaled in the background, so large photos aren't
+/// kept in memory at full size.
+#[derive(Default)]
+pub struct PreviewLoader {
+    previews: DecodeCache<egui::ColorImage>,
+}
+
+impl egui::load::ImageLoader for PreviewLoader {
+    fn id(&self) -> &str {
+        egui::load::generate_loader_id!(PreviewLoader)
+    }
+
+    fn load(
+        &self,
+        ctx: &egui::Context,
+        uri: &str,
+        _size_hint: egui::SizeHint,
+    ) -> egui::load::ImageLoadResult {
+        let Some((size, path)) = uri
+            .strip_prefix("package-preview://")
+            .and_then(|rest| rest.split_once('/'))
+            .and_then(|(size, path)| Some((size.parse::<u32>().ok()?, path)))
+        else {
+            return Err(egui::load::LoadError::NotSupported);
+        };
+
+        match self.previews.get(uri) {
+            Some(Ok(Some(image))) => return Ok(egui::load::ImagePoll::Ready { image }),
+            Some(Ok(None)) => return Ok(egui::load::ImagePoll::Pending { size: None }),
+            Some(Err(err)) => return Err(egui::load::LoadError::Loading(err)),
+            None => {},
+        }
+
+        let bytes = match ctx.try_load_bytes(&format!("package://{path}"))? {
+            egui::load::BytesPoll::Ready { bytes, .. } => bytes,
+            egui::load::BytesPoll::Pending { .. } => {
+                return Ok(egui::load::ImagePoll::Pending { size: None });
+            },
+        };
+
+        self.previews
+            .decode(ctx, uri, move || decode_preview(&bytes, size).map_err(|err| err.to_string()));
+        Ok(egui::load::ImagePoll::Pending { size: None })
+    }
+
+    fn forget(&self, uri: &str) {
+        self.previews.forget(uri);
+    }
+
+    fn forget_all(&self) {
+        self.previews.clear();
+    }
+
+    fn byte_size(&self) -> usize {
+        self.previews.byte_size(|image| image.pixels.len() * size_of::<egui::Color32>())
+    }
+}
+
+/// Decode an image to fit into `size` x `size`.
+fn decode_preview(bytes: &[u8], size: u32) -> image::ImageResult<egui::ColorImage> {
+    let mut image = image::load_from_memory(bytes)?;
+    if image.width() > size || image.height() > size {
+        image = image.thumbnail(size, size);
+    }
+    let image = image.to_rgba8();
+    let dimensions = [image.width() as usize, image.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(dimensions, image.as_flat_samples().as_slice()))
+}
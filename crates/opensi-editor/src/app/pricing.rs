@@ -0,0 +1,130 @@
+use opensi_core::prelude::*;
+
+use crate::{
+    app::context::{RoundContext, ThemeContext},
+    element::{ModalExt, ModalWrapper},
+    icon_str,
+};
+
+/// Max price which can be set by a price ladder.
+pub const MAX_PRICE: usize = 1_000_000;
+
+/// How questions of a round are repriced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepriceMode {
+    /// Every theme gets the same ladder of prices.
+    Ladder,
+    /// All prices are multiplied by a factor.
+    Multiply,
+    /// Every theme gets the ladder of the longest theme.
+    Normalize,
+}
+
+/// Settings of the repricing dialog.
+#[derive(Debug, Clone, Copy)]
+struct RepriceSettings {
+    mode: RepriceMode,
+    start: usize,
+    step: usize,
+    factor: f64,
+}
+
+impl Default for RepriceSettings {
+    fn default() -> Self {
+        Self { mode: RepriceMode::Ladder, start: 100, step: 100, factor: 2.0 }
+    }
+}
+
+/// Modal to reprice all questions of a [`Round`].
+pub fn round_pricing_modal(ctx: &mut RoundContext, modal: ModalWrapper, egui_ctx: &egui::Context) {
+    let settings_id = egui::Id::new("round-pricing-settings");
+    modal.show(egui_ctx, |ui| {
+        let mut settings: RepriceSettings =
+            ui.data(|data| data.get_temp(settings_id)).unwrap_or_default();
+
+        ui.modal_title(icon_str!(COINS, "Изменить цены раунда"));
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut settings.mode, RepriceMode::Ladder, "Лесенка");
+            ui.selectable_value(&mut settings.mode, RepriceMode::Multiply, "Умножить");
+            ui.selectable_value(&mut settings.mode, RepriceMode::Normalize, "Выровнять темы");
+        });
+        ui.add_space(8.0);
+        match settings.mode {
+            RepriceMode::Ladder => ladder_edit(&mut settings, ui),
+            RepriceMode::Multiply => {
+                ui.horizontal(|ui| {
+                    ui.label("Множитель:");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.factor)
+                            .prefix("×")
+                            .speed(0.1)
+                            .range(0.1..=100.0),
+                    );
+                });
+            },
+            RepriceMode::Normalize => {
+                ui.label("Все темы получат цены самой длинной темы раунда.");
+            },
+        }
+
+        ui.modal_buttons(|ui| {
+            if ui.modal_confirm(icon_str!(CHECK, "Применить")).clicked() {
+                let idx = ctx.idx();
+                let package = ctx.package();
+                match settings.mode {
+                    RepriceMode::Ladder => {
+                        package.reprice_round(idx, settings.start, settings.step);
+                    },
+                    RepriceMode::Multiply => {
+                        package.multiply_round_prices(idx, settings.factor);
+                    },
+                    RepriceMode::Normalize => {
+                        package.normalize_round_prices(idx);
+                    },
+                }
+            }
+            ui.modal_danger(icon_str!(PROHIBIT, "Отмена"));
+        });
+
+        ui.data_mut(|data| data.insert_temp(settings_id, settings));
+    });
+}
+
+/// Modal to reprice all questions of a [`Theme`].
+pub fn theme_pricing_modal(ctx: &mut ThemeContext, modal: ModalWrapper, egui_ctx: &egui::Context) {
+    let settings_id = egui::Id::new("theme-pricing-settings");
+    modal.show(egui_ctx, |ui| {
+        let mut settings: RepriceSettings =
+            ui.data(|data| data.get_temp(settings_id)).unwrap_or_default();
+
+        ui.modal_title(icon_str!(COINS, "Изменить цены темы"));
+        ladder_edit(&mut settings, ui);
+
+        ui.modal_buttons(|ui| {
+            if ui.modal_confirm(icon_str!(CHECK, "Применить")).clicked() {
+                let idx = ctx.idx();
+                ctx.package().reprice_theme(idx, settings.start, settings.step);
+            }
+            ui.modal_danger(icon_str!(PROHIBIT, "Отмена"));
+        });
+
+        ui.data_mut(|data| data.insert_temp(settings_id, settings));
+    });
+}
+
+fn ladder_edit(settings: &mut RepriceSettings, ui: &mut egui::Ui) {
+    egui::Grid::new("pricing-ladder").num_columns(2).spacing([20.0, 8.0]).show(ui, |ui| {
+        ui.label("Начальная цена:");
+        ui.add(egui::DragValue::new(&mut settings.start).range(0..=MAX_PRICE).speed(10));
+        ui.end_row();
+
+        ui.label("Шаг:");
+        ui.add(egui::DragValue::new(&mut settings.step).range(0..=MAX_PRICE).speed(10));
+        ui.end_row();
+    });
+
+    let preview = (0..5usize).map(|index| {
+        settings.start.saturating_add(index.saturating_mul(settings.step)).to_string()
+    });
+    ui.weak(format!("{}, …", preview.collect::<Vec<_>>().join(", ")));
+}
@@ -1,9 +1,16 @@
 use opensi_core::prelude::*;
 
 use crate::{
-    app::context::QuestionContext,
-    element::{PropertyTable, Sections, danger_button, info_edit, unselectable_label},
-    icon, icon_str,
+    app::{
+        context::QuestionContext,
+        preview::{PREVIEW_SIZE, preview_uri},
+        waveform::waveform_ui,
+    },
+    element::{
+        PropertyTable, Sections, atom_kind_icon, atom_kind_name, danger_button,
+        format_mismatch_text, info_edit, media_probe_text, unselectable_label,
+    },
+    icon, icon_format, icon_str, icon_string,
 };
 
 pub fn question_tab(ctx: &mut QuestionContext, ui: &mut egui::Ui) {
@@ -55,9 +62,46 @@ fn question_scenario(ctx: &mut QuestionContext, ui: &mut egui::Ui) {
     egui::ScrollArea::vertical().show(ui, |ui| {
         ui.scope(|ui| {
             ui.style_mut().spacing.item_spacing.y = 10.0;
-            let id = ctx.package().id.clone();
-            for atom in &mut ctx.question().scenario {
-                atom_ui(atom, &id, ui);
+            let storage_key = ctx.storage_key().to_string();
+            let count = ctx.question().scenario.len();
+            let idx = ctx.idx();
+            let resources = ctx
+                .package()
+                .get_question(idx)
+                .map(|question| question.scenario.iter().map(Atom::resource).collect::<Vec<_>>())
+                .unwrap_or_default();
+            let infos =
+                resources.iter().map(|id| ctx.resource_info(id.as_ref()?)).collect::<Vec<_>>();
+            let mut action = None;
+            for (index, atom) in ctx.question().scenario.iter_mut().enumerate() {
+                ui.push_id(ui.id().with("atom").with(index), |ui| {
+                    let info = infos.get(index).and_then(Option::as_ref);
+                    let meta = info.map(|(meta, _)| *meta);
+                    let probe = info.and_then(|(_, probe)| probe.as_ref());
+                    if let Some(atom_action) =
+                        atom_ui(atom, meta, probe, index, count, &storage_key, ui)
+                    {
+                        action = Some((index, atom_action));
+                    }
+                });
+            }
+
+            if let Some((index, action)) = action {
+                let scenario = &mut ctx.question().scenario;
+                match action {
+                    AtomAction::MoveUp if index > 0 => scenario.swap(index, index - 1),
+                    AtomAction::MoveDown if index + 1 < scenario.len() => {
+                        scenario.swap(index, index + 1)
+                    },
+                    AtomAction::Remove => {
+                        scenario.remove(index);
+                    },
+                    AtomAction::PickResource(kind) => {
+                        let idx = ctx.idx();
+                        ctx.pick_atom_resource(idx, kind, Some(index));
+                    },
+                    _ => {},
+                }
             }
         });
 
@@ -75,7 +119,7 @@ fn question_scenario(ctx: &mut QuestionContext, ui: &mut egui::Ui) {
                         if ui.button(icon_str!(IMAGE, "Добавить изображение")).clicked()
                         {
                             let idx = ctx.idx();
-                            ctx.pick_new_image_for(idx);
+                            ctx.pick_atom_resource(idx, AtomKind::Image, None);
                         }
                     });
                     row.col(|ui| {
@@ -91,16 +135,14 @@ fn question_scenario(ctx: &mut QuestionContext, ui: &mut egui::Ui) {
                     row.col(|ui| {
                         if ui.button(icon_str!(HEADPHONES, "Добавить аудио")).clicked()
                         {
-                            ctx.question()
-                                .scenario
-                                .push(Atom { kind: AtomKind::Voice, ..Atom::default() });
+                            let idx = ctx.idx();
+                            ctx.pick_atom_resource(idx, AtomKind::Voice, None);
                         }
                     });
                     row.col(|ui| {
                         if ui.button(icon_str!(VIDEO, "Добавить видео")).clicked() {
-                            ctx.question()
-                                .scenario
-                                .push(Atom { kind: AtomKind::Video, ..Atom::default() });
+                            let idx = ctx.idx();
+                            ctx.pick_atom_resource(idx, AtomKind::Video, None);
                         }
                     });
                 });
@@ -108,43 +150,168 @@ fn question_scenario(ctx: &mut QuestionContext, ui: &mut egui::Ui) {
     });
 }
 
-fn atom_ui(atom: &mut Atom, package_id: &str, ui: &mut egui::Ui) {
+/// Action requested from a single [`Atom`] ui.
+#[derive(Debug, Clone, Copy)]
+enum AtomAction {
+    MoveUp,
+    MoveDown,
+    Remove,
+    PickResource(AtomKind),
+}
+
+fn atom_ui(
+    atom: &mut Atom,
+    meta: Option<ResourceMeta>,
+    probe: Option<&MediaProbe>,
+    index: usize,
+    count: usize,
+    storage_key: &str,
+    ui: &mut egui::Ui,
+) -> Option<AtomAction> {
+    let mut action = None;
+
     ui.horizontal(|ui| {
-        let icon = match atom.kind {
-            AtomKind::Image => icon!(IMAGE),
-            AtomKind::Voice => icon!(HEADPHONES),
-            AtomKind::Video => icon!(VIDEO),
-            AtomKind::Text => icon!(CHAT_CIRCLE_TEXT),
-        };
         ui.add(
             egui::Label::new(
-                egui::RichText::new(icon).size(20.0).color(ui.visuals().hyperlink_color),
+                egui::RichText::new(atom_kind_icon(atom.kind))
+                    .size(20.0)
+                    .color(ui.visuals().hyperlink_color),
             )
             .selectable(false),
         );
         let start_position = ui.next_widget_position() + egui::vec2(-18.0, 11.0);
 
-        match (atom.kind, atom.resource()) {
-            (AtomKind::Text, _) => {
-                ui.add(
-                    egui::TextEdit::multiline(&mut atom.body)
-                        .desired_rows(2)
-                        .desired_width(ui.available_width())
-                        .margin(egui::Margin::symmetric(10, 6)),
-                );
-            },
-            (AtomKind::Image, Some(id)) => {
-                ui.add(
-                    egui::Image::new(format!("package://{}/{}", package_id, id.path()))
-                        .corner_radius(8.0)
-                        .fit_to_original_size(1.0)
-                        .max_width(ui.available_width()),
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                let mut kind = atom.kind;
+                egui::ComboBox::from_id_salt(ui.id().with("kind"))
+                    .selected_text(atom_kind_name(kind))
+                    .show_ui(ui, |ui| {
+                        for option in
+                            [AtomKind::Text, AtomKind::Image, AtomKind::Voice, AtomKind::Video]
+                        {
+                            ui.selectable_value(&mut kind, option, atom_kind_name(option));
+                        }
+                    });
+                // a media atom changes only once its file is picked
+                if kind != atom.kind && kind.is_text() {
+                    atom.kind = kind;
+                    atom.body.clear();
+                } else if kind != atom.kind {
+                    action = Some(AtomAction::PickResource(kind));
+                }
+
+                let mut has_time = atom.time.is_some();
+                if ui
+                    .toggle_value(&mut has_time, icon!(TIMER))
+                    .on_hover_text("Длительность показа атома")
+                    .changed()
+                {
+                    atom.time = has_time.then_some(5.0);
+                }
+                if let Some(time) = &mut atom.time {
+                    ui.add(
+                        egui::DragValue::new(time).range(0.0..=3600.0).speed(0.1).suffix(" сек"),
+                    );
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if danger_button(icon!(TRASH), ui).on_hover_text("Удалить атом").clicked()
+                    {
+                        action = Some(AtomAction::Remove);
+                    }
+                    if ui
+                        .add_enabled(index + 1 < count, egui::Button::new(icon!(ARROW_DOWN)))
+                        .on_hover_text("Переместить ниже")
+                        .clicked()
+                    {
+                        action = Some(AtomAction::MoveDown);
+                    }
+                    if ui
+                        .add_enabled(index > 0, egui::Button::new(icon!(ARROW_UP)))
+                        .on_hover_text("Переместить выше")
+                        .clicked()
+                    {
+                        action = Some(AtomAction::MoveUp);
+                    }
+                    if !atom.kind.is_text()
+                        && ui
+                            .button(icon!(FOLDER_OPEN))
+                            .on_hover_text("Выбрать другой файл")
+                            .clicked()
+                    {
+                        action = Some(AtomAction::PickResource(atom.kind));
+                    }
+                });
+            });
+
+            match (atom.kind, atom.resource()) {
+                (AtomKind::Text, _) => {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut atom.body)
+                            .desired_rows(2)
+                            .desired_width(ui.available_width())
+                            .margin(egui::Margin::symmetric(10, 6)),
+                    );
+                },
+                (AtomKind::Image, Some(id)) if !atom.body.is_empty() => {
+                    ui.add(
+                        egui::Image::new(preview_uri(storage_key, &id, PREVIEW_SIZE))
+                            .corner_radius(8.0)
+                            .fit_to_original_size(1.0)
+                            .max_width(ui.available_width()),
+                    );
+                },
+                (AtomKind::Voice, Some(id)) if !atom.body.is_empty() => {
+                    unselectable_label(egui::RichText::new(id.name()).monospace(), ui);
+                    waveform_ui(storage_key, &id, atom.time, ui);
+                },
+                (_, Some(id)) if !atom.body.is_empty() => {
+                    unselectable_label(egui::RichText::new(id.name()).monospace(), ui);
+                },
+                _ => {
+                    ui.weak("Файл не выбран");
+                },
+            }
+
+            let mismatch = meta.and_then(|meta| meta.mismatch);
+            if let (Some(mismatch), Some(id)) = (mismatch, atom.resource()) {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    icon_string!(WARNING, format_mismatch_text(&id, mismatch)),
                 );
-            },
-            _ => {
-                unselectable_label(format!("{atom:?}"), ui);
-            },
-        }
+            }
+
+            if let Some(probe) = probe.filter(|_| !atom.body.is_empty()) {
+                ui.horizontal(|ui| {
+                    ui.weak(media_probe_text(probe));
+                    // atom time is edited with a precision of 0.1 second
+                    let duration = probe.duration.map(|duration| (duration * 10.0).round() / 10.0);
+                    if let Some(duration) = duration.filter(|&duration| atom.time != Some(duration))
+                    {
+                        if ui
+                            .small_button(icon!(TIMER))
+                            .on_hover_text("Взять длительность показа из файла")
+                            .clicked()
+                        {
+                            atom.time = Some(duration);
+                        }
+                    }
+                });
+
+                let unsupported = probe.unsupported_codecs().map(Codec::name).collect::<Vec<_>>();
+                if !unsupported.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        icon_format!(
+                            WARNING,
+                            "SIGame может не воспроизвести кодек {}",
+                            unsupported.join(", ")
+                        ),
+                    );
+                }
+            }
+        });
 
         ui.painter().vline(
             start_position.x,
@@ -152,6 +319,8 @@ fn atom_ui(atom: &mut Atom, package_id: &str, ui: &mut egui::Ui) {
             ui.visuals().noninteractive().fg_stroke,
         );
     });
+
+    action
 }
 
 fn question_answers(question: &mut Question, ui: &mut egui::Ui) {
@@ -0,0 +1,418 @@
+use std::{path::PathBuf, time::Duration};
+
+#[cfg(target_arch = "wasm32")]
+use base64::Engine;
+use log::{error, info};
+use opensi_core::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use tokio_with_wasm::alias as tokio;
+
+use tokio::sync::oneshot;
+
+use crate::{
+    app::{EditorApp, PackageState, files},
+    element::{ModalExt, ModalWrapper},
+    icon_str,
+};
+
+/// Interval between autosaves of open packages.
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Storage key of all [`RecoveryInfo`].
+const RECOVERY_KEY: &str = "recovery";
+/// Storage key prefix of autosaved packages on web, where
+/// there is no file system.
+#[cfg(target_arch = "wasm32")]
+const RECOVERY_PACKAGE_KEY: &str = "recovery-package";
+/// Usual local storage quota of browsers, in characters.
+#[cfg(target_arch = "wasm32")]
+const STORAGE_QUOTA: usize = 5 * 1024 * 1024;
+
+/// Meta information of an autosaved package.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+struct RecoveryInfo {
+    /// Unix timestamp of the autosave.
+    saved_at: i64,
+    /// File the package was opened from or saved to.
+    path: Option<PathBuf>,
+}
+
+/// Package left by a previous session which was closed
+/// with unsaved changes, e.g. after a crash.
+pub struct Recovery {
+    saved_at: chrono::DateTime<chrono::Local>,
+    path: Option<PathBuf>,
+    package: Package,
+    /// Changes compared to the file on disk, if there is one.
+    diff: Option<PackageDiff>,
+}
+
+impl Recovery {
+    /// Load all autosaved packages.
+    pub fn load(storage: &dyn eframe::Storage) -> Vec<Self> {
+        stored_info(storage)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, info)| {
+                let bytes = read_package(storage, index)?;
+                let package = Package::from_zip_buffer(bytes)
+                    .inspect_err(|err| error!("Unable to read autosaved package: {err}"))
+                    .ok()?;
+                let saved_at = chrono::DateTime::from_timestamp(info.saved_at, 0)?.into();
+
+                #[cfg(not(target_arch = "wasm32"))]
+                let diff = info
+                    .path
+                    .as_ref()
+                    .and_then(|path| {
+                        if path.is_dir() {
+                            Package::open_dir(path)
+                        } else {
+                            Package::open_zip_file(path)
+                        }
+                        .ok()
+                    })
+                    .map(|original| original.diff(&package));
+                #[cfg(target_arch = "wasm32")]
+                let diff = None;
+
+                Some(Self { saved_at, path: info.path, package, diff })
+            })
+            .collect()
+    }
+}
+
+/// Packages autosaved in the background, see [`Autosave::update`].
+#[derive(Debug, Default)]
+struct Autosaved {
+    saved_at: i64,
+    /// Autosaved packages with files they were opened from.
+    saved: Vec<(Package, Option<PathBuf>)>,
+    /// Zipped packages to write into the storage on web, where
+    /// it's only accessible from the UI thread.
+    #[cfg(target_arch = "wasm32")]
+    zipped: Vec<Vec<u8>>,
+    /// Packages which couldn't be autosaved.
+    errors: Vec<String>,
+}
+
+impl Autosaved {
+    /// Zip the packages and write them on native.
+    fn zip(saved_at: i64, packages: Vec<(Package, Option<PathBuf>)>) -> Self {
+        let mut autosaved = Self { saved_at, ..Self::default() };
+        for (package, path) in packages {
+            let written = package.to_bytes().map_err(|err| err.to_string()).and_then(|bytes| {
+                #[cfg(not(target_arch = "wasm32"))]
+                return write_package(autosaved.saved.len(), &bytes);
+                #[cfg(target_arch = "wasm32")]
+                {
+                    autosaved.zipped.push(bytes);
+                    Ok(())
+                }
+            });
+            match written {
+                Ok(()) => autosaved.saved.push((package, path)),
+                Err(err) => autosaved.errors.push(format!("«{}»: {err}", package.name)),
+            }
+        }
+        autosaved
+    }
+}
+
+/// Periodic autosave of open packages with unsaved changes.
+/// Packages are zipped in the background.
+#[derive(Debug, Default)]
+pub struct Autosave {
+    /// The last autosaved versions, to not write the same packages twice.
+    saved: Vec<Package>,
+    /// There are no autosaves left in the storage.
+    cleared: bool,
+    /// Autosave which is running in the background.
+    pending: Option<oneshot::Receiver<Autosaved>>,
+    /// Why the last autosave failed, if it did.
+    error: Option<String>,
+}
+
+impl Autosave {
+    /// Autosave packages with unsaved changes, or remove previous
+    /// autosaves once there is nothing to recover.
+    pub fn update(&mut self, states: &[&PackageState], storage: &mut dyn eframe::Storage) {
+        self.poll(storage);
+        if self.pending.is_some() {
+            return;
+        }
+
+        let dirty = states
+            .iter()
+            .filter_map(|state| match state {
+                PackageState::Active { package, path, dirty: true, .. } => Some((package, path)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if dirty.is_empty() {
+            if !self.cleared {
+                discard(storage);
+                self.saved.clear();
+                self.cleared = true;
+                self.error = None;
+            }
+            return;
+        }
+        if self.saved.iter().eq(dirty.iter().map(|(package, _)| *package)) {
+            return;
+        }
+
+        let saved_at = chrono::Utc::now().timestamp();
+        let packages = dirty
+            .into_iter()
+            .map(|(package, path)| (package.clone(), path.clone()))
+            .collect::<Vec<_>>();
+        self.pending = Some(files::spawn_work(move || Autosaved::zip(saved_at, packages)));
+    }
+
+    /// Why the last autosave failed, if it did.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// An autosave is running in the background.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Record the autosave running in the background once it's done.
+    pub fn poll(&mut self, storage: &mut dyn eframe::Storage) {
+        let Some(pending) = &mut self.pending else {
+            return;
+        };
+        let autosaved = match pending.try_recv() {
+            Ok(autosaved) => autosaved,
+            Err(oneshot::error::TryRecvError::Empty) => return,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                self.pending = None;
+                return;
+            },
+        };
+        self.pending = None;
+
+        #[cfg(target_arch = "wasm32")]
+        let mut zipped = autosaved.zipped.into_iter();
+        // packages are only written here on web
+        #[cfg_attr(not(target_arch = "wasm32"), allow(unused_mut))]
+        let mut errors = autosaved.errors;
+        let mut infos = vec![];
+        let mut saved = vec![];
+        for (package, path) in autosaved.saved {
+            #[cfg(target_arch = "wasm32")]
+            if let Some(bytes) = zipped.next() {
+                if let Err(err) = write_package(storage, infos.len(), &bytes) {
+                    errors.push(format!("«{}»: {err}", package.name));
+                    continue;
+                }
+            }
+            infos.push(RecoveryInfo { saved_at: autosaved.saved_at, path });
+            saved.push(package);
+        }
+
+        for index in infos.len()..stored_info(storage).len() {
+            remove_package(storage, index);
+        }
+        eframe::set_value(storage, RECOVERY_KEY, &infos);
+        info!("Autosaved {} packages", saved.len());
+        for err in &errors {
+            error!("Unable to autosave package {err}");
+        }
+        self.saved = saved;
+        self.cleared = false;
+        self.error = (!errors.is_empty()).then(|| errors.join("\n"));
+    }
+}
+
+fn stored_info(storage: &dyn eframe::Storage) -> Vec<RecoveryInfo> {
+    eframe::get_value(storage, RECOVERY_KEY).unwrap_or_default()
+}
+
+/// Remove all autosaved packages.
+fn discard(storage: &mut dyn eframe::Storage) {
+    for index in 0..stored_info(storage).len() {
+        remove_package(storage, index);
+    }
+    eframe::set_value::<Vec<RecoveryInfo>>(storage, RECOVERY_KEY, &vec![]);
+}
+
+/// File of an autosaved package in the data directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn recovery_file(index: usize) -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("opensi-editor").join(format!("recovery-{index}.siq")))
+}
+
+/// Write an autosaved package through a temporary file, so a previous
+/// autosave isn't lost if the app is closed in the middle of writing.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_package(index: usize, bytes: &[u8]) -> Result<(), String> {
+    let file = recovery_file(index).ok_or("no data directory")?;
+    let temp = file.with_extension("siq.tmp");
+    file.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&temp, bytes))
+        .and_then(|_| std::fs::rename(&temp, &file))
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_package(_storage: &dyn eframe::Storage, index: usize) -> Option<Vec<u8>> {
+    std::fs::read(recovery_file(index)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_package(_storage: &mut dyn eframe::Storage, index: usize) {
+    if let Some(file) = recovery_file(index).filter(|file| file.exists()) {
+        let _ = std::fs::remove_file(file)
+            .inspect_err(|err| error!("Unable to remove autosaved package: {err}"));
+    }
+}
+
+/// Write an autosaved package into the local storage. Browsers silently
+/// reject writes above their quota, so the written package is read back.
+#[cfg(target_arch = "wasm32")]
+fn write_package(
+    storage: &mut dyn eframe::Storage,
+    index: usize,
+    bytes: &[u8],
+) -> Result<(), String> {
+    let key = format!("{RECOVERY_PACKAGE_KEY}-{index}");
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    if encoded.len() > STORAGE_QUOTA {
+        remove_package(storage, index);
+        return Err(format!(
+            "пакет слишком большой для хранилища браузера ({} МБ)",
+            bytes.len() / (1024 * 1024)
+        ));
+    }
+
+    // clear the previous autosave first, so it isn't read back instead
+    remove_package(storage, index);
+    let len = encoded.len();
+    storage.set_string(&key, encoded);
+    if storage.get_string(&key).map(|stored| stored.len()) != Some(len) {
+        remove_package(storage, index);
+        return Err("хранилище браузера переполнено".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_package(storage: &dyn eframe::Storage, index: usize) -> Option<Vec<u8>> {
+    let encoded = storage.get_string(&format!("{RECOVERY_PACKAGE_KEY}-{index}"))?;
+    base64::engine::general_purpose::STANDARD.decode(encoded).ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remove_package(storage: &mut dyn eframe::Storage, index: usize) {
+    storage.set_string(&format!("{RECOVERY_PACKAGE_KEY}-{index}"), String::new());
+}
+
+/// Offer to restore packages left by the previous session.
+pub fn recovery_modal(app: &mut EditorApp, ctx: &egui::Context, frame: &mut eframe::Frame) {
+    if app.recovery.is_empty() {
+        return;
+    }
+
+    let mut restore = None;
+    let mut modal = ModalWrapper::new(ctx, "recovery-modal");
+    modal.open();
+    modal.show(ctx, |ui| {
+        ui.modal_title(icon_str!(LIFEBUOY, "Восстановление пакетов"));
+        ui.label("Найдены автоматически сохранённые пакеты с несохранёнными изменениями.");
+
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            for recovery in &app.recovery {
+                ui.separator();
+                recovery_summary(recovery, ui);
+            }
+        });
+
+        ui.modal_buttons(|ui| {
+            if ui.modal_confirm(icon_str!(ARROW_COUNTER_CLOCKWISE, "Восстановить")).clicked()
+            {
+                restore = Some(true);
+            }
+            if ui.modal_danger(icon_str!(TRASH, "Удалить")).clicked() {
+                restore = Some(false);
+            }
+        });
+    });
+
+    // closing the offer discards autosaves
+    if !ModalWrapper::new(ctx, "recovery-modal").is_open() {
+        restore = restore.or(Some(false));
+    }
+
+    match restore {
+        Some(true) => {
+            for Recovery { package, path, .. } in std::mem::take(&mut app.recovery) {
+                let mut state = PackageState::open(package, path);
+                if let PackageState::Active { ref mut dirty, .. } = state {
+                    *dirty = true;
+                }
+                app.open_package(state);
+            }
+        },
+        Some(false) => {
+            app.recovery.clear();
+            if let Some(storage) = frame.storage_mut() {
+                discard(storage);
+            }
+        },
+        None => {},
+    }
+}
+
+fn recovery_summary(recovery: &Recovery, ui: &mut egui::Ui) {
+    ui.strong(format!(
+        "«{}» от {}",
+        recovery.package.name,
+        recovery.saved_at.format("%d.%m.%Y %H:%M"),
+    ));
+    if let Some(path) = &recovery.path {
+        ui.weak(format!("Файл: {}", path.display()));
+    }
+
+    match &recovery.diff {
+        Some(diff) if diff.is_empty() => {
+            ui.label("Отличий от сохранённого файла нет.");
+        },
+        Some(diff) => {
+            ui.label("Изменения по сравнению с сохранённым файлом:");
+            diff_summary(diff, ui);
+        },
+        None => {
+            let package = &recovery.package;
+            let themes = package.rounds.iter().map(|round| round.themes.len()).sum::<usize>();
+            ui.label(format!(
+                "Раундов: {}, тем: {themes}, ресурсов: {}.",
+                package.rounds.len(),
+                package.resources.len(),
+            ));
+        },
+    }
+}
+
+fn diff_summary(diff: &PackageDiff, ui: &mut egui::Ui) {
+    let counts = [
+        ("Раунды", diff.rounds),
+        ("Темы", diff.themes),
+        ("Вопросы", diff.questions),
+        ("Ресурсы", diff.resources),
+    ];
+    for (name, count) in counts.into_iter().filter(|(_, count)| count.is_changed()) {
+        ui.label(format!("• {name}: {} → {}", count.before, count.after));
+    }
+    if diff.changed_questions > 0 {
+        ui.label(format!("• Изменено вопросов: {}", diff.changed_questions));
+    }
+    if diff.metadata_changed {
+        ui.label("• Изменены сведения о пакете");
+    }
+}
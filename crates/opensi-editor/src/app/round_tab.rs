@@ -1,9 +1,9 @@
 use opensi_core::prelude::*;
 
 use crate::{
-    app::context::RoundContext,
+    app::{context::RoundContext, pricing},
     element::{
-        PropertyTable, Sections,
+        ModalWrapper, PropertyTable, Sections,
         card::{CardStyle, CardTable},
         info_edit,
     },
@@ -27,19 +27,19 @@ pub fn round_tab(ctx: &mut RoundContext, ui: &mut egui::Ui) {
         }
     };
 
-    CardTable::new("round-themes").show(ui, count, |mut row| {
+    let storage_key = ctx.storage_key().to_string();
+    let table = CardTable::new("round-themes").thumbnails(&storage_key);
+    let marked = table.show(ui, count, |mut row| {
         let idx = ctx.idx().theme(row.index());
 
         if ctx.package().contains_theme(idx) {
-            if row.theme(ctx.package(), idx, CardStyle::Important).clicked() {
-                ctx.select(idx.into());
-            }
+            let response = row.theme(ctx.package(), idx, CardStyle::Important);
+            ctx.handle_click(idx.into(), &response);
 
             for question_idx in 0..ctx.package().count_questions(idx).min(count.0 - 2) {
                 let idx = idx.question(question_idx);
-                if row.question(ctx.package(), idx, CardStyle::Normal).clicked() {
-                    ctx.select(idx.into());
-                }
+                let response = row.question(ctx.package(), idx, CardStyle::Normal);
+                ctx.handle_click(idx.into(), &response);
             }
 
             if row.custom(icon_str!(FILE_PLUS, "Добавить вопрос"), CardStyle::Weak).clicked()
@@ -53,16 +53,21 @@ pub fn round_tab(ctx: &mut RoundContext, ui: &mut egui::Ui) {
             }
         }
     });
+    if let Some(nodes) = marked {
+        let extend = ui.input(|input| input.modifiers.command);
+        ctx.mark(&nodes, extend);
+    }
 }
 
 pub fn round_properties(ctx: &mut RoundContext, ui: &mut egui::Ui) {
+    let mut pricing_modal = ModalWrapper::new(ui.ctx(), "round-pricing-modal");
     Sections::new("round-properties")
         .line(egui_extras::Size::relative(0.75), 1)
         .line(egui_extras::Size::remainder(), 1)
         .show(ui, |mut body| {
             body.line(|mut line| {
                 line.section("Раунд", |ui| {
-                    round_edit(ctx.round(), ui);
+                    round_edit(ctx.round(), &mut pricing_modal, ui);
                 });
             });
             body.line(|mut line| {
@@ -71,14 +76,22 @@ pub fn round_properties(ctx: &mut RoundContext, ui: &mut egui::Ui) {
                 });
             });
         });
+    pricing::round_pricing_modal(ctx, pricing_modal, ui.ctx());
 }
 
-fn round_edit(round: &mut Round, ui: &mut egui::Ui) {
+fn round_edit(round: &mut Round, pricing_modal: &mut ModalWrapper, ui: &mut egui::Ui) {
     PropertyTable::new("round-properties").show(ui, |mut properties| {
         properties
             .row(icon!(STICKER), "Название", |ui| ui.text_edit_singleline(&mut round.name));
         properties.row(icon!(STAR), "Тип", |ui| {
             ui.add_enabled_ui(false, |ui| ui.label(format!("{:?}?", round.kind))).inner
         });
+        properties.row(icon!(COINS), "Цены", |ui| {
+            let response = ui.button(icon_str!(CALCULATOR, "Изменить цены…"));
+            if response.clicked() {
+                pricing_modal.open();
+            }
+            response
+        });
     });
 }
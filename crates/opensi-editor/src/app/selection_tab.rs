@@ -0,0 +1,126 @@
+use opensi_core::{prelude::*, v4::QuestionTypev4};
+
+use crate::{
+    app::context::PackageContext,
+    element::{PropertyTable, Sections, danger_button, string_list},
+    icon, icon_str,
+};
+
+/// Properties of all nodes marked for bulk operations.
+pub fn selection_properties(ctx: &mut PackageContext, ui: &mut egui::Ui) {
+    let marked = ctx.marked();
+    Sections::new("selection-properties")
+        .line(egui_extras::Size::relative(0.75), 1)
+        .line(egui_extras::Size::remainder(), 1)
+        .show(ui, |mut body| {
+            body.line(|mut line| {
+                line.section(format!("Выбрано: {}", marked.len()), |ui| {
+                    selection_edit(ctx.package(), &marked, ui);
+                });
+            });
+            body.line(|mut line| {
+                line.section("Действия", |ui| {
+                    selection_actions(ctx, &marked, ui);
+                });
+            });
+        });
+}
+
+fn selection_edit(package: &mut Package, marked: &[PackageNode], ui: &mut egui::Ui) {
+    let questions = marked
+        .iter()
+        .filter_map(|node| match node {
+            PackageNode::Question(idx) => Some(*idx),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    PropertyTable::new("selection-properties").show(ui, |mut properties| {
+        properties.row(icon!(STAR), "Тип вопроса", |ui| {
+            let types = questions
+                .iter()
+                .filter_map(|&idx| package.get_question(idx))
+                .map(|question| question.question_type.to_string())
+                .collect::<Vec<_>>();
+            let selected_text = match types.first() {
+                None => "Нет вопросов".to_string(),
+                Some(first) if types.iter().all(|other| other == first) => first.clone(),
+                Some(_) => "Разные".to_string(),
+            };
+
+            ui.add_enabled_ui(!questions.is_empty(), |ui| {
+                egui::ComboBox::from_id_salt("selection-question-type")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for name in QuestionTypev4::NAMES {
+                            let question_type = QuestionTypev4::new(*name);
+                            if ui.button(question_type.to_string()).clicked() {
+                                for &idx in &questions {
+                                    if let Some(question) = package.get_question_mut(idx) {
+                                        question.question_type = question_type.clone();
+                                    }
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                    })
+                    .response
+            })
+            .inner
+        });
+
+        properties.row(icon!(USERS), "Авторы", |ui| {
+            let authors_id = egui::Id::new("selection-authors").with(marked);
+            let mut authors =
+                ui.data(|data| data.get_temp::<Vec<String>>(authors_id)).unwrap_or_else(|| {
+                    marked
+                        .first()
+                        .and_then(|&node| node_info(package, node))
+                        .and_then(|info| info.as_ref())
+                        .map(|info| info.authors.clone())
+                        .unwrap_or_default()
+                });
+            let response = string_list("selection-authors-list", &mut authors, ui);
+            ui.data_mut(|data| data.insert_temp(authors_id, authors));
+            response
+        });
+    });
+}
+
+fn selection_actions(ctx: &mut PackageContext, marked: &[PackageNode], ui: &mut egui::Ui) {
+    ui.vertical_centered_justified(|ui| {
+        if ui.button(icon_str!(USERS, "Задать авторов")).clicked() {
+            let authors_id = egui::Id::new("selection-authors").with(marked);
+            let authors =
+                ui.data(|data| data.get_temp::<Vec<String>>(authors_id)).unwrap_or_default();
+            for &node in marked {
+                if let Some(info) = node_info(ctx.package(), node) {
+                    info.get_or_insert_with(Info::default).authors = authors.clone();
+                }
+            }
+        }
+        if ui.button(icon_str!(COPY, "Дублировать")).clicked() {
+            let copies = ctx.package().duplicate_nodes(marked);
+            ctx.mark(&copies, false);
+        }
+        if ui.button(icon_str!(SELECTION_SLASH, "Снять выделение")).clicked() {
+            ctx.mark(&[], false);
+        }
+        ui.separator();
+        if danger_button(icon_str!(TRASH, "Удалить"), ui).clicked() {
+            ctx.package().remove_nodes(marked);
+            ctx.mark(&[], false);
+        }
+    });
+}
+
+/// Additional info of any package node.
+fn node_info(package: &mut Package, node: PackageNode) -> Option<&mut Option<Info>> {
+    match node {
+        PackageNode::Round(idx) => package.get_round_mut(idx).map(|round| &mut round.info),
+        PackageNode::Theme(idx) => package.get_theme_mut(idx).map(|theme| &mut theme.info),
+        PackageNode::Question(idx) => {
+            package.get_question_mut(idx).map(|question| &mut question.info)
+        },
+    }
+}
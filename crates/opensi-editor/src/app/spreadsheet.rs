@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+
+use opensi_core::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use tokio_with_wasm::alias as tokio;
+
+use tokio::sync::oneshot;
+
+use crate::{
+    app::{
+        EditorApp,
+        context::ImportedSpreadsheet,
+        files::{FileError, LoadingResult},
+    },
+    element::{ModalExt, ModalWrapper},
+    icon_format, icon_str,
+};
+
+/// Amount of rows shown in the preview of an imported spreadsheet.
+const PREVIEW_ROWS: usize = 5;
+const PREVIEW_CELL_WIDTH: f32 = 140.0;
+
+/// Spreadsheet which is being imported into the active package.
+#[derive(Debug)]
+pub struct SpreadsheetImport {
+    file_name: String,
+    sheet: Spreadsheet,
+    mapping: ColumnMapping,
+    /// Folder to read media files from.
+    media_dir: Option<PathBuf>,
+    /// Import which is running in the background.
+    pending: Option<oneshot::Receiver<ImportedSpreadsheet>>,
+    report: Option<Result<SpreadsheetReport, String>>,
+}
+
+impl SpreadsheetImport {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_media_dir(&mut self, media_dir: PathBuf) {
+        self.media_dir = Some(media_dir);
+    }
+}
+
+/// Adapter for [`Spreadsheet`] to use with [`FileLoader`](super::files::FileLoader):
+/// it starts an import into the active package.
+pub fn spreadsheet_loader(buffer: Vec<u8>, path: &Path, app: &mut EditorApp) -> LoadingResult<()> {
+    let sheet = Spreadsheet::parse(&buffer).map_err(FileError::ArchiveError)?;
+    let mapping = ColumnMapping::guess(&sheet);
+
+    // media files are usually next to the spreadsheet
+    #[cfg(not(target_arch = "wasm32"))]
+    let media_dir = path.parent().filter(|dir| dir.is_dir()).map(Path::to_path_buf);
+    #[cfg(target_arch = "wasm32")]
+    let media_dir = None;
+
+    app.spreadsheet_import = Some(SpreadsheetImport {
+        file_name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        sheet,
+        mapping,
+        media_dir,
+        pending: None,
+        report: None,
+    });
+    Ok(())
+}
+
+/// Map columns of a picked spreadsheet and import its rows as questions.
+pub fn spreadsheet_import_modal(app: &mut EditorApp, ctx: &egui::Context) {
+    if app.spreadsheet_import.is_none() {
+        return;
+    }
+
+    poll_import(app, ctx);
+
+    let mut modal = ModalWrapper::new(ctx, "spreadsheet-import-modal");
+    modal.open();
+    modal.show(ctx, |ui| {
+        let Some(import) = app.spreadsheet_import.as_mut() else {
+            return;
+        };
+        ui.modal_title(icon_str!(TABLE, "Импорт из таблицы"));
+
+        if let Some(report) = &import.report {
+            match report {
+                Ok(report) => spreadsheet_report(report, ui),
+                Err(err) => {
+                    ui.colored_label(ui.visuals().warn_fg_color, err);
+                },
+            }
+            ui.modal_buttons(|ui| {
+                ui.modal_button(icon_str!(X, "Закрыть"));
+            });
+            return;
+        }
+
+        ui.label(format!(
+            "{}: строк {}, столбцов {}",
+            import.file_name,
+            import.sheet.rows.len(),
+            import.sheet.width()
+        ));
+        let is_running = import.pending.is_some();
+        ui.add_enabled_ui(!is_running, |ui| {
+            ui.checkbox(&mut import.mapping.header, "Первая строка — заголовок");
+            ui.add_space(4.0);
+            column_mapping(import, ui);
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let pick_media = {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.label("Папка с медиа:");
+                match &import.media_dir {
+                    Some(dir) => ui.weak(dir.display().to_string()),
+                    None => ui.weak("не выбрана"),
+                };
+                ui.add_enabled(!is_running, egui::Button::new(icon_str!(FOLDER_SIMPLE, "Выбрать…")))
+                    .clicked()
+            })
+            .inner
+        };
+
+        if is_running {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Вопросы импортируются…");
+            });
+        }
+
+        let mapped = import.mapping.columns.iter().any(Option::is_some);
+        let mut start_import = false;
+        ui.modal_buttons(|ui| {
+            let import_button = egui::Button::new(icon_str!(DOWNLOAD_SIMPLE, "Импортировать"));
+            start_import = ui.add_enabled(mapped && !is_running, import_button).clicked();
+            ui.modal_button(icon_str!(PROHIBIT, "Отмена"));
+        });
+
+        if start_import {
+            if let Some(mut import) = app.spreadsheet_import.take() {
+                if let Some(mut pkg_ctx) = app.package_ctx() {
+                    import.pending = Some(pkg_ctx.import_spreadsheet(
+                        import.sheet.clone(),
+                        import.mapping.clone(),
+                        import.media_dir.clone(),
+                    ));
+                }
+                app.spreadsheet_import = Some(import);
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if pick_media {
+            app.ctx().pick_spreadsheet_media();
+        }
+    });
+
+    // closed by a button, escape or click outside
+    if !ModalWrapper::new(ctx, "spreadsheet-import-modal").is_open() {
+        app.spreadsheet_import = None;
+    }
+}
+
+/// Apply the import running in the background once it's done.
+fn poll_import(app: &mut EditorApp, ctx: &egui::Context) {
+    let Some(pending) = app.spreadsheet_import.as_mut().and_then(|import| import.pending.as_mut())
+    else {
+        return;
+    };
+    let report = match pending.try_recv() {
+        Ok(imported) => imported.apply(app),
+        Err(oneshot::error::TryRecvError::Empty) => {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            return;
+        },
+        Err(oneshot::error::TryRecvError::Closed) => {
+            Err("Не удалось импортировать таблицу.".into())
+        },
+    };
+    if let Some(import) = &mut app.spreadsheet_import {
+        import.pending = None;
+        import.report = Some(report);
+    }
+}
+
+/// Meaning of every column with a preview of the first rows.
+fn column_mapping(import: &mut SpreadsheetImport, ui: &mut egui::Ui) {
+    let width = import.sheet.width();
+    import.mapping.columns.resize(width, None);
+
+    egui::ScrollArea::both().max_height(240.0).show(ui, |ui| {
+        egui::Grid::new("spreadsheet-columns").striped(true).show(ui, |ui| {
+            for (index, meaning) in import.mapping.columns.iter_mut().enumerate() {
+                let text = meaning.map_or("—", |meaning| meaning.name());
+                egui::ComboBox::from_id_salt(("spreadsheet-column", index))
+                    .selected_text(text)
+                    .width(PREVIEW_CELL_WIDTH)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(meaning, None, "—");
+                        for column in SpreadsheetColumn::ALL {
+                            ui.selectable_value(meaning, Some(column), column.name());
+                        }
+                    });
+            }
+            ui.end_row();
+
+            for (row_index, row) in import.sheet.rows.iter().take(PREVIEW_ROWS).enumerate() {
+                let is_header = row_index == 0 && import.mapping.header;
+                for index in 0..width {
+                    let cell = row.get(index).map(String::as_str).unwrap_or_default();
+                    let text = egui::RichText::new(cell);
+                    let text = if is_header { text.strong() } else { text };
+                    ui.add_sized(
+                        [PREVIEW_CELL_WIDTH, ui.spacing().interact_size.y],
+                        egui::Label::new(text).truncate(),
+                    );
+                }
+                ui.end_row();
+            }
+        });
+    });
+
+    let duplicates = SpreadsheetColumn::ALL.into_iter().filter(|column| {
+        import.mapping.columns.iter().filter(|meaning| **meaning == Some(*column)).count() > 1
+    });
+    for column in duplicates {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            icon_format!(
+                WARNING,
+                "Столбец «{}» выбран несколько раз, будет взят первый",
+                column.name()
+            ),
+        );
+    }
+}
+
+fn spreadsheet_report(report: &SpreadsheetReport, ui: &mut egui::Ui) {
+    ui.label(format!("Импортировано вопросов: {}", report.questions));
+    if report.rounds > 0 || report.themes > 0 {
+        ui.label(format!("Новых раундов: {}, новых тем: {}", report.rounds, report.themes));
+    }
+    if !report.missing_media.is_empty() {
+        ui.colored_label(ui.visuals().warn_fg_color, "Не найдены медиафайлы:");
+        egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+            for file in &report.missing_media {
+                ui.weak(format!("• {file}"));
+            }
+        });
+    }
+}
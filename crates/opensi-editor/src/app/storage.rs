@@ -1,36 +1,277 @@
-use std::sync::Arc;
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+};
 
 use opensi_core::prelude::*;
 
+/// Max amount of cached resource bytes. Least recently used
+/// resources are evicted above it.
+pub const MAX_CACHED_BYTES: usize = 256 * 1024 * 1024;
+
+/// Cached resource with the last time it was used.
+#[derive(Debug)]
+struct CachedBytes {
+    bytes: egui::load::Bytes,
+    last_used: u64,
+}
+
+/// Inspected resource with the content it was inspected from.
+#[derive(Debug)]
+struct CachedInfo {
+    bytes: Arc<[u8]>,
+    meta: ResourceMeta,
+    probe: Option<MediaProbe>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    entries: dashmap::DashMap<String, CachedBytes>,
+    /// Inspected resources, see [`SharedPackageBytesStorage::resource_info`].
+    info: dashmap::DashMap<String, CachedInfo>,
+    /// Resources which were requested while not being cached.
+    missing: dashmap::DashSet<String>,
+    /// Requested resources which their packages don't have.
+    not_found: dashmap::DashSet<String>,
+    /// Uris of replaced resources and resources of closed packages,
+    /// which egui should forget.
+    evicted: Mutex<Vec<String>>,
+    size: AtomicUsize,
+    clock: AtomicU64,
+}
+
 /// Storage adapter for egui to be able to use [`Package`] resources.
+///
+/// Resources are cached lazily: a resource requested by egui is marked as
+/// missing, and [`SharedPackageBytesStorage::restore_missing`] caches it
+/// from its package on the next frame. Least recently used resources are
+/// evicted above [`MAX_CACHED_BYTES`].
 #[derive(Clone, Default, Debug)]
 pub struct SharedPackageBytesStorage {
-    cache: Arc<dashmap::DashMap<String, egui::load::Bytes>>,
+    cache: Arc<Cache>,
 }
 
 impl SharedPackageBytesStorage {
     pub fn get(&self, path: impl AsRef<str>) -> Option<egui::load::Bytes> {
         let path = path.as_ref();
-        self.cache.as_ref().get(path).map(|r| r.value().clone())
+        let mut entry = self.cache.entries.get_mut(path)?;
+        entry.last_used = self.cache.clock.fetch_add(1, Ordering::Relaxed);
+        Some(entry.bytes.clone())
     }
 
+    /// Cache an image or an audio resource of the package with `key`.
     pub fn insert<'id>(
         &self,
         id: &'id ResourceId,
-        package: &Package,
+        key: &str,
         bytes: Arc<[u8]>,
     ) -> Option<&'id str> {
-        if !matches!(id, ResourceId::Image(..)) {
+        if !matches!(id, ResourceId::Image(..) | ResourceId::Audio(..)) {
             return None;
         }
 
-        let path = format!("{}/{}", package.id, id.path());
-        self.cache.as_ref().insert(path.clone(), egui::load::Bytes::Shared(bytes));
+        let path = resource_path(key, id);
+        let last_used = self.cache.clock.fetch_add(1, Ordering::Relaxed);
+        self.cache.missing.remove(&path);
+        // egui remembers the failed load of a resource which wasn't found before
+        if self.cache.not_found.remove(&path).is_some() {
+            self.evicted(&path);
+        }
+        self.cache.size.fetch_add(bytes.len(), Ordering::Relaxed);
+        let entry = CachedBytes { bytes: egui::load::Bytes::Shared(bytes.clone()), last_used };
+        if let Some(previous) = self.cache.entries.insert(path.clone(), entry) {
+            self.cache.size.fetch_sub(previous.bytes.len(), Ordering::Relaxed);
+            if !matches!(previous.bytes, egui::load::Bytes::Shared(ref old) if Arc::ptr_eq(old, &bytes))
+            {
+                self.evicted(&path);
+            }
+        }
 
         log::info!("Cached new resource: {path}");
+        self.enforce_limit(&path);
 
         Some(id.name())
     }
+
+    /// Format metadata and media probe of a resource of the package with
+    /// `key`. Resources are inspected once per content, not every frame.
+    pub fn resource_info(
+        &self,
+        key: &str,
+        package: &Package,
+        id: &ResourceId,
+    ) -> Option<(ResourceMeta, Option<MediaProbe>)> {
+        let bytes = package.resources.get(id)?;
+        let path = resource_path(key, id);
+        if let Some(info) = self.cache.info.get(&path) {
+            if Arc::ptr_eq(&info.bytes, bytes) {
+                return Some((info.meta, info.probe.clone()));
+            }
+        }
+
+        let meta = id.inspect(bytes);
+        let probe = package.probe_resource(id);
+        let info = CachedInfo { bytes: bytes.clone(), meta, probe: probe.clone() };
+        self.cache.info.insert(path, info);
+        Some((meta, probe))
+    }
+
+    /// Cache resources of open packages, which egui requested while they
+    /// were not cached. Packages are given with their keys.
+    pub fn restore_missing<'a>(&self, packages: impl IntoIterator<Item = (&'a str, &'a Package)>) {
+        for (key, package) in packages {
+            for (id, bytes) in &package.resources {
+                if self.cache.missing.contains(&resource_path(key, id)) {
+                    self.insert(id, key, bytes.clone());
+                }
+            }
+        }
+        let not_found = self.cache.missing.iter().map(|path| path.clone()).collect::<Vec<_>>();
+        for path in not_found {
+            self.cache.missing.remove(&path);
+            self.cache.not_found.insert(path);
+        }
+    }
+
+    /// Any resource was requested while not being cached, and it's
+    /// not known yet whether its package has it.
+    pub fn has_missing(&self) -> bool {
+        !self.cache.missing.is_empty()
+    }
+
+    /// Remove all cached resources of the package with `key`.
+    pub fn remove_package(&self, key: &str) {
+        let prefix = format!("{key}/");
+        let paths = self
+            .cache
+            .entries
+            .iter()
+            .filter(|entry| entry.key().starts_with(&prefix))
+            .map(|entry| entry.key().clone())
+            .collect::<Vec<_>>();
+        for path in paths {
+            if self.remove(&path) {
+                self.evicted(&path);
+            }
+        }
+        self.cache.info.retain(|path, _| !path.starts_with(&prefix));
+        self.cache.missing.retain(|path| !path.starts_with(&prefix));
+        self.cache.not_found.retain(|path| !path.starts_with(&prefix));
+        log::info!("Removed cached resources of package {key}");
+    }
+
+    /// Forget cached resources of the package with `key` which differ
+    /// from its current resources, e.g. after undo or optimisation.
+    pub fn sync_package(&self, key: &str, package: &Package) {
+        let resources = package
+            .resources
+            .iter()
+            .map(|(id, bytes)| (resource_path(key, id), bytes))
+            .collect::<std::collections::HashMap<_, _>>();
+        let prefix = format!("{key}/");
+        let stale = self
+            .cache
+            .entries
+            .iter()
+            .filter(|entry| entry.key().starts_with(&prefix))
+            .filter(|entry| match (&entry.bytes, resources.get(entry.key())) {
+                (egui::load::Bytes::Shared(cached), Some(bytes)) => !Arc::ptr_eq(cached, bytes),
+                _ => true,
+            })
+            .map(|entry| entry.key().clone())
+            .collect::<Vec<_>>();
+        for path in stale {
+            if self.remove(&path) {
+                self.evicted(&path);
+            }
+        }
+        self.cache.info.retain(|path, info| {
+            !path.starts_with(&prefix)
+                || resources.get(path).is_some_and(|bytes| Arc::ptr_eq(&info.bytes, bytes))
+        });
+
+        // resources which weren't found could be added, e.g. by undo
+        let found = self
+            .cache
+            .not_found
+            .iter()
+            .filter(|path| resources.contains_key(path.as_str()))
+            .map(|path| path.clone())
+            .collect::<Vec<_>>();
+        for path in found {
+            self.cache.not_found.remove(&path);
+            self.evicted(&path);
+        }
+    }
+
+    /// Take uris of resources which were replaced or removed with
+    /// their package since the last call, to forget them in egui.
+    pub fn take_evicted(&self) -> Vec<String> {
+        std::mem::take(&mut *self.cache.evicted.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    /// Amount of cached bytes.
+    pub fn byte_size(&self) -> usize {
+        self.cache.size.load(Ordering::Relaxed)
+    }
+
+    /// Remove a cached resource. Returns `true` if it was cached.
+    fn remove(&self, path: &str) -> bool {
+        let Some((_, entry)) = self.cache.entries.remove(path) else {
+            return false;
+        };
+        self.cache.size.fetch_sub(entry.bytes.len(), Ordering::Relaxed);
+        true
+    }
+
+    fn clear(&self) {
+        self.cache.entries.clear();
+        self.cache.missing.clear();
+        self.cache.not_found.clear();
+        self.cache.size.store(0, Ordering::Relaxed);
+    }
+
+    fn evicted(&self, path: &str) {
+        let mut evicted = self.cache.evicted.lock().unwrap_or_else(|err| err.into_inner());
+        evicted.push(format!("package://{path}"));
+    }
+
+    /// Evict least recently used resources above [`MAX_CACHED_BYTES`],
+    /// except for the resource at `keep`.
+    fn enforce_limit(&self, keep: &str) {
+        if self.byte_size() <= MAX_CACHED_BYTES {
+            return;
+        }
+
+        let mut entries = self
+            .cache
+            .entries
+            .iter()
+            .filter(|entry| entry.key() != keep)
+            .map(|entry| (entry.last_used, entry.key().clone()))
+            .collect::<Vec<_>>();
+        entries.sort();
+        for (_, path) in entries {
+            if self.byte_size() <= MAX_CACHED_BYTES {
+                break;
+            }
+            // egui keeps only downscaled previews of the resource, so it
+            // doesn't need to forget them
+            if self.remove(&path) {
+                log::info!("Evicted cached resource: {path}");
+            }
+        }
+    }
+}
+
+/// Path of a resource in [`SharedPackageBytesStorage`].
+fn resource_path(key: &str, id: &ResourceId) -> String {
+    format!("{key}/{}", id.path())
+}
+
+/// Uri of a cached resource for [`EguiPackageBytesLoader`].
+pub fn resource_uri(key: &str, id: &ResourceId) -> String {
+    format!("package://{}", resource_path(key, id))
 }
 
 /// [`egui::load::BytesLoader`] implementation for [`SharedPackageBytesStorage`].
@@ -47,25 +288,39 @@ impl egui::load::BytesLoader for EguiPackageBytesLoader {
         egui::load::generate_loader_id!(PackageBytesLoader)
     }
 
-    fn load(&self, _ctx: &egui::Context, uri: &str) -> egui::load::BytesLoadResult {
+    fn load(&self, ctx: &egui::Context, uri: &str) -> egui::load::BytesLoadResult {
         let Some(path) = uri.strip_prefix("package://") else {
             return Err(egui::load::LoadError::NotSupported);
         };
 
         let Some(bytes) = self.0.get(path) else {
+            // the resource is restored from its package on the next frame,
+            // unless it was already requested and its package doesn't have it
+            if !self.0.cache.not_found.contains(path) {
+                self.0.cache.missing.insert(path.to_string());
+                ctx.request_repaint();
+                return Ok(egui::load::BytesPoll::Pending { size: None });
+            }
             return Err(egui::load::LoadError::Loading(format!(
-                "Package image for '{path}' isn't loaded into app's cache!"
+                "Package resource '{path}' isn't found in its package!"
             )));
         };
 
-        Ok(egui::load::BytesPoll::Ready { size: None, bytes, mime: None })
+        let mime = MediaFormat::sniff(&bytes).map(|format| format.mime().to_string());
+        Ok(egui::load::BytesPoll::Ready { size: None, bytes, mime })
     }
 
-    fn forget(&self, _uri: &str) {}
+    fn forget(&self, uri: &str) {
+        if let Some(path) = uri.strip_prefix("package://") {
+            self.0.remove(path);
+        }
+    }
 
-    fn forget_all(&self) {}
+    fn forget_all(&self) {
+        self.0.clear();
+    }
 
     fn byte_size(&self) -> usize {
-        0
+        self.0.byte_size()
     }
 }
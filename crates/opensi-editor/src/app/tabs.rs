@@ -0,0 +1,119 @@
+use std::path::Path;
+
+use crate::{
+    app::{EditorApp, PackageState, guard::GuardedAction},
+    icon,
+};
+
+/// # Open packages in tabs.
+///
+/// The active package lives in `EditorApp::package_state`, and its tab
+/// is an empty slot in `EditorApp::tabs`: packages are swapped between
+/// them when another tab is activated.
+impl EditorApp {
+    /// Open a package in a new tab and make it active.
+    pub(super) fn open_package(&mut self, state: PackageState) {
+        if self.has_active_package() {
+            self.tabs[self.active_tab] = std::mem::take(&mut self.package_state);
+        }
+        self.tabs.push(PackageState::None);
+        self.active_tab = self.tabs.len() - 1;
+        self.package_state = state;
+    }
+
+    /// Make the package in the tab at `index` active.
+    pub(super) fn activate_tab(&mut self, index: usize) {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return;
+        }
+        std::mem::swap(&mut self.package_state, &mut self.tabs[self.active_tab]);
+        std::mem::swap(&mut self.package_state, &mut self.tabs[index]);
+        self.active_tab = index;
+    }
+
+    /// Close the active package and activate a neighbouring tab.
+    pub(super) fn close_package(&mut self) {
+        let PackageState::Active { ref storage_key, .. } = self.package_state else {
+            return;
+        };
+        self.storage.remove_package(storage_key);
+
+        self.package_state = PackageState::None;
+        self.tabs.remove(self.active_tab);
+        if !self.tabs.is_empty() {
+            self.active_tab = self.active_tab.min(self.tabs.len() - 1);
+            std::mem::swap(&mut self.package_state, &mut self.tabs[self.active_tab]);
+        }
+    }
+
+    /// Packages of all tabs in their order.
+    pub(super) fn package_states(&self) -> impl Iterator<Item = &PackageState> {
+        self.tabs.iter().enumerate().map(|(index, state)| {
+            if index == self.active_tab { &self.package_state } else { state }
+        })
+    }
+
+    /// Index of the tab with a package opened from the `path`.
+    pub(super) fn find_tab(&self, path: &Path) -> Option<usize> {
+        self.package_states().position(|state| {
+            matches!(state, PackageState::Active { path: Some(other), .. } if other == path)
+        })
+    }
+
+    /// Package with resources at `storage_key`, whether it is active or not.
+    pub(super) fn find_package_mut(&mut self, storage_key: &str) -> Option<&mut PackageState> {
+        std::iter::once(&mut self.package_state).chain(self.tabs.iter_mut()).find(
+            |state| matches!(state, PackageState::Active { storage_key: key, .. } if key == storage_key),
+        )
+    }
+
+    /// Index of the first tab with unsaved changes.
+    pub(super) fn dirty_tab(&self) -> Option<usize> {
+        self.package_states()
+            .position(|state| matches!(state, PackageState::Active { dirty: true, .. }))
+    }
+}
+
+/// Tabs of all open packages.
+pub fn tab_bar(app: &mut EditorApp, ui: &mut egui::Ui) {
+    let mut activate = None;
+    let mut close = None;
+
+    egui::ScrollArea::horizontal().id_salt("package-tabs").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            for (index, state) in app.package_states().enumerate() {
+                let PackageState::Active { ref package, dirty, ref path, .. } = *state else {
+                    continue;
+                };
+
+                let name = if dirty { format!("{}*", package.name) } else { package.name.clone() };
+                let is_active = index == app.active_tab;
+                let response = ui.selectable_label(is_active, name);
+                let response = match path {
+                    Some(path) => response.on_hover_text(path.display().to_string()),
+                    None => response,
+                };
+                if response.clicked() {
+                    activate = Some(index);
+                }
+                if response.middle_clicked() {
+                    close = Some(index);
+                }
+
+                let close_button = egui::Button::new(icon!(X)).small().frame(false);
+                if ui.add(close_button).on_hover_text("Закрыть пакет").clicked() {
+                    close = Some(index);
+                }
+                ui.separator();
+            }
+        });
+    });
+
+    if let Some(index) = activate {
+        app.activate_tab(index);
+    }
+    if let Some(index) = close {
+        app.activate_tab(index);
+        app.guarded(GuardedAction::Close);
+    }
+}
@@ -1,9 +1,9 @@
 use opensi_core::prelude::*;
 
 use crate::{
-    app::context::ThemeContext,
+    app::{context::ThemeContext, pricing},
     element::{
-        PropertyTable, Sections,
+        ModalWrapper, PropertyTable, Sections,
         card::{CardStyle, CardTable},
         info_edit,
     },
@@ -11,29 +11,38 @@ use crate::{
 };
 
 pub fn theme_tab(ctx: &mut ThemeContext, ui: &mut egui::Ui) {
-    CardTable::new("theme-questions").show(ui, (1, ctx.theme().questions.len() + 1), |mut row| {
-        let idx = ctx.idx().question(row.index());
-        if ctx.package().contains_question(idx) {
-            if row.question(ctx.package(), idx, CardStyle::Important).clicked() {
-                ctx.select(idx.into());
+    let storage_key = ctx.storage_key().to_string();
+    let marked = CardTable::new("theme-questions").thumbnails(&storage_key).show(
+        ui,
+        (1, ctx.theme().questions.len() + 1),
+        |mut row| {
+            let idx = ctx.idx().question(row.index());
+            if ctx.package().contains_question(idx) {
+                let response = row.question(ctx.package(), idx, CardStyle::Important);
+                ctx.handle_click(idx.into(), &response);
+            } else {
+                if row.custom(icon_str!(FILE_PLUS, "Добавить вопрос"), CardStyle::Weak).clicked()
+                {
+                    ctx.package().allocate_question(idx.parent());
+                }
             }
-        } else {
-            if row.custom(icon_str!(FILE_PLUS, "Добавить вопрос"), CardStyle::Weak).clicked()
-            {
-                ctx.package().allocate_question(idx.parent());
-            }
-        }
-    });
+        },
+    );
+    if let Some(nodes) = marked {
+        let extend = ui.input(|input| input.modifiers.command);
+        ctx.mark(&nodes, extend);
+    }
 }
 
 pub fn theme_properties(ctx: &mut ThemeContext, ui: &mut egui::Ui) {
+    let mut pricing_modal = ModalWrapper::new(ui.ctx(), "theme-pricing-modal");
     Sections::new("theme-properties")
         .line(egui_extras::Size::relative(0.75), 1)
         .line(egui_extras::Size::remainder(), 1)
         .show(ui, |mut body| {
             body.line(|mut line| {
                 line.section("Тема", |ui| {
-                    theme_edit(ctx.theme(), ui);
+                    theme_edit(ctx.theme(), &mut pricing_modal, ui);
                 });
             });
             body.line(|mut line| {
@@ -42,11 +51,19 @@ pub fn theme_properties(ctx: &mut ThemeContext, ui: &mut egui::Ui) {
                 });
             });
         });
+    pricing::theme_pricing_modal(ctx, pricing_modal, ui.ctx());
 }
 
-fn theme_edit(theme: &mut Theme, ui: &mut egui::Ui) {
+fn theme_edit(theme: &mut Theme, pricing_modal: &mut ModalWrapper, ui: &mut egui::Ui) {
     PropertyTable::new("theme-properties").show(ui, |mut properties| {
         properties
             .row(icon!(STICKER), "Название", |ui| ui.text_edit_singleline(&mut theme.name));
+        properties.row(icon!(COINS), "Цены", |ui| {
+            let response = ui.button(icon_str!(CALCULATOR, "Изменить цены…"));
+            if response.clicked() {
+                pricing_modal.open();
+            }
+            response
+        });
     });
 }
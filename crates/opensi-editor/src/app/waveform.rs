@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use opensi_core::prelude::*;
+
+use crate::{
+    app::{decode::DecodeCache, storage::resource_uri},
+    element::{duration_name, unselectable_label},
+    icon_str,
+};
+
+/// Amount of peaks decoded for a waveform.
+const WAVEFORM_PEAKS: usize = 512;
+const WAVEFORM_HEIGHT: f32 = 48.0;
+
+/// Waveforms of audio resources, decoded in the background
+/// and kept in the egui memory by resource uri.
+#[derive(Clone, Default)]
+struct WaveformCache(DecodeCache<Waveform>);
+
+impl WaveformCache {
+    fn get(ctx: &egui::Context) -> Self {
+        ctx.data_mut(|data| {
+            data.get_temp_mut_or_default::<Self>(egui::Id::new("waveforms")).clone()
+        })
+    }
+
+    fn load(&self, ctx: &egui::Context, uri: &str) -> Result<Option<Arc<Waveform>>, String> {
+        if let Some(waveform) = self.0.get(uri) {
+            return waveform;
+        }
+
+        let bytes = match ctx.try_load_bytes(uri) {
+            Ok(egui::load::BytesPoll::Ready { bytes, .. }) => bytes,
+            Ok(egui::load::BytesPoll::Pending { .. }) => return Ok(None),
+            Err(err) => {
+                // the resource is forgotten once it's added to its package
+                self.0.fail(uri, err.to_string());
+                return Err(err.to_string());
+            },
+        };
+
+        self.0.decode(ctx, uri, move || {
+            Waveform::decode(&bytes, WAVEFORM_PEAKS).map_err(|err| err.to_string())
+        });
+        Ok(None)
+    }
+}
+
+/// Forget a waveform of an evicted or replaced resource.
+pub fn forget_waveform(ctx: &egui::Context, uri: &str) {
+    WaveformCache::get(ctx).0.forget(uri);
+}
+
+/// Waveform of an audio resource with its duration, and markers
+/// of the start and the end of the playback, which is cut by `time`.
+pub fn waveform_ui(key: &str, id: &ResourceId, time: Option<f64>, ui: &mut egui::Ui) {
+    let uri = resource_uri(key, id);
+    let waveform = match WaveformCache::get(ui.ctx()).load(ui.ctx(), &uri) {
+        Ok(Some(waveform)) => waveform,
+        Ok(None) => {
+            ui.add_sized([ui.available_width(), WAVEFORM_HEIGHT], egui::Spinner::new());
+            return;
+        },
+        Err(err) => {
+            ui.weak(format!("Не удалось прочитать аудио: {err}"));
+            return;
+        },
+    };
+
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), WAVEFORM_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let visuals = ui.visuals();
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, visuals.extreme_bg_color);
+
+    let end = match time {
+        Some(time) if waveform.duration > 0.0 && time < waveform.duration => {
+            (time / waveform.duration) as f32
+        },
+        _ => 1.0,
+    };
+    let end_x = rect.left() + rect.width() * end;
+
+    let columns = rect.width().max(1.0) as usize;
+    let peaks = &waveform.peaks;
+    for column in 0..columns {
+        let start = column * peaks.len() / columns;
+        let end = ((column + 1) * peaks.len() / columns).max(start + 1);
+        let peak =
+            peaks.get(start..end).into_iter().flatten().fold(0.0f32, |max, &peak| max.max(peak));
+        let x = rect.left() + column as f32 + 0.5;
+        let height = (peak * rect.height() / 2.0).max(0.5);
+        let color = if x <= end_x { visuals.hyperlink_color } else { visuals.weak_text_color() };
+        painter.vline(
+            x,
+            (rect.center().y - height)..=(rect.center().y + height),
+            egui::Stroke::new(1.0_f32, color),
+        );
+    }
+
+    let marker = egui::Stroke::new(2.0_f32, visuals.strong_text_color());
+    painter.vline(rect.left() + 1.0, rect.y_range(), marker);
+    painter.vline(end_x.min(rect.right() - 1.0), rect.y_range(), marker);
+
+    if let Some(pointer) = response.hover_pos() {
+        let position = (pointer.x - rect.left()) / rect.width() * waveform.duration as f32;
+        response.on_hover_text_at_pointer(duration_name(position as f64));
+    }
+
+    ui.horizontal(|ui| {
+        let duration = duration_name(waveform.duration);
+        match time {
+            Some(time) if time < waveform.duration => {
+                unselectable_label(format!("{} из {duration}", duration_name(time)), ui)
+            },
+            _ => unselectable_label(duration, ui),
+        };
+        if waveform.is_silent() {
+            ui.colored_label(ui.visuals().warn_fg_color, icon_str!(WARNING, "Аудио без звука"));
+        }
+    });
+}
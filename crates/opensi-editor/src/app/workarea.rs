@@ -1,5 +1,5 @@
 use crate::app::context::{PackageContext, QuestionContext, RoundContext, ThemeContext};
-use crate::app::{package_tab, question_tab, round_tab, theme_tab};
+use crate::app::{package_tab, question_tab, round_tab, selection_tab, theme_tab};
 use crate::element::node_name;
 use crate::icon_string;
 
@@ -24,6 +24,11 @@ pub fn workarea(ctx: &mut PackageContext, ui: &mut egui::Ui) {
 
 /// UI for selected node properties.
 pub fn properties(ctx: &mut PackageContext, ui: &mut egui::Ui) {
+    if ctx.marked().len() > 1 {
+        selection_tab::selection_properties(ctx, ui);
+        return;
+    }
+
     match ctx.selected() {
         Some(PackageNode::Round(idx)) => {
             if let Some(mut ctx) = RoundContext::try_new(ctx, idx) {
@@ -2,8 +2,13 @@ use opensi_core::prelude::*;
 use std::{borrow::Cow, fmt::Debug};
 
 use super::{
-    node_context::PackageNodeContextMenu, question_name, round_name, theme_name, unselectable_label,
+    drag::{DropAxis, node_drag_and_drop},
+    node_context::PackageNodeContextMenu,
+    question_name, round_name,
+    selection::{self, RubberBand},
+    theme_name, unselectable_label,
 };
+use crate::app::question_thumbnail;
 
 /// Rectangular cilckable card for package nodes (and more).
 // TODO: context menu
@@ -11,6 +16,9 @@ use super::{
 pub struct Card<'a> {
     kind: CardKind<'a>,
     style: CardStyle,
+    drop_axis: DropAxis,
+    /// Uri of an image shown next to the content.
+    thumbnail: Option<String>,
 }
 
 /// Types of content of [`Card`].
@@ -131,17 +139,47 @@ impl Card<'_> {
                 .halign(egui::Align::Center)
                 .wrap();
 
-        ui.add(label);
+        match &self.thumbnail {
+            Some(uri) => {
+                ui.horizontal_centered(|ui| {
+                    ui.add(
+                        egui::Image::new(uri.as_str())
+                            .fit_to_exact_size(egui::vec2(card_height - 32.0, card_height - 32.0))
+                            .maintain_aspect_ratio(true)
+                            .corner_radius(4.0),
+                    );
+                    ui.add(label);
+                });
+            },
+            None => {
+                ui.add(label);
+            },
+        }
+    }
+
+    fn package_node(&mut self) -> Option<(&mut Package, PackageNode)> {
+        match &mut self.kind {
+            CardKind::Round(package, round_idx) => Some((package, (*round_idx).into())),
+            CardKind::Theme(package, theme_idx) => Some((package, (*theme_idx).into())),
+            CardKind::Question(package, question_idx) => Some((package, (*question_idx).into())),
+            CardKind::Custom(_) => None,
+        }
     }
 
-    fn context_menu(&mut self, response: &egui::Response, ui: &mut egui::Ui) {
-        let (package, node) = match &mut self.kind {
-            CardKind::Round(package, round_idx) => (package, (*round_idx).into()),
-            CardKind::Theme(package, theme_idx) => (package, (*theme_idx).into()),
-            CardKind::Question(package, question_idx) => (package, (*question_idx).into()),
-            _ => return,
+    fn interact(&mut self, response: &egui::Response, ui: &mut egui::Ui) {
+        let drop_axis = self.drop_axis;
+        let Some((package, node)) = self.package_node() else {
+            return;
         };
 
+        selection::register_node(node, response.rect, ui);
+        if selection::is_marked(node, ui.ctx()) {
+            let stroke = egui::Stroke::new(2.0_f32, ui.visuals().selection.stroke.color);
+            let rect = response.rect - egui::Margin::symmetric(0, 4);
+            ui.painter().rect_stroke(rect, 8, stroke, egui::StrokeKind::Inside);
+        }
+
+        node_drag_and_drop(package, node, response, drop_axis, ui);
         PackageNodeContextMenu { package, node }.show(response, ui);
     }
 }
@@ -176,19 +214,26 @@ impl<'a> egui::Widget for Card<'a> {
         }
         frame.paint(ui);
 
-        let response = ui.allocate_rect(rect, egui::Sense::click());
-        self.context_menu(&response, ui);
+        let sense = if self.package_node().is_some() {
+            egui::Sense::click_and_drag()
+        } else {
+            egui::Sense::click()
+        };
+        let response = ui.allocate_rect(rect, sense);
+        self.interact(&response, ui);
         response
     }
 }
 
 /// Builder for a signle row inside [`CardTable`].
-pub struct CardTableRow<'a, 'b> {
+pub struct CardTableRow<'a, 'b, 'c> {
     strip: egui_extras::Strip<'a, 'b>,
     index: usize,
+    columns: usize,
+    storage_key: Option<&'c str>,
 }
 
-impl CardTableRow<'_, '_> {
+impl CardTableRow<'_, '_, '_> {
     pub fn index(&self) -> usize {
         self.index
     }
@@ -208,7 +253,14 @@ impl CardTableRow<'_, '_> {
         style: CardStyle,
     ) -> egui::Response {
         let idx = idx.into();
-        self.row(|ui| ui.add(Card { kind: CardKind::Round(package, idx), style }))
+        self.row(|ui| {
+            ui.add(Card {
+                kind: CardKind::Round(package, idx),
+                style,
+                drop_axis: DropAxis::Vertical,
+                thumbnail: None,
+            })
+        })
     }
 
     pub fn theme(
@@ -218,7 +270,14 @@ impl CardTableRow<'_, '_> {
         style: CardStyle,
     ) -> egui::Response {
         let idx = idx.into();
-        self.row(|ui| ui.add(Card { kind: CardKind::Theme(package, idx), style }))
+        self.row(|ui| {
+            ui.add(Card {
+                kind: CardKind::Theme(package, idx),
+                style,
+                drop_axis: DropAxis::Vertical,
+                thumbnail: None,
+            })
+        })
     }
 
     pub fn question(
@@ -228,37 +287,67 @@ impl CardTableRow<'_, '_> {
         style: CardStyle,
     ) -> egui::Response {
         let idx = idx.into();
-        self.row(|ui| ui.add(Card { kind: CardKind::Question(package, idx), style }))
+        // questions are laid out in a row after their theme in multi-column tables
+        let drop_axis = if self.columns > 1 { DropAxis::Horizontal } else { DropAxis::Vertical };
+        let thumbnail = self.storage_key.and_then(|key| {
+            package.get_question(idx).and_then(|question| question_thumbnail(key, question))
+        });
+        self.row(|ui| {
+            ui.add(Card {
+                kind: CardKind::Question(package, idx),
+                style,
+                drop_axis,
+                thumbnail: thumbnail.clone(),
+            })
+        })
     }
 
     pub fn custom(&mut self, str: impl AsRef<str>, style: CardStyle) -> egui::Response {
-        self.row(|ui| ui.add(Card { kind: CardKind::Custom(str.as_ref()), style }))
+        self.row(|ui| {
+            ui.add(Card {
+                kind: CardKind::Custom(str.as_ref()),
+                style,
+                drop_axis: DropAxis::Vertical,
+                thumbnail: None,
+            })
+        })
     }
 }
 
 /// Table for building a grid or list of [`Card`]s.
-pub struct CardTable {
+pub struct CardTable<'a> {
     id: egui::Id,
+    storage_key: Option<&'a str>,
 }
 
-impl CardTable {
+impl<'a> CardTable<'a> {
     pub fn new(id: impl std::hash::Hash) -> Self {
         let id = egui::Id::new(id);
-        Self { id }
+        Self { id, storage_key: None }
+    }
+
+    /// Show thumbnails of image questions from the package
+    /// with resources at `storage_key`.
+    pub fn thumbnails(mut self, storage_key: &'a str) -> Self {
+        self.storage_key = Some(storage_key);
+        self
     }
 
+    /// Show the table. Returns nodes picked with a [`RubberBand`], once
+    /// they are picked.
     pub fn show(
         self,
         ui: &mut egui::Ui,
         count: (usize, usize),
         mut builder: impl FnMut(CardTableRow),
-    ) {
+    ) -> Option<Vec<PackageNode>> {
         egui::ScrollArea::both()
             .id_salt(self.id)
             // FIXME: this fixes always present horizontal scroll, but kinda yucky fix
             .min_scrolled_width(ui.available_width() + 1.0)
             // .max_height(ui.max_rect().height())
             .show(ui, |ui| {
+                let band = RubberBand::begin(self.id.with("rubber-band"), ui);
                 ui.with_layout(egui::Layout::top_down_justified(egui::Align::Center), |ui| {
                     egui_extras::StripBuilder::new(ui)
                         .sizes(egui_extras::Size::initial(80.0), count.1)
@@ -271,7 +360,12 @@ impl CardTable {
                                             egui::Direction::LeftToRight,
                                         ))
                                         .horizontal(|strip| {
-                                            let row = CardTableRow { strip, index: row };
+                                            let row = CardTableRow {
+                                                strip,
+                                                index: row,
+                                                columns: count.0,
+                                                storage_key: self.storage_key,
+                                            };
                                             builder(row);
                                         });
                                 });
@@ -279,6 +373,8 @@ impl CardTable {
                         });
                     ui.add_space(10.0);
                 });
-            });
+                band.end(ui)
+            })
+            .inner
     }
 }
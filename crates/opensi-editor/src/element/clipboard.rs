@@ -0,0 +1,54 @@
+use opensi_core::prelude::*;
+
+/// Id of the node, which was chosen as a target for the next paste.
+fn paste_target_id() -> egui::Id {
+    egui::Id::new("clipboard-paste-target")
+}
+
+/// Copy nodes with their resources into the system clipboard.
+pub fn copy_nodes(package: &Package, nodes: &[PackageNode], ctx: &egui::Context) {
+    let fragment = package.copy_fragment(nodes);
+    if fragment.is_empty() {
+        return;
+    }
+    match fragment.to_xml_string() {
+        Ok(xml) => ctx.copy_text(xml),
+        Err(err) => log::error!("Unable to copy nodes: {err}"),
+    }
+}
+
+/// Copy nodes into the system clipboard and remove them from package.
+pub fn cut_nodes(package: &mut Package, nodes: &[PackageNode], ctx: &egui::Context) {
+    copy_nodes(package, nodes, ctx);
+    package.remove_nodes(nodes);
+}
+
+/// Ask for the clipboard contents to be pasted relative to the `target`.
+/// The contents arrive with [`egui::Event::Paste`] on the next frame.
+pub fn request_paste(target: PackageNode, ctx: &egui::Context) {
+    ctx.data_mut(|data| data.insert_temp(paste_target_id(), target));
+    ctx.send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+}
+
+/// Take the target chosen with [`request_paste`].
+pub fn take_paste_target(ctx: &egui::Context) -> Option<PackageNode> {
+    ctx.data_mut(|data| {
+        let target = data.get_temp::<PackageNode>(paste_target_id());
+        data.remove::<PackageNode>(paste_target_id());
+        target
+    })
+}
+
+/// Parse nodes from the clipboard text, if it contains any.
+pub fn parse_fragment(text: &str) -> Option<Fragment> {
+    if !text.trim_start().starts_with("<opensi-fragment") {
+        return None;
+    }
+    match Fragment::from_xml_str(text) {
+        Ok(fragment) => (!fragment.is_empty()).then_some(fragment),
+        Err(err) => {
+            log::error!("Unable to paste nodes: {err}");
+            None
+        },
+    }
+}
@@ -0,0 +1,143 @@
+use opensi_core::prelude::*;
+
+use super::{node_name, selection};
+
+/// Direction in which dragged nodes are placed around the target.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DropAxis {
+    /// Nodes are placed above or below the target.
+    #[default]
+    Vertical,
+    /// Nodes are placed to the left or to the right of the target.
+    Horizontal,
+}
+
+/// Make `response` a source and a target for dragged [`PackageNode`]s.
+///
+/// Dragged node can be dropped before or after a node of the same kind,
+/// or into the end of a parent node. If the dragged node is marked, all
+/// marked nodes of its kind are moved together. Returns a new position of
+/// the (first) node which was dropped onto `response`.
+pub fn node_drag_and_drop(
+    package: &mut Package,
+    node: PackageNode,
+    response: &egui::Response,
+    axis: DropAxis,
+    ui: &egui::Ui,
+) -> Option<PackageNode> {
+    response.dnd_set_drag_payload(node);
+    if response.dragged() {
+        egui::show_tooltip_at_pointer(ui.ctx(), ui.layer_id(), response.id.with("drag"), |ui| {
+            ui.label(node_name(node, package));
+        });
+    }
+
+    let dragged = *response.dnd_hover_payload::<PackageNode>()?;
+    let pointer = ui.ctx().pointer_hover_pos()?;
+    let rect = response.rect;
+    let after = match axis {
+        DropAxis::Vertical => pointer.y > rect.center().y,
+        DropAxis::Horizontal => pointer.x > rect.center().x,
+    };
+    let group = selection::marked(ui.ctx())
+        .into_iter()
+        .filter(|marked| std::mem::discriminant(marked) == std::mem::discriminant(&dragged))
+        .collect::<Vec<_>>();
+    let is_group = group.len() > 1 && group.contains(&dragged);
+    let to = if is_group {
+        insertion_point(package, dragged, node, after)?
+    } else {
+        drop_position(package, dragged, node, after)?
+    };
+
+    let stroke = egui::Stroke::new(2.0_f32, ui.visuals().selection.stroke.color);
+    if std::mem::discriminant(&dragged) == std::mem::discriminant(&node) {
+        let rect = rect.expand(2.0);
+        let line = match (axis, after) {
+            (DropAxis::Vertical, false) => [rect.left_top(), rect.right_top()],
+            (DropAxis::Vertical, true) => [rect.left_bottom(), rect.right_bottom()],
+            (DropAxis::Horizontal, false) => [rect.left_top(), rect.left_bottom()],
+            (DropAxis::Horizontal, true) => [rect.right_top(), rect.right_bottom()],
+        };
+        ui.painter().line_segment(line, stroke);
+    } else {
+        ui.painter().rect_stroke(rect, 4, stroke, egui::StrokeKind::Outside);
+    }
+
+    // payload is not taken, so that the move is still recognized
+    // as a structure edit until the end of the frame
+    if ui.input(|input| input.pointer.any_released()) {
+        if is_group {
+            return package.move_nodes(&group, to).first().copied();
+        }
+        return package.move_node(dragged, to);
+    }
+    None
+}
+
+/// Position in front of which nodes of the same kind as `dragged` are
+/// placed, when they are dropped before or after the `target`.
+fn insertion_point(
+    package: &Package,
+    dragged: PackageNode,
+    target: PackageNode,
+    after: bool,
+) -> Option<PackageNode> {
+    let position: PackageNode = match (dragged, target) {
+        (PackageNode::Round(_), PackageNode::Round(to)) => {
+            RoundIdx::from(*to + usize::from(after)).into()
+        },
+        (PackageNode::Theme(_), PackageNode::Theme(to)) => {
+            to.parent().theme(*to + usize::from(after)).into()
+        },
+        (PackageNode::Question(_), PackageNode::Question(to)) => {
+            to.parent().question(*to + usize::from(after)).into()
+        },
+        (PackageNode::Theme(_), PackageNode::Round(to)) => {
+            to.theme(package.count_themes(to)).into()
+        },
+        (PackageNode::Question(_), PackageNode::Theme(to)) => {
+            to.question(package.count_questions(to)).into()
+        },
+        _ => return None,
+    };
+    Some(position)
+}
+
+/// Position for a `dragged` node when it's dropped before or after the `target`.
+fn drop_position(
+    package: &Package,
+    dragged: PackageNode,
+    target: PackageNode,
+    after: bool,
+) -> Option<PackageNode> {
+    fn insert_index(from: usize, to: usize, after: bool, same_parent: bool) -> usize {
+        let index = if after { to + 1 } else { to };
+        if same_parent && from < index { index - 1 } else { index }
+    }
+
+    let position: PackageNode = match (dragged, target) {
+        (PackageNode::Round(from), PackageNode::Round(to)) => {
+            RoundIdx::from(insert_index(*from, *to, after, true)).into()
+        },
+        (PackageNode::Theme(from), PackageNode::Theme(to)) => {
+            let same_parent = from.parent() == to.parent();
+            to.parent().theme(insert_index(*from, *to, after, same_parent)).into()
+        },
+        (PackageNode::Question(from), PackageNode::Question(to)) => {
+            let same_parent = from.parent() == to.parent();
+            to.parent().question(insert_index(*from, *to, after, same_parent)).into()
+        },
+        (PackageNode::Theme(from), PackageNode::Round(to)) => {
+            let same_parent = from.parent() == to;
+            to.theme(package.count_themes(to) - usize::from(same_parent)).into()
+        },
+        (PackageNode::Question(from), PackageNode::Theme(to)) => {
+            let same_parent = from.parent() == to;
+            to.question(package.count_questions(to) - usize::from(same_parent)).into()
+        },
+        _ => return None,
+    };
+
+    (position != dragged).then_some(position)
+}
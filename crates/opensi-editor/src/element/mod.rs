@@ -1,10 +1,13 @@
 pub mod card;
+pub mod clipboard;
 pub mod common;
+pub mod drag;
 pub mod modal;
 pub mod naming;
 pub mod node_context;
 pub mod property;
 pub mod section;
+pub mod selection;
 
 pub use common::*;
 pub use modal::{ModalExt, ModalWrapper};
@@ -1,11 +1,11 @@
 use opensi_core::prelude::*;
 use std::borrow::Cow;
 
-use crate::{icon_format, icon_string};
+use crate::{icon, icon_format, icon_string};
 
-const UNKNOWN_ROUND: &'static str = "<Неизвестный раунд>";
-const UNKNOWN_THEME: &'static str = "<Неизвестная тема>";
-const UNKNOWN_QUESTION: &'static str = "<Неизвестный вопрос>";
+const UNKNOWN_ROUND: &str = "<Неизвестный раунд>";
+const UNKNOWN_THEME: &str = "<Неизвестная тема>";
+const UNKNOWN_QUESTION: &str = "<Неизвестный вопрос>";
 
 /// Utility method to get a pretty name for a [`PackageNode`].
 pub fn node_name<'a>(node: PackageNode, package: &'a Package) -> Cow<'a, str> {
@@ -35,3 +35,86 @@ pub fn theme_name(theme: &Theme) -> String {
 pub fn question_name(question: &Question) -> String {
     icon_format!(NOTE, "({})", question.price)
 }
+
+pub fn atom_kind_icon(kind: AtomKind) -> &'static str {
+    match kind {
+        AtomKind::Image => icon!(IMAGE),
+        AtomKind::Voice => icon!(HEADPHONES),
+        AtomKind::Video => icon!(VIDEO),
+        AtomKind::Text => icon!(CHAT_CIRCLE_TEXT),
+    }
+}
+
+pub fn atom_kind_name(kind: AtomKind) -> String {
+    let name = match kind {
+        AtomKind::Image => "Изображение",
+        AtomKind::Voice => "Аудио",
+        AtomKind::Video => "Видео",
+        AtomKind::Text => "Текст",
+    };
+    format!("{} {}", atom_kind_icon(kind), name)
+}
+
+/// Human readable size of a file, e.g. "1.5 МБ".
+pub fn size_name(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["Б", "КБ", "МБ", "ГБ"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{bytes} {}", UNITS[0]) } else { format!("{size:.1} {}", UNITS[unit]) }
+}
+
+/// Human readable duration, e.g. "3:05" or "1:02:03".
+pub fn duration_name(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// Codecs, duration and bitrate of a media resource,
+/// e.g. "H.264, AAC · 1:30 · 2400 кбит/с".
+pub fn media_probe_text(probe: &MediaProbe) -> String {
+    let codecs = probe.codecs.iter().map(Codec::name).collect::<Vec<_>>().join(", ");
+    let duration = probe.duration.map(duration_name);
+    let bitrate = probe.bitrate.map(|bitrate| format!("{} кбит/с", bitrate / 1000));
+    [Some(codecs).filter(|codecs| !codecs.is_empty()), duration, bitrate]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Explanation of a resource which content doesn't fit its name.
+pub fn format_mismatch_text(id: &ResourceId, mismatch: FormatMismatch) -> String {
+    match mismatch {
+        FormatMismatch::Unknown => "Неизвестный формат файла".to_string(),
+        FormatMismatch::WrongKind(format) => format!(
+            "Ожидалось {}, а файл содержит {} {} ({})",
+            atom_kind_label(id.atom_kind()),
+            atom_kind_label(format.atom_kind()),
+            format.name(),
+            format.mime(),
+        ),
+        FormatMismatch::WrongExtension(format) => format!(
+            "Расширение не соответствует содержимому: {} ({})",
+            format.name(),
+            format.mime(),
+        ),
+    }
+}
+
+fn atom_kind_label(kind: AtomKind) -> &'static str {
+    match kind {
+        AtomKind::Image => "изображение",
+        AtomKind::Voice => "аудио",
+        AtomKind::Video => "видео",
+        AtomKind::Text => "текст",
+    }
+}
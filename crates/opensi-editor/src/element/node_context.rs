@@ -2,7 +2,7 @@ use opensi_core::prelude::*;
 
 use crate::icon_str;
 
-use super::{ModalExt, ModalWrapper, danger_button};
+use super::{ModalExt, ModalWrapper, clipboard, danger_button};
 
 /// Context menu for [`PackageNode`].
 pub struct PackageNodeContextMenu<'p> {
@@ -63,6 +63,19 @@ impl PackageNodeContextMenu<'_> {
                 ui.close_menu();
             }
             ui.separator();
+            if ui.button(icon_str!(CLIPBOARD_TEXT, "Копировать")).clicked() {
+                clipboard::copy_nodes(self.package, &[self.node], ui.ctx());
+                ui.close_menu();
+            }
+            if ui.button(icon_str!(SCISSORS, "Вырезать")).clicked() {
+                clipboard::cut_nodes(self.package, &[self.node], ui.ctx());
+                ui.close_menu();
+            }
+            if ui.button(icon_str!(CLIPBOARD, "Вставить")).clicked() {
+                clipboard::request_paste(self.node, ui.ctx());
+                ui.close_menu();
+            }
+            ui.separator();
             if danger_button(icon_str!(TRASH, "Удалить"), ui).clicked() {
                 self.package.remove_node(self.node);
                 ui.close_menu();
@@ -91,7 +104,7 @@ impl PackageNodeContextMenu<'_> {
 
                 if response.changed() {
                     if is_question {
-                        new_value.retain(|c| c.is_digit(10));
+                        new_value.retain(|c| c.is_ascii_digit());
                     }
                     ui.memory_mut(|memory| memory.data.insert_temp(new_value_id, new_value));
                 }
@@ -0,0 +1,105 @@
+use opensi_core::prelude::*;
+
+/// Id of the nodes marked for bulk operations.
+fn marked_id() -> egui::Id {
+    egui::Id::new("selection-marked-nodes")
+}
+
+/// Id of the node rects laid out during the current frame.
+fn node_rects_id() -> egui::Id {
+    egui::Id::new("selection-node-rects")
+}
+
+/// Share nodes marked for bulk operations with widgets for the current frame.
+pub fn set_marked(nodes: Vec<PackageNode>, ctx: &egui::Context) {
+    ctx.data_mut(|data| data.insert_temp(marked_id(), nodes));
+}
+
+/// Nodes marked for bulk operations.
+pub fn marked(ctx: &egui::Context) -> Vec<PackageNode> {
+    ctx.data(|data| data.get_temp(marked_id())).unwrap_or_default()
+}
+
+pub fn is_marked(node: PackageNode, ctx: &egui::Context) -> bool {
+    ctx.data(|data| {
+        data.get_temp::<Vec<PackageNode>>(marked_id()).is_some_and(|marked| marked.contains(&node))
+    })
+}
+
+/// Remember where a node was laid out, so that it can be
+/// picked with a [`RubberBand`].
+pub fn register_node(node: PackageNode, rect: egui::Rect, ui: &egui::Ui) {
+    let rect = rect.intersect(ui.clip_rect());
+    if !rect.is_positive() {
+        return;
+    }
+
+    let frame = ui.ctx().cumulative_pass_nr();
+    ui.data_mut(|data| {
+        let rects =
+            data.get_temp_mut_or_default::<(u64, Vec<(PackageNode, egui::Rect)>)>(node_rects_id());
+        if rects.0 != frame {
+            *rects = (frame, vec![]);
+        }
+        rects.1.push((node, rect));
+    });
+}
+
+/// Rectangular selection of nodes by dragging over the empty space.
+pub struct RubberBand {
+    response: egui::Response,
+}
+
+impl RubberBand {
+    /// Start the band over the visible part of `ui`. It must be started before
+    /// any nodes are added, so that the nodes are interacted with first.
+    pub fn begin(id: impl Into<egui::Id>, ui: &mut egui::Ui) -> Self {
+        let response = ui.interact(ui.clip_rect(), id.into(), egui::Sense::drag());
+        Self { response }
+    }
+
+    /// Finish the band after all nodes are added. Returns nodes inside of
+    /// the band, once the drag is over.
+    pub fn end(self, ui: &egui::Ui) -> Option<Vec<PackageNode>> {
+        let origin_id = self.response.id.with("origin");
+        if self.response.drag_started() {
+            let origin = ui.input(|input| input.pointer.press_origin());
+            ui.data_mut(|data| data.insert_temp(origin_id, origin));
+        }
+        if !self.response.dragged() && !self.response.drag_stopped() {
+            return None;
+        }
+
+        let origin = ui.data(|data| data.get_temp::<Option<egui::Pos2>>(origin_id)).flatten()?;
+        let pointer = ui.input(|input| input.pointer.interact_pos())?;
+        let band = egui::Rect::from_two_pos(origin, pointer);
+
+        let selection = ui.visuals().selection;
+        ui.painter().rect(
+            band,
+            2,
+            selection.bg_fill.gamma_multiply(0.3),
+            selection.stroke,
+            egui::StrokeKind::Inside,
+        );
+
+        if !self.response.drag_stopped() {
+            return None;
+        }
+
+        let frame = ui.ctx().cumulative_pass_nr();
+        let rects = ui
+            .data(|data| data.get_temp::<(u64, Vec<(PackageNode, egui::Rect)>)>(node_rects_id()))
+            .filter(|rects| rects.0 == frame)
+            .map(|rects| rects.1)
+            .unwrap_or_default();
+        let mut nodes = rects
+            .into_iter()
+            .filter(|(_, rect)| rect.intersects(band))
+            .map(|(node, _)| node)
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        Some(nodes)
+    }
+}
@@ -76,10 +76,10 @@ impl ColorScheme {
             visuals.code_bg_color = self.base_strong;
 
             visuals.selection.bg_fill = accent_bg;
-            visuals.selection.stroke = egui::Stroke::new(1.0, self.accent);
+            visuals.selection.stroke = egui::Stroke::new(1.0_f32, self.accent);
 
             visuals.text_cursor = egui::style::TextCursorStyle {
-                stroke: egui::Stroke::new(2.0, self.accent),
+                stroke: egui::Stroke::new(2.0_f32, self.accent),
                 preview: false,
                 blink: true,
                 on_duration: 0.66,
@@ -89,36 +89,36 @@ impl ColorScheme {
             visuals.widgets.noninteractive = egui::style::WidgetVisuals {
                 bg_fill: self.base,
                 weak_bg_fill: self.base_weak,
-                bg_stroke: egui::Stroke::new(0.0, egui::Color32::TRANSPARENT),
-                fg_stroke: egui::Stroke::new(1.0, self.text_weak),
+                bg_stroke: egui::Stroke::new(0.0_f32, egui::Color32::TRANSPARENT),
+                fg_stroke: egui::Stroke::new(1.0_f32, self.text_weak),
                 corner_radius: egui::CornerRadius::same(4),
                 expansion: 0.0,
             };
             visuals.widgets.inactive = egui::style::WidgetVisuals {
                 bg_fill: self.base_alt,
                 weak_bg_fill: self.base_strong,
-                bg_stroke: egui::Stroke::new(0.0, egui::Color32::TRANSPARENT),
-                fg_stroke: egui::Stroke::new(1.0, self.text),
+                bg_stroke: egui::Stroke::new(0.0_f32, egui::Color32::TRANSPARENT),
+                fg_stroke: egui::Stroke::new(1.0_f32, self.text),
                 corner_radius: egui::CornerRadius::same(4),
                 expansion: 0.0,
             };
             visuals.widgets.hovered = egui::style::WidgetVisuals {
                 bg_fill: accent_bg,
                 weak_bg_fill: accent_bg_weak,
-                bg_stroke: egui::Stroke::new(1.0, self.accent),
-                fg_stroke: egui::Stroke::new(1.0, self.accent),
+                bg_stroke: egui::Stroke::new(1.0_f32, self.accent),
+                fg_stroke: egui::Stroke::new(1.0_f32, self.accent),
                 corner_radius: egui::CornerRadius::same(4),
                 expansion: 0.0,
             };
             visuals.widgets.active = egui::style::WidgetVisuals {
                 bg_fill: self.base_alt,
                 weak_bg_fill: self.base_strong,
-                bg_stroke: egui::Stroke::new(0.0, self.text_strong),
-                fg_stroke: egui::Stroke::new(1.0, self.text_weak),
+                bg_stroke: egui::Stroke::new(0.0_f32, self.text_strong),
+                fg_stroke: egui::Stroke::new(1.0_f32, self.text_weak),
                 corner_radius: egui::CornerRadius::same(4),
                 expansion: 0.0,
             };
-            visuals.widgets.open = visuals.widgets.active.clone();
+            visuals.widgets.open = visuals.widgets.active;
 
             visuals.window_shadow = egui::Shadow {
                 offset: [0, 5],
@@ -126,7 +126,7 @@ impl ColorScheme {
                 spread: 0,
                 color: egui::Color32::from_black_alpha(80),
             };
-            visuals.popup_shadow = visuals.window_shadow.clone();
+            visuals.popup_shadow = visuals.window_shadow;
 
             visuals.dark_mode = self.dark;
         });